@@ -0,0 +1,245 @@
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+// The 16 standard colours, as VS Code's terminal draws them
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+enum Segment<'a> {
+    Text(&'a str),
+    // Parameters of a `CSI ... m` sequence, e.g. "1;31"
+    Sgr(&'a str),
+}
+
+// Splits `pending` + `chunk` into text and SGR sequences, dropping every other escape sequence.
+// A sequence cut off at the end of the chunk is kept in `pending` for the next call.
+fn split_escapes(pending: &mut String, chunk: &str, mut segment: impl FnMut(Segment)) {
+    pending.push_str(chunk);
+    let input = std::mem::take(pending);
+    let mut rest = input.as_str();
+
+    while let Some(at) = rest.find(ESC) {
+        if at > 0 {
+            segment(Segment::Text(&rest[..at]));
+        }
+        let sequence = &rest[at + 1..];
+        let consumed = match sequence.chars().next() {
+            None => None,
+            Some('[') => sequence[1..]
+                .find(|c: char| ('\x40'..='\x7e').contains(&c))
+                .map(|end| {
+                    if sequence[1 + end..].starts_with('m') {
+                        segment(Segment::Sgr(&sequence[1..1 + end]));
+                    }
+                    end + 2
+                }),
+            // OSC (window titles, hyperlinks) ends with BEL or ESC \
+            Some(']') => sequence.find([BEL, ESC]).and_then(|end| match sequence[end..].chars().next() {
+                Some(BEL) => Some(end + 1),
+                _ => sequence[end + 1..].chars().next().map(|_| end + 2),
+            }),
+            Some(c) => Some(c.len_utf8()),
+        };
+        match consumed {
+            Some(len) => rest = &sequence[len..],
+            None => {
+                *pending = rest[at..].to_string();
+                return;
+            }
+        }
+    }
+    if !rest.is_empty() {
+        segment(Segment::Text(rest));
+    }
+}
+
+/// Removes ANSI escape sequences from streamed output
+#[derive(Debug, Default)]
+pub struct AnsiStripper {
+    pending: String,
+}
+
+impl AnsiStripper {
+    pub fn push(&mut self, chunk: &str) -> String {
+        let mut text = String::with_capacity(chunk.len());
+        split_escapes(&mut self.pending, chunk, |segment| {
+            if let Segment::Text(t) = segment {
+                text.push_str(t);
+            }
+        });
+        text
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+fn indexed_color(index: u64) -> Option<String> {
+    match index {
+        0..=15 => Some(PALETTE[index as usize].to_string()),
+        // 6x6x6 colour cube
+        16..=231 => {
+            let level = |n: u64| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = index - 16;
+            Some(format!("#{:02x}{:02x}{:02x}", level(n / 36), level(n / 6 % 6), level(n % 6)))
+        }
+        232..=255 => {
+            let gray = 8 + (index - 232) * 10;
+            Some(format!("#{:02x}{:02x}{:02x}", gray, gray, gray))
+        }
+        _ => None,
+    }
+}
+
+// The colour of a `38;5;n` / `38;2;r;g;b` sequence, consuming its parameters
+fn extended_color(params: &mut impl Iterator<Item = u64>) -> Option<String> {
+    match params.next()? {
+        5 => indexed_color(params.next()?),
+        2 => {
+            let (r, g, b) = (params.next()?, params.next()?, params.next()?);
+            Some(format!("#{:02x}{:02x}{:02x}", r.min(255), g.min(255), b.min(255)))
+        }
+        _ => None,
+    }
+}
+
+impl Style {
+    fn apply(&mut self, sgr: &str) {
+        // An empty parameter means 0, so `ESC[m` resets
+        let mut params = sgr.split(';').map(|p| p.parse::<u64>().unwrap_or(0));
+        while let Some(code) = params.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = indexed_color(code - 30),
+                90..=97 => self.fg = indexed_color(code - 90 + 8),
+                38 => self.fg = extended_color(&mut params),
+                39 => self.fg = None,
+                40..=47 => self.bg = indexed_color(code - 40),
+                100..=107 => self.bg = indexed_color(code - 100 + 8),
+                48 => self.bg = extended_color(&mut params),
+                49 => self.bg = None,
+                _ => {}
+            }
+        }
+    }
+
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if let Some(fg) = &self.fg {
+            css.push(format!("color:{}", fg));
+        }
+        if let Some(bg) = &self.bg {
+            css.push(format!("background-color:{}", bg));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.7".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+}
+
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+/// Converts streamed output with SGR colour codes to HTML-escaped text in styled `<span>`s.
+/// Styles carry across chunks; a span left open at the end of a chunk is closed and reopened
+/// in the next one so every chunk is well-formed on its own.
+#[derive(Debug, Default)]
+pub struct AnsiToHtml {
+    pending: String,
+    style: Style,
+}
+
+impl AnsiToHtml {
+    pub fn push(&mut self, chunk: &str) -> String {
+        let mut html = String::with_capacity(chunk.len());
+        let mut open: Option<Style> = None;
+        let style = &mut self.style;
+        split_escapes(&mut self.pending, chunk, |segment| match segment {
+            Segment::Sgr(params) => style.apply(params),
+            Segment::Text(text) => {
+                if open.as_ref() != Some(&*style) {
+                    if open.take().is_some() {
+                        html.push_str("</span>");
+                    }
+                    if *style != Style::default() {
+                        html.push_str(&format!("<span style=\"{}\">", style.css()));
+                        open = Some(style.clone());
+                    }
+                }
+                push_escaped(&mut html, text);
+            }
+        });
+        if open.is_some() {
+            html.push_str("</span>");
+        }
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sgr_codes_become_styled_spans() {
+        let mut converter = AnsiToHtml::default();
+        let html = converter.push("\x1b[1;31merror\x1b[0m: <file> \x1b[38;5;33mblue\x1b[39m plain");
+        assert_eq!(
+            html,
+            "<span style=\"color:#cd3131;font-weight:bold\">error</span>: &lt;file&gt; \
+             <span style=\"color:#0087ff\">blue</span> plain"
+        );
+    }
+
+    #[test]
+    fn styles_and_split_sequences_carry_across_chunks() {
+        let mut converter = AnsiToHtml::default();
+        assert_eq!(converter.push("ok \x1b[3"), "ok ");
+        assert_eq!(converter.push("2mgreen"), "<span style=\"color:#0dbc79\">green</span>");
+        assert_eq!(converter.push(" still\x1b[m done"), "<span style=\"color:#0dbc79\"> still</span> done");
+    }
+
+    #[test]
+    fn stripper_drops_all_escape_sequences() {
+        let mut stripper = AnsiStripper::default();
+        let text = stripper.push("\x1b]0;title\x07\x1b[2K\x1b[1;32mdone\x1b[0m\x1b");
+        assert_eq!(text, "done");
+        assert_eq!(stripper.push("[0m!"), "!");
+    }
+}
@@ -0,0 +1,1581 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use crate::error::CommandError;
+use crate::output_limit::collect_output;
+use crate::preferences::{Preferences, PreferencesState};
+use crate::recording::RecordingState;
+use crate::timing::TimingHistograms;
+
+/// Parsed result of an `az` invocation
+#[derive(Debug, Serialize)]
+pub struct AzOutput {
+    // `null` when az printed nothing, e.g. for delete commands
+    pub result: serde_json::Value,
+    // Size of the raw output saved to `output_file`, when one was requested and written
+    pub bytes_written: Option<u64>,
+    // Where `output_file` resolved to, so the UI can reveal it
+    pub output_path: Option<String>,
+    // Set when the parsed result is fine but saving the raw output failed
+    pub warning: Option<String>,
+    // Notices az printed to stdout ahead of the JSON, e.g. that an update is available
+    pub stdout_warning: Option<String>,
+}
+
+fn chooses_output(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "--output" || arg == "-o" || arg.starts_with("--output="))
+}
+
+// Ask for JSON unless the caller already picked an output format
+fn with_json_output(mut args: Vec<String>) -> Vec<String> {
+    if !chooses_output(&args) {
+        args.push("--output".to_string());
+        args.push("json".to_string());
+    }
+    args
+}
+
+fn starts_json(line: &[u8]) -> bool {
+    let start = line.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(line.len());
+    let line = &line[start..];
+    match line.first() {
+        Some(b'{' | b'[' | b'"' | b'-' | b'0'..=b'9') => true,
+        _ => line.starts_with(b"true") || line.starts_with(b"false") || line.starts_with(b"null"),
+    }
+}
+
+// az sometimes prints notices (e.g. "WARNING: You have 2 update(s) available") to stdout ahead
+// of the JSON. Splits the lines before the first one that can start JSON off the payload and
+// returns them as the warning text. Output without any such line comes back whole, so a parse
+// error still shows what az printed.
+pub(crate) fn split_az_json(stdout: &[u8]) -> (&[u8], Option<String>) {
+    let mut start = 0;
+    for line in stdout.split_inclusive(|&b| b == b'\n') {
+        if starts_json(line) {
+            let warning = String::from_utf8_lossy(&stdout[..start]).trim().to_string();
+            return (&stdout[start..], (!warning.is_empty()).then_some(warning));
+        }
+        start += line.len();
+    }
+    (stdout, None)
+}
+
+fn write_raw_output(path: &Path, stdout: &[u8]) -> Result<u64, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, stdout).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(stdout.len() as u64)
+}
+
+// Parses az's stdout and, if asked, saves the raw bytes too. A failed save doesn't cost the
+// caller the parsed result; it comes back as a warning instead.
+fn capture_az_output(stdout: &[u8], output_file: Option<&Path>) -> Result<AzOutput, String> {
+    let (json, stdout_warning) = split_az_json(stdout);
+    let text = String::from_utf8_lossy(json);
+    let result = if text.trim().is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(&text).map_err(|e| format!("Failed to parse az output as JSON: {}", e))?
+    };
+
+    let (bytes_written, warning) = match output_file.map(|path| write_raw_output(path, stdout)) {
+        Some(Ok(bytes)) => (Some(bytes), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+
+    Ok(AzOutput {
+        result,
+        bytes_written,
+        output_path: output_file.map(|path| path.display().to_string()),
+        warning,
+        stdout_warning,
+    })
+}
+
+// Runs `az` with JSON output and returns the parsed result. `output_file` also saves the raw
+// stdout there (a relative path lands under `workspace_dir`), so archiving a result doesn't
+// need a second invocation. The `az` entry in
+// `default_args` goes first unless `skip_defaults` is set. `azure_config_dir` overrides
+// AZURE_CONFIG_DIR for this call (created first with `create_config_dir`). With
+// `use_managed_identity`, a config dir without a login falls back to the machine's managed
+// identity, signed in under a separate `<config dir>-managed-identity` profile so the user's own
+// accounts are left alone.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_az(
+    prefs: tauri::State<'_, PreferencesState>,
+    recording: tauri::State<'_, RecordingState>,
+    timing: tauri::State<'_, TimingHistograms>,
+    args: Vec<String>,
+    output_file: Option<String>,
+    skip_defaults: Option<bool>,
+    azure_config_dir: Option<String>,
+    create_config_dir: Option<bool>,
+    use_managed_identity: Option<bool>,
+) -> Result<AzOutput, CommandError> {
+    let prefs = prefs.get();
+    let args = with_json_output(prefs.args_for("az", args, skip_defaults.unwrap_or(false)));
+    let tool_info = crate::locate_tool("az".to_string(), &prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure CLI not available".to_string()).into());
+    }
+    let az_path = tool_info.path.unwrap();
+
+    let mut env = crate::azure_env_with_config_dir(azure_config_dir.as_deref(), create_config_dir.unwrap_or(false))?;
+    if use_managed_identity.unwrap_or(false) {
+        crate::managed_identity::ensure_login(&az_path, &mut env).await?;
+    }
+    let output_file = output_file.map(|path| prefs.resolve_output_path(&path)).transpose()?;
+    let max_buffered_bytes = prefs.max_buffered_output_bytes;
+    let run_args = args.clone();
+    let started = std::time::Instant::now();
+    let result = tokio::task::spawn_blocking(move || {
+        execute_az(&az_path, &run_args, env, output_file.as_deref(), max_buffered_bytes)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("az query task failed: {}", e).into()));
+    timing.record("az", started.elapsed());
+    recording.record("run_az", &args, &result);
+    result
+}
+
+fn execute_az(
+    az_path: &str,
+    args: &[String],
+    env: HashMap<String, String>,
+    output_file: Option<&Path>,
+    max_buffered_bytes: u64,
+) -> Result<AzOutput, CommandError> {
+    let child = Command::new(az_path)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute az: {}", e))?;
+    let output = collect_output(child, max_buffered_bytes)?;
+
+    if !output.status.success() {
+        return Err(az_failure(&output));
+    }
+
+    Ok(capture_az_output(&output.stdout, output_file)?)
+}
+
+/// Outcome of one query in a batch; exactly one of the fields is set
+#[derive(Debug, Serialize)]
+pub struct AzBatchResult {
+    pub output: Option<AzOutput>,
+    pub error: Option<CommandError>,
+}
+
+impl From<Result<AzOutput, CommandError>> for AzBatchResult {
+    fn from(result: Result<AzOutput, CommandError>) -> Self {
+        match result {
+            Ok(output) => AzBatchResult { output: Some(output), error: None },
+            Err(error) => AzBatchResult { output: None, error: Some(error) },
+        }
+    }
+}
+
+// Runs every query against the same az binary and env, at most `max_concurrency` at a time,
+// returning results in input order
+async fn run_batch(
+    az_path: &str,
+    queries: Vec<Vec<String>>,
+    env: HashMap<String, String>,
+    max_concurrency: usize,
+    max_buffered_bytes: u64,
+) -> Vec<AzBatchResult> {
+    let permits = Arc::new(Semaphore::new(max_concurrency.max(1)));
+    let handles: Vec<_> = queries
+        .into_iter()
+        .map(|args| {
+            let permits = permits.clone();
+            let az_path = az_path.to_string();
+            let env = env.clone();
+            tokio::spawn(async move {
+                let _permit = permits.acquire_owned().await.map_err(|e| e.to_string())?;
+                tokio::task::spawn_blocking(move || execute_az(&az_path, &args, env, None, max_buffered_bytes))
+                    .await
+                    .map_err(|e| format!("az query task failed: {}", e))?
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        let result = handle.await.unwrap_or_else(|e| Err(format!("az query task failed: {}", e).into()));
+        results.push(result.into());
+    }
+    results
+}
+
+// Runs several az queries concurrently (capped by `max_concurrency`, or the preference when 0)
+// for dashboards that aggregate them. Each query gets JSON output and the `az` default args;
+// a failing query only fails its own entry.
+#[tauri::command]
+pub async fn run_az_batch(
+    prefs: tauri::State<'_, PreferencesState>,
+    queries: Vec<Vec<String>>,
+    max_concurrency: usize,
+) -> Result<Vec<AzBatchResult>, String> {
+    let prefs = prefs.get();
+    let tool_info = crate::locate_tool("az".to_string(), &prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure CLI not available".to_string()));
+    }
+    let az_path = tool_info.path.unwrap();
+
+    let queries = queries
+        .into_iter()
+        .map(|args| with_json_output(prefs.args_for("az", args, false)))
+        .collect();
+    let max_concurrency = if max_concurrency == 0 { prefs.max_concurrency } else { max_concurrency };
+    Ok(run_batch(
+        &az_path,
+        queries,
+        crate::azure_tool_env(),
+        max_concurrency,
+        prefs.max_buffered_output_bytes,
+    )
+    .await)
+}
+
+/// An Azure region as shown in location pickers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AzureLocation {
+    pub name: String,
+    pub display_name: String,
+    // e.g. "United States"; missing for logical locations
+    pub geography: Option<String>,
+}
+
+// Shape of one entry in `az account list-locations` output
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLocation {
+    name: String,
+    display_name: String,
+    #[serde(default)]
+    metadata: Option<RawLocationMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawLocationMetadata {
+    #[serde(default)]
+    geography: Option<String>,
+}
+
+fn parse_locations(stdout: &[u8]) -> Result<Vec<AzureLocation>, String> {
+    let raw: Vec<RawLocation> = parse_az_json(stdout, "locations")?;
+    Ok(raw
+        .into_iter()
+        .map(|location| AzureLocation {
+            name: location.name,
+            display_name: location.display_name,
+            geography: location.metadata.and_then(|metadata| metadata.geography),
+        })
+        .collect())
+}
+
+// az's wording when there's no usable login
+pub(crate) fn is_auth_error(stderr: &str) -> bool {
+    stderr.contains("az login") || stderr.contains("DefaultAzureCredential")
+}
+
+/// Regions from the last successful `list_azure_locations` for the current account. Kept in managed state.
+#[derive(Default)]
+pub struct LocationsCache {
+    locations: Mutex<Option<Vec<AzureLocation>>>,
+}
+
+impl LocationsCache {
+    // The regions on offer depend on the subscription, so a subscription or login change drops them
+    pub(crate) fn clear(&self) {
+        *self.locations.lock().unwrap() = None;
+    }
+}
+
+// Runs a read-only az query with JSON output, whatever its exit status
+async fn az_json_output(prefs: &Preferences, args: &[&str]) -> Result<std::process::Output, CommandError> {
+    let tool_info = crate::locate_tool("az".to_string(), prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure CLI not available".to_string()).into());
+    }
+    let az_path = tool_info.path.unwrap();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let max_buffered_bytes = prefs.max_buffered_output_bytes;
+
+    tokio::task::spawn_blocking(move || {
+        let child = Command::new(&az_path)
+            .args(&args)
+            .args(["--output", "json"])
+            .envs(crate::azure_tool_env())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to execute az: {}", e))?;
+        collect_output(child, max_buffered_bytes)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("az query task failed: {}", e).into()))
+}
+
+fn az_failure(output: &std::process::Output) -> CommandError {
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if is_auth_error(&stderr) {
+        return CommandError::AuthRequired("Not logged in to Azure. Please run 'az login'.".to_string());
+    }
+    format!("az exited with {}: {}", output.status, stderr).into()
+}
+
+// Runs a read-only az query with JSON output, returning stdout. A missing login comes back
+// as `AuthRequired` so the UI can prompt for `az login`.
+async fn az_json_query(prefs: &Preferences, args: &[&str]) -> Result<Vec<u8>, CommandError> {
+    let output = az_json_output(prefs, args).await?;
+    if !output.status.success() {
+        return Err(az_failure(&output));
+    }
+    Ok(output.stdout)
+}
+
+// Deserializes az's JSON stdout, skipping any notices printed ahead of it. Empty output reads
+// as `null`, so commands that print nothing for an empty list can be parsed as an `Option`.
+fn parse_az_json<T: DeserializeOwned>(stdout: &[u8], what: &str) -> Result<T, String> {
+    let (stdout, _) = split_az_json(stdout);
+    let json = if stdout.iter().all(u8::is_ascii_whitespace) { b"null".as_slice() } else { stdout };
+    serde_json::from_slice(json).map_err(|e| format!("Failed to parse az {}: {}", what, e))
+}
+
+/// Result shapes `run_az_typed` can deserialize
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AzShape {
+    // `az account show`
+    Account,
+    // `az resource list`
+    ResourceList,
+    // `az group list`
+    GroupList,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AzAccount {
+    pub id: String,
+    pub name: String,
+    pub tenant_id: String,
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub user: Option<AzAccountUser>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AzAccountUser {
+    pub name: String,
+    // "user" or "servicePrincipal"
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AzResource {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub resource_group: Option<String>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub tags: HashMap<String, String>,
+}
+
+/// A `run_az_typed` result, serialized as the bare value of the requested shape
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum AzTyped {
+    Account(AzAccount),
+    ResourceList(Vec<AzResource>),
+    GroupList(Vec<ResourceGroup>),
+}
+
+// A finished az run as `shape`; failures go through az_failure so a missing login is
+// `AuthRequired` here as everywhere else
+fn typed_output(shape: AzShape, output: &std::process::Output) -> Result<AzTyped, CommandError> {
+    if !output.status.success() {
+        return Err(az_failure(output));
+    }
+    Ok(typed_json(shape, &output.stdout)?)
+}
+
+fn typed_json(shape: AzShape, stdout: &[u8]) -> Result<AzTyped, String> {
+    Ok(match shape {
+        AzShape::Account => AzTyped::Account(parse_az_json(stdout, "account")?),
+        AzShape::ResourceList => {
+            let resources: Option<Vec<AzResource>> = parse_az_json(stdout, "resource list")?;
+            AzTyped::ResourceList(resources.unwrap_or_default())
+        }
+        AzShape::GroupList => AzTyped::GroupList(parse_resource_groups(stdout)?),
+    })
+}
+
+// Pages followed before giving up on a list that never ends
+const MAX_AZ_PAGES: u32 = 50;
+
+/// A list fetched page by page, aggregated into one result
+#[derive(Debug, Serialize)]
+pub struct AzPages {
+    pub result: AzTyped,
+    pub pages: u32,
+}
+
+/// What `run_az_typed` returns: the bare typed result, or the aggregated pages when
+/// `all_pages` was set
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum AzTypedResponse {
+    Typed(AzTyped),
+    AllPages(AzPages),
+}
+
+// One page of list output: a bare array (az already followed the pages itself) or an ARM-style
+// `{"value": [...], "nextLink": "..."}` object, as `az rest` returns
+fn split_page(stdout: &[u8]) -> Result<(Vec<serde_json::Value>, Option<String>), String> {
+    let page: serde_json::Value = parse_az_json(stdout, "page")?;
+    match page {
+        serde_json::Value::Null => Ok((Vec::new(), None)),
+        serde_json::Value::Array(items) => Ok((items, None)),
+        serde_json::Value::Object(mut fields) => {
+            let items = match fields.remove("value") {
+                Some(serde_json::Value::Array(items)) => items,
+                _ => return Err("Failed to parse az page: no `value` array".to_string()),
+            };
+            let next = fields.remove("nextLink").and_then(|link| link.as_str().map(str::to_string));
+            Ok((items, next.filter(|link| !link.is_empty())))
+        }
+        _ => Err("Failed to parse az page: expected an array or a `value` object".to_string()),
+    }
+}
+
+// Aggregates `first` and every page after it, fetching each `nextLink` with `fetch`, up to
+// `max_pages`. Returns the items and how many pages they came from.
+async fn collect_pages<F, Fut>(
+    first: &[u8],
+    max_pages: u32,
+    mut fetch: F,
+) -> Result<(Vec<serde_json::Value>, u32), CommandError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<u8>, CommandError>>,
+{
+    let (mut items, mut next) = split_page(first)?;
+    let mut pages = 1;
+    while let Some(link) = next {
+        if pages >= max_pages {
+            return Err(format!("Stopped after {} pages; narrow the query with --query or filters", max_pages).into());
+        }
+        let (page_items, page_next) = split_page(&fetch(link).await?)?;
+        items.extend(page_items);
+        next = page_next;
+        pages += 1;
+    }
+    Ok((items, pages))
+}
+
+// Runs az with JSON output and deserializes the result as the `expected` shape, so callers
+// get typed fields instead of picking through raw JSON. The output format is always JSON, so
+// `args` must not choose one. With `all_pages` a list shape follows each `nextLink` in the
+// output (as `az rest` returns for ARM lists) and returns every page as one list together with
+// the number of pages fetched; commands that page on their own take `--all` in `args` instead.
+#[tauri::command]
+pub async fn run_az_typed(
+    prefs: tauri::State<'_, PreferencesState>,
+    args: Vec<String>,
+    expected: AzShape,
+    all_pages: Option<bool>,
+) -> Result<AzTypedResponse, CommandError> {
+    if chooses_output(&args) {
+        return Err("run_az_typed always requests JSON output; remove the --output flag".to_string().into());
+    }
+    let prefs = prefs.get();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if !all_pages.unwrap_or(false) {
+        let output = az_json_output(&prefs, &args).await?;
+        return Ok(AzTypedResponse::Typed(typed_output(expected, &output)?));
+    }
+
+    if expected == AzShape::Account {
+        return Err("all_pages only applies to list shapes".to_string().into());
+    }
+    let first = az_json_query(&prefs, &args).await?;
+    let (items, pages) = collect_pages(&first, MAX_AZ_PAGES, |link| {
+        let prefs = &prefs;
+        async move { az_json_query(prefs, &["rest", "--method", "get", "--url", link.as_str()]).await }
+    })
+    .await?;
+    let aggregated = serde_json::to_vec(&items).map_err(|e| format!("Failed to aggregate az pages: {}", e))?;
+    Ok(AzTypedResponse::AllPages(AzPages {
+        result: typed_json(expected, &aggregated)?,
+        pages,
+    }))
+}
+
+#[tauri::command]
+pub async fn list_azure_locations(
+    prefs: tauri::State<'_, PreferencesState>,
+    cache: tauri::State<'_, LocationsCache>,
+) -> Result<Vec<AzureLocation>, CommandError> {
+    if let Some(locations) = cache.locations.lock().unwrap().clone() {
+        return Ok(locations);
+    }
+
+    let stdout = az_json_query(&prefs.get(), &["account", "list-locations"]).await?;
+    let locations = parse_locations(&stdout)?;
+    *cache.locations.lock().unwrap() = Some(locations.clone());
+    Ok(locations)
+}
+
+/// A resource group, as offered in the group selector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceGroup {
+    pub name: String,
+    pub location: String,
+    // az reports `null` for groups without tags
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub tags: HashMap<String, String>,
+}
+
+fn null_as_empty<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+fn parse_resource_groups(stdout: &[u8]) -> Result<Vec<ResourceGroup>, String> {
+    let groups: Option<Vec<ResourceGroup>> = parse_az_json(stdout, "resource groups")?;
+    Ok(groups.unwrap_or_default())
+}
+
+// Groups in az's active subscription, as set by `set_azure_subscription`, or in `subscription`
+// (a name or id) when given
+#[tauri::command]
+pub async fn list_resource_groups(
+    prefs: tauri::State<'_, PreferencesState>,
+    subscription: Option<String>,
+) -> Result<Vec<ResourceGroup>, CommandError> {
+    let mut args = vec!["group", "list"];
+    if let Some(subscription) = subscription.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        args.extend(["--subscription", subscription]);
+    }
+    let stdout = az_json_query(&prefs.get(), &args).await?;
+    Ok(parse_resource_groups(&stdout)?)
+}
+
+// Resource Graph query counting everything the caller can see, without listing it
+const RESOURCE_COUNT_QUERY: &str = "Resources | summarize total = count()";
+
+fn parse_resource_count(stdout: &[u8]) -> Result<u64, String> {
+    let response: serde_json::Value = serde_json::from_slice(split_az_json(stdout).0)
+        .map_err(|e| format!("Failed to parse az graph output: {}", e))?;
+    // `az graph query` wraps rows in `data`; older extension versions return the rows bare
+    let rows = response.get("data").unwrap_or(&response);
+    rows.get(0)
+        .and_then(|row| row.get("total"))
+        .and_then(|total| total.as_u64())
+        .ok_or_else(|| "az graph output has no resource count".to_string())
+}
+
+// Counts resources through Azure Resource Graph before committing to a full finder scan.
+// `args` are extra `az graph query` flags, e.g. `--subscriptions` or `--management-groups`.
+// Needs the `resource-graph` az extension.
+#[tauri::command]
+pub async fn count_azure_resources(
+    prefs: tauri::State<'_, PreferencesState>,
+    args: Vec<String>,
+) -> Result<u64, CommandError> {
+    let mut query = vec!["graph", "query", "-q", RESOURCE_COUNT_QUERY];
+    query.extend(args.iter().map(String::as_str));
+    let stdout = az_json_query(&prefs.get(), &query).await?;
+    Ok(parse_resource_count(&stdout)?)
+}
+
+/// Resources fetched by id, in the order they were asked for
+#[derive(Debug, Default, Serialize)]
+pub struct AzureResources {
+    pub resources: Vec<serde_json::Value>,
+    // Well-formed ids az had nothing for
+    pub not_found: Vec<String>,
+    // Ids that don't look like ARM resource ids, which were never sent
+    pub invalid: Vec<String>,
+}
+
+// `/subscriptions/{id}/.../providers/{namespace}/{type}/{name}`, with nested types allowed
+fn is_resource_id(id: &str) -> bool {
+    let Some(path) = id.strip_prefix('/') else {
+        return false;
+    };
+    let segments: Vec<&str> = path.split('/').collect();
+    if segments.iter().any(|segment| segment.is_empty() || segment.contains(char::is_whitespace)) {
+        return false;
+    }
+    let providers = segments.iter().rposition(|segment| segment.eq_ignore_ascii_case("providers"));
+    segments.len() >= 2
+        && segments[0].eq_ignore_ascii_case("subscriptions")
+        // Namespace, then type/name pairs
+        && providers.is_some_and(|at| {
+            let after = segments.len() - at - 1;
+            after >= 3 && after % 2 == 1
+        })
+}
+
+// Lines up az's results with the requested ids, by id since ARM ids are case-insensitive.
+// `az resource show --ids` gives a bare object for a single id and an array for several.
+fn match_resources(ids: &[String], stdout: &[u8]) -> Result<(Vec<serde_json::Value>, Vec<String>), String> {
+    let (stdout, _) = split_az_json(stdout);
+    let parsed: serde_json::Value = if stdout.iter().all(u8::is_ascii_whitespace) {
+        serde_json::Value::Array(Vec::new())
+    } else {
+        serde_json::from_slice(stdout).map_err(|e| format!("Failed to parse az resource output: {}", e))?
+    };
+    let mut found = match parsed {
+        serde_json::Value::Array(resources) => resources,
+        serde_json::Value::Null => Vec::new(),
+        resource => vec![resource],
+    };
+
+    let mut resources = Vec::with_capacity(ids.len());
+    let mut not_found = Vec::new();
+    for id in ids {
+        let position = found.iter().position(|resource| {
+            resource
+                .get("id")
+                .and_then(serde_json::Value::as_str)
+                .is_some_and(|found_id| found_id.eq_ignore_ascii_case(id))
+        });
+        match position {
+            Some(at) => resources.push(found.remove(at)),
+            None => not_found.push(id.clone()),
+        }
+    }
+    Ok((resources, not_found))
+}
+
+// Fetches several resources with one `az resource show --ids` instead of a call per id, for
+// detail views. Malformed ids are reported rather than sent; ids az can't find are listed in
+// `not_found` while the rest still come back.
+#[tauri::command]
+pub async fn get_azure_resources(
+    prefs: tauri::State<'_, PreferencesState>,
+    ids: Vec<String>,
+) -> Result<AzureResources, CommandError> {
+    let (valid, invalid): (Vec<String>, Vec<String>) = ids.into_iter().partition(|id| is_resource_id(id));
+    if valid.is_empty() {
+        return Ok(AzureResources {
+            invalid,
+            ..Default::default()
+        });
+    }
+
+    let mut args = vec!["resource", "show", "--ids"];
+    args.extend(valid.iter().map(String::as_str));
+    let output = az_json_output(&prefs.get(), &args).await?;
+    // A missing resource fails the whole call, but az still prints the ones it found
+    if !output.status.success() && output.stdout.iter().all(u8::is_ascii_whitespace) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !(stderr.contains("ResourceNotFound") || stderr.contains("was not found")) {
+            return Err(az_failure(&output));
+        }
+    }
+    let (resources, not_found) = match_resources(&valid, &output.stdout)?;
+    Ok(AzureResources {
+        resources,
+        not_found,
+        invalid,
+    })
+}
+
+// Subscription ids are GUIDs: 8-4-4-4-12 hex digits
+fn is_subscription_id(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionEntry {
+    id: String,
+    name: String,
+}
+
+// The id of the one subscription in `az account list` output named `name`, ignoring case.
+// A subscription visible through several tenants is listed once per tenant, so matches are
+// compared by id before calling a name ambiguous.
+fn subscription_id_by_name(name: &str, stdout: &[u8]) -> Result<String, String> {
+    let subscriptions: Vec<SubscriptionEntry> = parse_az_json(stdout, "account list output")?;
+    let mut ids: Vec<String> = subscriptions
+        .into_iter()
+        .filter(|subscription| subscription.name.eq_ignore_ascii_case(name))
+        .map(|subscription| subscription.id)
+        .collect();
+    ids.sort_by_key(|id| id.to_lowercase());
+    ids.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    match ids.len() {
+        0 => Err(format!("No subscription named '{}'", name)),
+        1 => Ok(ids.remove(0)),
+        _ => Err(format!(
+            "Subscription name '{}' is ambiguous; use one of these ids: {}",
+            name,
+            ids.join(", ")
+        )),
+    }
+}
+
+// Subscription id for a name or id, for commands that need the id. An id is returned as is
+// without calling az; a name is looked up in `az account list`.
+#[tauri::command]
+pub async fn resolve_subscription(
+    prefs: tauri::State<'_, PreferencesState>,
+    name_or_id: String,
+) -> Result<String, CommandError> {
+    let name_or_id = name_or_id.trim();
+    if name_or_id.is_empty() {
+        return Err("Subscription name or id must not be empty".to_string().into());
+    }
+    if is_subscription_id(name_or_id) {
+        return Ok(name_or_id.to_string());
+    }
+    let stdout = az_json_query(&prefs.get(), &["account", "list", "--query", "[].{id: id, name: name}"]).await?;
+    Ok(subscription_id_by_name(name_or_id, &stdout)?)
+}
+
+/// One identity az is logged in as, with the subscriptions it can use
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoggedInIdentity {
+    // None for accounts az lists without a user, e.g. tenant-level entries
+    pub user: Option<AzAccountUser>,
+    // Whether the active subscription belongs to this identity
+    pub active: bool,
+    pub subscriptions: Vec<AzAccount>,
+}
+
+// Groups `az account list --all` output by user, in the order az lists them
+fn group_identities(stdout: &[u8]) -> Result<Vec<LoggedInIdentity>, String> {
+    let accounts: Option<Vec<AzAccount>> = parse_az_json(stdout, "account list output")?;
+    let mut identities: Vec<LoggedInIdentity> = Vec::new();
+    for account in accounts.unwrap_or_default() {
+        let same_user = |identity: &&mut LoggedInIdentity| match (&identity.user, &account.user) {
+            (Some(a), Some(b)) => a.name.eq_ignore_ascii_case(&b.name) && a.kind == b.kind,
+            (a, b) => a == b,
+        };
+        match identities.iter_mut().find(same_user) {
+            Some(identity) => {
+                identity.active |= account.is_default;
+                identity.subscriptions.push(account);
+            }
+            None => identities.push(LoggedInIdentity {
+                user: account.user.clone(),
+                active: account.is_default,
+                subscriptions: vec![account],
+            }),
+        }
+    }
+    Ok(identities)
+}
+
+// Every identity az is logged in as (az keeps several logins side by side) with its
+// subscriptions, including disabled ones, and which one is active. Empty when nobody is logged
+// in. Switch between them with `set_azure_subscription`.
+#[tauri::command]
+pub async fn list_azure_identities(
+    prefs: tauri::State<'_, PreferencesState>,
+) -> Result<Vec<LoggedInIdentity>, CommandError> {
+    let output = az_json_output(&prefs.get(), &["account", "list", "--all"]).await?;
+    if !output.status.success() {
+        // Without any login az fails and points at `az login`; that's just no identities here
+        return match az_failure(&output) {
+            CommandError::AuthRequired(_) => Ok(Vec::new()),
+            error => Err(error),
+        };
+    }
+    Ok(group_identities(&output.stdout)?)
+}
+
+// Makes a subscription (name or id) the active one for later az calls
+#[tauri::command]
+pub async fn set_azure_subscription(
+    prefs: tauri::State<'_, PreferencesState>,
+    locations: tauri::State<'_, LocationsCache>,
+    tokens: tauri::State<'_, TokenStatusCache>,
+    subscription: String,
+) -> Result<(), CommandError> {
+    let subscription = subscription.trim();
+    if subscription.is_empty() {
+        return Err("Subscription name or id must not be empty".to_string().into());
+    }
+    az_json_query(&prefs.get(), &["account", "set", "--subscription", subscription]).await?;
+    locations.clear();
+    tokens.clear();
+    Ok(())
+}
+
+/// A resource provider's registration in the current subscription
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderRegistration {
+    pub namespace: String,
+    // "Registered", "NotRegistered", "Registering" or "Unregistering"
+    pub state: String,
+    pub registered: bool,
+}
+
+// e.g. `Microsoft.ContainerService`
+fn check_provider_namespace(namespace: &str) -> Result<(), String> {
+    let valid = namespace.contains('.')
+        && namespace
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !valid {
+        return Err(format!("Not a resource provider namespace: '{}'", namespace));
+    }
+    Ok(())
+}
+
+fn parse_registration(namespace: &str, stdout: &[u8]) -> Result<ProviderRegistration, String> {
+    let state: Option<String> = parse_az_json(stdout, "provider registration state")?;
+    let state = state.ok_or_else(|| format!("az reported no registration state for {}", namespace))?;
+    Ok(ProviderRegistration {
+        namespace: namespace.to_string(),
+        registered: state.eq_ignore_ascii_case("Registered"),
+        state,
+    })
+}
+
+async fn provider_registration(prefs: &Preferences, namespace: &str) -> Result<ProviderRegistration, CommandError> {
+    check_provider_namespace(namespace)?;
+    let stdout = az_json_query(prefs, &["provider", "show", "-n", namespace, "--query", "registrationState"]).await?;
+    Ok(parse_registration(namespace, &stdout)?)
+}
+
+// Whether a resource provider is registered in the current subscription, so a missing
+// registration can be flagged before an az operation fails on it
+#[tauri::command]
+pub async fn check_provider_registration(
+    prefs: tauri::State<'_, PreferencesState>,
+    namespace: String,
+) -> Result<ProviderRegistration, CommandError> {
+    provider_registration(&prefs.get(), namespace.trim()).await
+}
+
+// Registers a resource provider and returns its state afterwards. Registration continues in
+// the background and can take minutes, so this usually reports `Registering`; `wait` blocks
+// until it's done.
+#[tauri::command]
+pub async fn register_provider(
+    prefs: tauri::State<'_, PreferencesState>,
+    namespace: String,
+    wait: Option<bool>,
+) -> Result<ProviderRegistration, CommandError> {
+    let prefs = prefs.get();
+    let namespace = namespace.trim();
+    check_provider_namespace(namespace)?;
+    let mut args = vec!["provider", "register", "-n", namespace];
+    if wait.unwrap_or(false) {
+        args.push("--wait");
+    }
+    az_json_query(&prefs, &args).await?;
+    provider_registration(&prefs, namespace).await
+}
+
+/// When the current az access token expires. The token itself is never returned.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenStatus {
+    // Unix timestamp, seconds
+    pub expires_on: i64,
+    // Negative once the token has expired
+    pub expires_in_secs: i64,
+    pub tenant: Option<String>,
+    pub subscription: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct TokenExpiry {
+    // Only reported by az 2.54+; older versions give just the local-time `expiresOn` string
+    expires_on: Option<i64>,
+    tenant: Option<String>,
+    subscription: Option<String>,
+}
+
+impl TokenExpiry {
+    pub(crate) fn status(&self, now: i64) -> Option<TokenStatus> {
+        let expires_on = self.expires_on?;
+        Some(TokenStatus {
+            expires_on,
+            expires_in_secs: expires_on - now,
+            tenant: self.tenant.clone(),
+            subscription: self.subscription.clone(),
+        })
+    }
+}
+
+// `expires_on` is an integer in the CLI's output but a string in some MSAL-based builds
+pub(crate) fn parse_token_expiry(stdout: &[u8]) -> Result<TokenExpiry, String> {
+    let mut payload: serde_json::Value = serde_json::from_slice(split_az_json(stdout).0)
+        .map_err(|e| format!("Failed to parse az account get-access-token output: {}", e))?;
+    if let Some(expires_on) = payload.get_mut("expires_on") {
+        if let Some(parsed) = expires_on.as_str().and_then(|s| s.parse::<i64>().ok()) {
+            *expires_on = parsed.into();
+        }
+    }
+    serde_json::from_value(payload)
+        .map_err(|e| format!("Failed to parse az account get-access-token output: {}", e))
+}
+
+pub(crate) fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Expiry of the last token seen, so polling doesn't spawn az each time. Kept in managed state.
+#[derive(Default)]
+pub struct TokenStatusCache {
+    expiry: Mutex<Option<TokenExpiry>>,
+}
+
+impl TokenStatusCache {
+    // A subscription or login change means a different token, so the cached expiry no longer applies
+    pub(crate) fn clear(&self) {
+        *self.expiry.lock().unwrap() = None;
+    }
+}
+
+// Expiry of the current az access token, for refreshing ahead of a long finder run. Served from
+// the cache until that token has expired or `refresh` is set; the query leaves the token itself
+// out of az's output.
+#[tauri::command]
+pub async fn azure_token_status(
+    prefs: tauri::State<'_, PreferencesState>,
+    cache: tauri::State<'_, TokenStatusCache>,
+    refresh: Option<bool>,
+) -> Result<TokenStatus, CommandError> {
+    let now = unix_now();
+    if !refresh.unwrap_or(false) {
+        let cached = cache.expiry.lock().unwrap().as_ref().and_then(|expiry| expiry.status(now));
+        if let Some(status) = cached.filter(|status| status.expires_in_secs > 0) {
+            return Ok(status);
+        }
+    }
+
+    let stdout = az_json_query(
+        &prefs.get(),
+        &[
+            "account",
+            "get-access-token",
+            "--query",
+            "{expires_on: expires_on, tenant: tenant, subscription: subscription}",
+        ],
+    )
+    .await?;
+    let expiry = parse_token_expiry(&stdout)?;
+    let status = expiry
+        .status(now)
+        .ok_or_else(|| "This Azure CLI doesn't report token expiry; az 2.54 or later is needed".to_string())?;
+    *cache.expiry.lock().unwrap() = Some(expiry);
+    Ok(status)
+}
+
+/// One `az` found on the machine and the version it reports
+#[derive(Debug, Serialize)]
+pub struct AzInstall {
+    pub path: String,
+    // None when the binary couldn't be run
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AzInstallDiagnostics {
+    pub installs: Vec<AzInstall>,
+    // The az the app actually runs, as locate_tool resolves it
+    pub active: Option<String>,
+    // More than one distinct version among the installs
+    pub conflicting: bool,
+}
+
+async fn install_diagnostics(paths: Vec<String>, active: Option<String>) -> AzInstallDiagnostics {
+    let mut installs = Vec::with_capacity(paths.len());
+    for path in paths {
+        let version = crate::warmup::version_of(&path).await;
+        installs.push(AzInstall { path, version });
+    }
+
+    let mut versions: Vec<&str> = installs.iter().filter_map(|i| i.version.as_deref()).collect();
+    versions.sort_unstable();
+    versions.dedup();
+    let conflicting = versions.len() > 1;
+    AzInstallDiagnostics { installs, active, conflicting }
+}
+
+// Answers "which az am I running": every az on PATH and in the common install locations
+// (e.g. both the MSI and a pip install on Windows) with the version each one reports
+#[tauri::command]
+pub async fn az_install_diagnostics(
+    prefs: tauri::State<'_, PreferencesState>,
+) -> Result<AzInstallDiagnostics, String> {
+    let prefs = prefs.get();
+    let mut paths = Vec::new();
+    if let Some(configured) = prefs.tool_paths.get("az") {
+        paths.push(configured.clone());
+    }
+    for path in crate::find_all_tool_paths("az", &crate::AZ_COMMON_PATHS) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    let active = crate::locate_tool("az".to_string(), &prefs).await?.path;
+    Ok(install_diagnostics(paths, active).await)
+}
+
+/// Whether the `az` the app runs looks like the real Azure CLI rather than a wrapper
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AzIdentity {
+    pub path: String,
+    pub genuine: bool,
+    // First line `az --version` printed, for inspecting what answered instead
+    pub first_line: Option<String>,
+    // The azure-cli version from the banner, when it has one
+    pub version: Option<String>,
+}
+
+// The real CLI starts `--version` with `azure-cli  <version>` followed by its `core` package
+fn identify_az(path: &str, version_output: &str) -> AzIdentity {
+    let mut lines = version_output.lines().map(str::trim).filter(|line| !line.is_empty());
+    let first_line = lines.next().map(str::to_string);
+    let version = first_line.as_deref().and_then(|line| {
+        let mut words = line.split_whitespace();
+        let name = words.next()?;
+        let version = words.next()?;
+        let looks_like_version = version.split('.').count() >= 3
+            && version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+        (name == "azure-cli" && looks_like_version).then(|| version.to_string())
+    });
+    let has_core = lines.any(|line| line.split_whitespace().next() == Some("core"));
+    AzIdentity {
+        path: path.to_string(),
+        genuine: version.is_some() && has_core,
+        first_line,
+        version,
+    }
+}
+
+// Runs `az --version` on the az the app resolves and checks for the azure-cli banner, to catch
+// an alias or wrapper script standing in for the real CLI
+#[tauri::command]
+pub async fn verify_az_identity(prefs: tauri::State<'_, PreferencesState>) -> Result<AzIdentity, String> {
+    let tool_info = crate::locate_tool("az".to_string(), &prefs.get()).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure CLI not available".to_string()));
+    }
+    let az_path = tool_info.path.unwrap();
+    let output = tokio::process::Command::new(&az_path)
+        .arg("--version")
+        .envs(crate::azure_tool_env())
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute az: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let text = if stdout.trim().is_empty() { String::from_utf8_lossy(&output.stderr) } else { stdout };
+    Ok(identify_az(&az_path, &text))
+}
+
+/// One setting from `az config get`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AzConfigEntry {
+    pub name: String,
+    pub value: String,
+    // The config file or environment variable the value came from
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// The az configuration in effect, by section
+#[derive(Debug, Default, Serialize)]
+pub struct AzConfigDump {
+    pub sections: BTreeMap<String, Vec<AzConfigEntry>>,
+    // The `defaults` section as name to value, i.e. what `az configure --defaults` set (e.g.
+    // `group`, `location`); these fill in arguments left off az commands
+    pub defaults: BTreeMap<String, String>,
+}
+
+fn parse_config(stdout: &[u8]) -> Result<AzConfigDump, String> {
+    let sections: Option<BTreeMap<String, Vec<AzConfigEntry>>> = parse_az_json(stdout, "config")?;
+    let sections = sections.unwrap_or_default();
+    let defaults = sections
+        .get("defaults")
+        .map(|entries| entries.iter().map(|entry| (entry.name.clone(), entry.value.clone())).collect())
+        .unwrap_or_default();
+    Ok(AzConfigDump { sections, defaults })
+}
+
+// Every az config setting, including the `az configure --defaults` values, read with the same
+// env the app runs az with, to explain commands picking up a group or location nobody passed.
+// Empty when nothing is configured.
+#[tauri::command]
+pub async fn az_config_dump(prefs: tauri::State<'_, PreferencesState>) -> Result<AzConfigDump, CommandError> {
+    let stdout = az_json_query(&prefs.get(), &["config", "get"]).await?;
+    Ok(parse_config(&stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_output_is_requested_unless_already_chosen() {
+        assert_eq!(
+            with_json_output(vec!["group".to_string(), "list".to_string()]),
+            ["group", "list", "--output", "json"]
+        );
+        assert_eq!(with_json_output(vec!["-o".to_string(), "tsv".to_string()]), ["-o", "tsv"]);
+    }
+
+    #[test]
+    fn parsed_result_and_raw_file_are_both_produced() {
+        let dir = std::env::temp_dir().join(format!("skanyxx-az-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("nested").join("groups.json");
+        let stdout = br#"[{ "name": "rg-a" }]"#;
+
+        let output = capture_az_output(stdout, Some(&path)).unwrap();
+        assert_eq!(output.result, serde_json::json!([{ "name": "rg-a" }]));
+        assert_eq!(output.bytes_written, Some(stdout.len() as u64));
+        assert_eq!(output.warning, None);
+        assert_eq!(fs::read(&path).unwrap(), stdout);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn notices_before_the_json_are_split_off() {
+        let stdout = b"WARNING: You have 2 update(s) available. Consider updating your CLI installation with 'az upgrade'\n\n{\n  \"name\": \"Production\"\n}\n";
+        let (json, warning) = split_az_json(stdout);
+        assert!(json.starts_with(b"{"));
+        assert_eq!(
+            warning.as_deref(),
+            Some("WARNING: You have 2 update(s) available. Consider updating your CLI installation with 'az upgrade'")
+        );
+
+        let output = capture_az_output(stdout, None).unwrap();
+        assert_eq!(output.result, serde_json::json!({ "name": "Production" }));
+        assert!(output.stdout_warning.unwrap().starts_with("WARNING: You have 2 update(s)"));
+        let account: serde_json::Value = parse_az_json(stdout, "account").unwrap();
+        assert_eq!(account["name"], "Production");
+
+        assert_eq!(split_az_json(b"[]"), (b"[]".as_slice(), None));
+        // Nothing that could be JSON: left whole for the parse error
+        assert_eq!(split_az_json(b"name\trg-a\n"), (b"name\trg-a\n".as_slice(), None));
+    }
+
+    #[test]
+    fn locations_payload_deserializes() {
+        let payload = br#"[
+            {
+                "displayName": "East US",
+                "id": "/subscriptions/s/locations/eastus",
+                "metadata": {
+                    "geography": "United States",
+                    "geographyGroup": "US",
+                    "physicalLocation": "Virginia",
+                    "regionType": "Physical"
+                },
+                "name": "eastus",
+                "regionalDisplayName": "(US) East US",
+                "type": "Region"
+            },
+            {
+                "displayName": "United States",
+                "id": "/subscriptions/s/locations/unitedstates",
+                "metadata": { "regionType": "Logical" },
+                "name": "unitedstates",
+                "type": "Region"
+            }
+        ]"#;
+        let locations = parse_locations(payload).unwrap();
+        assert_eq!(
+            locations[0],
+            AzureLocation {
+                name: "eastus".to_string(),
+                display_name: "East US".to_string(),
+                geography: Some("United States".to_string()),
+            }
+        );
+        assert_eq!(locations[1].geography, None);
+        assert!(is_auth_error("ERROR: Please run 'az login' to setup account."));
+    }
+
+    #[test]
+    fn resource_group_list_deserializes() {
+        let payload = br#"[
+            {
+                "id": "/subscriptions/s/resourceGroups/rg-web",
+                "location": "westeurope",
+                "managedBy": null,
+                "name": "rg-web",
+                "properties": { "provisioningState": "Succeeded" },
+                "tags": { "env": "prod" },
+                "type": "Microsoft.Resources/resourceGroups"
+            },
+            {
+                "id": "/subscriptions/s/resourceGroups/rg-scratch",
+                "location": "eastus",
+                "name": "rg-scratch",
+                "tags": null
+            }
+        ]"#;
+        let groups = parse_resource_groups(payload).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "rg-web");
+        assert_eq!(groups[0].location, "westeurope");
+        assert_eq!(groups[0].tags.get("env").map(String::as_str), Some("prod"));
+        assert!(groups[1].tags.is_empty());
+
+        assert!(parse_resource_groups(b"[]").unwrap().is_empty());
+        assert!(parse_resource_groups(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn count_query_returns_an_integer() {
+        let payload = br#"{ "count": 1, "data": [{ "total": 1284 }], "skip_token": null, "total_records": 1 }"#;
+        assert_eq!(parse_resource_count(payload).unwrap(), 1284);
+        assert_eq!(parse_resource_count(br#"[{ "total": 7 }]"#).unwrap(), 7);
+        assert!(parse_resource_count(br#"{ "data": [] }"#).is_err());
+    }
+
+    #[test]
+    fn failed_save_keeps_the_parsed_result() {
+        // A directory can't be written as a file
+        let dir = std::env::temp_dir();
+        let output = capture_az_output(b"{}", Some(&dir)).unwrap();
+        assert_eq!(output.result, serde_json::json!({}));
+        assert_eq!(output.bytes_written, None);
+        assert!(output.warning.unwrap().starts_with("Failed to write"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn differing_install_versions_are_flagged() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("skanyxx-az-installs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut paths = Vec::new();
+        for (name, version) in [("msi", "2.61.0"), ("pip", "2.49.0")] {
+            let bin = dir.join(name);
+            fs::create_dir_all(&bin).unwrap();
+            let az = bin.join("az");
+            fs::write(&az, format!("#!/bin/sh\necho 'azure-cli {}'\n", version)).unwrap();
+            fs::set_permissions(&az, fs::Permissions::from_mode(0o755)).unwrap();
+            paths.push(az.to_string_lossy().into_owned());
+        }
+
+        let report = install_diagnostics(paths.clone(), Some(paths[0].clone())).await;
+        assert!(report.conflicting);
+        assert_eq!(report.installs[0].version.as_deref(), Some("azure-cli 2.61.0"));
+        assert_eq!(report.installs[1].version.as_deref(), Some("azure-cli 2.49.0"));
+
+        let single = install_diagnostics(vec![paths[0].clone()], None).await;
+        assert!(!single.conflicting);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn batch_keeps_input_order_under_the_concurrency_cap() {
+        let query = |script: &str| vec!["-c".to_string(), script.to_string()];
+        let queries = vec![
+            query("sleep 0.3; echo '[1]'"),
+            query("sleep 0.3; echo 'not json' >&2; exit 1"),
+            query("sleep 0.3; echo '{\"n\": 3}'"),
+            query("echo \"Please run 'az login' to setup account.\" >&2; exit 1"),
+        ];
+
+        let started = std::time::Instant::now();
+        let results = run_batch("sh", queries, HashMap::new(), 2, u64::MAX).await;
+        // Two run together, the third waits for a free slot
+        assert!(started.elapsed() >= std::time::Duration::from_millis(600));
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].output.as_ref().unwrap().result, serde_json::json!([1]));
+        assert!(results[1].output.is_none());
+        assert!(results[1].error.as_ref().unwrap().to_string().contains("not json"));
+        assert_eq!(results[2].output.as_ref().unwrap().result, serde_json::json!({ "n": 3 }));
+        assert!(matches!(results[3].error, Some(CommandError::AuthRequired(_))));
+    }
+
+    #[test]
+    fn token_payload_gives_expiry_without_the_token() {
+        let payload = br#"{
+            "accessToken": "eyJ0eXAiOiJKV1QiLCJhbGciOiJSUzI1NiJ9.secret",
+            "expiresOn": "2024-01-01 12:00:00.000000",
+            "expires_on": 1704110400,
+            "subscription": "00000000-0000-0000-0000-000000000001",
+            "tenant": "00000000-0000-0000-0000-000000000002",
+            "tokenType": "Bearer"
+        }"#;
+        let status = parse_token_expiry(payload).unwrap().status(1704110400 - 1800).unwrap();
+        assert_eq!(status.expires_on, 1704110400);
+        assert_eq!(status.expires_in_secs, 1800);
+        assert_eq!(status.tenant.as_deref(), Some("00000000-0000-0000-0000-000000000002"));
+        assert!(!serde_json::to_string(&status).unwrap().contains("secret"));
+
+        let as_string = parse_token_expiry(br#"{"expires_on": "1704110400"}"#).unwrap();
+        assert_eq!(as_string.status(1704110460).unwrap().expires_in_secs, -60);
+        assert!(parse_token_expiry(br#"{"expiresOn": "2024-01-01 12:00:00.000000"}"#)
+            .unwrap()
+            .status(0)
+            .is_none());
+    }
+
+    #[test]
+    fn resources_are_matched_to_ids_and_malformed_ids_rejected() {
+        let vm = "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Compute/virtualMachines/vm1";
+        let db = "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Sql/servers/sql1/databases/db1";
+        let malformed = "subscriptions/s1/resourceGroups/rg";
+        assert!(is_resource_id(vm));
+        assert!(is_resource_id(db));
+        assert!(!is_resource_id(malformed));
+        assert!(!is_resource_id("/subscriptions/s1/resourceGroups/rg"));
+        assert!(!is_resource_id("/subscriptions/s1/providers/Microsoft.Compute/virtualMachines"));
+
+        let ids = vec![db.to_string(), vm.to_string()];
+        // az's order needn't match the request, and it may change the id's casing
+        let stdout = format!(
+            r#"[{{"id": "{}", "name": "vm1"}}, {{"id": "{}", "name": "db1"}}]"#,
+            vm.to_uppercase(),
+            db
+        );
+        let (resources, not_found) = match_resources(&ids, stdout.as_bytes()).unwrap();
+        let names: Vec<&str> = resources.iter().map(|r| r["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["db1", "vm1"]);
+        assert!(not_found.is_empty());
+
+        let single = format!(r#"{{"id": "{}", "name": "vm1"}}"#, vm);
+        let (resources, not_found) = match_resources(&ids, single.as_bytes()).unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(not_found, [db]);
+    }
+
+    #[test]
+    fn subscription_names_resolve_to_ids_and_ids_pass_through() {
+        let id = "0b1f6471-1bf0-4dda-aec3-cb9272f09590";
+        assert!(is_subscription_id(id));
+        assert!(!is_subscription_id("Production"));
+        assert!(!is_subscription_id("0b1f6471-1bf0-4dda-aec3-cb9272f0959"));
+
+        let payload = br#"[
+            { "id": "0b1f6471-1bf0-4dda-aec3-cb9272f09590", "name": "Production" },
+            { "id": "6f2c1d7e-4a1b-4c2d-9e3f-5a6b7c8d9e0f", "name": "Staging" },
+            { "id": "6f2c1d7e-4a1b-4c2d-9e3f-5a6b7c8d9e0f", "name": "Staging" },
+            { "id": "9a8b7c6d-5e4f-4a3b-2c1d-0e9f8a7b6c5d", "name": "Sandbox" },
+            { "id": "1c2d3e4f-5a6b-4c7d-8e9f-0a1b2c3d4e5f", "name": "sandbox" }
+        ]"#;
+        assert_eq!(subscription_id_by_name("production", payload).unwrap(), id);
+        // Listed twice through two tenants, still one subscription
+        assert_eq!(
+            subscription_id_by_name("Staging", payload).unwrap(),
+            "6f2c1d7e-4a1b-4c2d-9e3f-5a6b7c8d9e0f"
+        );
+        assert!(subscription_id_by_name("Sandbox", payload).unwrap_err().contains("ambiguous"));
+        assert_eq!(
+            subscription_id_by_name("Dev", payload).unwrap_err(),
+            "No subscription named 'Dev'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn typed_output_deserializes_or_maps_auth_failures() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let output = |code: i32, stdout: &[u8], stderr: &[u8]| std::process::Output {
+            status: std::process::ExitStatus::from_raw(code << 8),
+            stdout: stdout.to_vec(),
+            stderr: stderr.to_vec(),
+        };
+
+        let account = br#"{
+            "environmentName": "AzureCloud",
+            "id": "0b1f6471-1bf0-4dda-aec3-cb9272f09590",
+            "isDefault": true,
+            "name": "Production",
+            "state": "Enabled",
+            "tenantId": "72f988bf-86f1-41af-91ab-2d7cd011db47",
+            "user": { "name": "dev@contoso.com", "type": "user" }
+        }"#;
+        let AzTyped::Account(account) = typed_output(AzShape::Account, &output(0, account, b"")).unwrap() else {
+            panic!("expected an account");
+        };
+        assert_eq!(account.name, "Production");
+        assert_eq!(account.tenant_id, "72f988bf-86f1-41af-91ab-2d7cd011db47");
+        assert_eq!(account.user.unwrap().kind, "user");
+
+        let resources = br#"[{
+            "id": "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Web/sites/app1",
+            "name": "app1",
+            "type": "Microsoft.Web/sites",
+            "location": "westeurope",
+            "resourceGroup": "rg",
+            "tags": null
+        }]"#;
+        let typed = typed_output(AzShape::ResourceList, &output(0, resources, b"")).unwrap();
+        assert!(matches!(&typed, AzTyped::ResourceList(list) if list[0].resource_group.as_deref() == Some("rg")));
+        assert_eq!(
+            typed_output(AzShape::GroupList, &output(0, b"", b"")).unwrap(),
+            AzTyped::GroupList(Vec::new())
+        );
+
+        let failed = output(1, b"", b"ERROR: Please run 'az login' to setup account.");
+        assert!(matches!(
+            typed_output(AzShape::Account, &failed),
+            Err(CommandError::AuthRequired(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn next_links_are_followed_into_one_list() {
+        let first = br#"{
+            "value": [{ "id": "/subscriptions/s1/resourceGroups/rg1", "name": "rg1", "location": "westeurope" }],
+            "nextLink": "https://management.azure.com/subscriptions/s1/resourcegroups?$skiptoken=2"
+        }"#;
+        let mut requested = Vec::new();
+        let (items, pages) = collect_pages(first, MAX_AZ_PAGES, |link| {
+            requested.push(link);
+            async { Ok(br#"{"value": [{ "id": "/subscriptions/s1/resourceGroups/rg2", "name": "rg2", "location": "northeurope" }]}"#.to_vec()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(pages, 2);
+        assert_eq!(requested, ["https://management.azure.com/subscriptions/s1/resourcegroups?$skiptoken=2"]);
+
+        let aggregated = serde_json::to_vec(&items).unwrap();
+        let AzTyped::GroupList(groups) = typed_json(AzShape::GroupList, &aggregated).unwrap() else {
+            panic!("expected groups");
+        };
+        let names: Vec<&str> = groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, ["rg1", "rg2"]);
+
+        // A bare array is a single, complete page
+        let (items, pages) = collect_pages(b"[1, 2]", MAX_AZ_PAGES, |_| async { unreachable!() }).await.unwrap();
+        assert_eq!((items.len(), pages), (2, 1));
+
+        let endless = collect_pages(first, 3, |_| async { Ok(first.to_vec()) }).await;
+        assert!(endless.unwrap_err().to_string().contains("Stopped after 3 pages"));
+    }
+
+    #[test]
+    fn provider_registration_state_is_parsed() {
+        let registered = parse_registration("Microsoft.Web", b"\"Registered\"\n").unwrap();
+        assert_eq!(registered.state, "Registered");
+        assert!(registered.registered);
+
+        let missing = parse_registration("Microsoft.ContainerService", b"\"NotRegistered\"\n").unwrap();
+        assert_eq!(missing.state, "NotRegistered");
+        assert!(!missing.registered);
+
+        assert!(parse_registration("Microsoft.Web", b"").is_err());
+        assert!(check_provider_namespace("Microsoft.Web").is_ok());
+        assert!(check_provider_namespace("Microsoft.Web; rm -rf").is_err());
+    }
+
+    #[test]
+    fn genuine_az_is_told_apart_from_a_shim() {
+        let genuine = "azure-cli                         2.57.0\n\n\
+                       core                              2.57.0\n\
+                       telemetry                          1.1.0\n\n\
+                       Python location '/usr/bin/python3'\n";
+        let identity = identify_az("/usr/bin/az", genuine);
+        assert!(identity.genuine);
+        assert_eq!(identity.version.as_deref(), Some("2.57.0"));
+
+        let wrapper = "az-wrapper 1.0 (forwards to docker run mcr.microsoft.com/azure-cli)";
+        let shim = identify_az("/usr/local/bin/az", &format!("{}\n", wrapper));
+        assert!(!shim.genuine);
+        assert_eq!(shim.version, None);
+        assert_eq!(shim.first_line.as_deref(), Some(wrapper));
+
+        // The banner alone, without the package list the real CLI prints, isn't enough
+        assert!(!identify_az("/tmp/az", "azure-cli 2.57.0\n").genuine);
+    }
+
+    #[test]
+    fn config_sections_and_defaults_are_parsed() {
+        let payload = br#"{
+            "core": [
+                {"name": "output", "source": "/home/u/.azure/config", "value": "table"}
+            ],
+            "defaults": [
+                {"name": "group", "source": "/home/u/.azure/config", "value": "rg-web"},
+                {"name": "location", "source": "AZURE_DEFAULTS_LOCATION", "value": "westeurope"}
+            ]
+        }"#;
+        let config = parse_config(payload).unwrap();
+        assert_eq!(config.sections.len(), 2);
+        assert_eq!(config.sections["core"][0].value, "table");
+        assert_eq!(config.defaults["group"], "rg-web");
+        assert_eq!(config.defaults["location"], "westeurope");
+
+        for empty in [b"{}\n".as_slice(), b""] {
+            let config = parse_config(empty).unwrap();
+            assert!(config.sections.is_empty());
+            assert!(config.defaults.is_empty());
+        }
+    }
+
+    #[test]
+    fn identities_are_grouped_with_the_active_one_marked() {
+        let payload = br#"[
+            {"id": "00000000-0000-0000-0000-000000000001", "name": "Prod", "tenantId": "t1", "isDefault": false,
+             "state": "Enabled", "user": {"name": "ana@contoso.com", "type": "user"}},
+            {"id": "00000000-0000-0000-0000-000000000002", "name": "Ops", "tenantId": "t2", "isDefault": true,
+             "state": "Enabled", "user": {"name": "deploy-sp", "type": "servicePrincipal"}},
+            {"id": "00000000-0000-0000-0000-000000000003", "name": "Dev", "tenantId": "t1", "isDefault": false,
+             "state": "Disabled", "user": {"name": "Ana@contoso.com", "type": "user"}}
+        ]"#;
+        let identities = group_identities(payload).unwrap();
+        assert_eq!(identities.len(), 2);
+
+        assert_eq!(identities[0].user.as_ref().unwrap().name, "ana@contoso.com");
+        assert!(!identities[0].active);
+        let names: Vec<&str> = identities[0].subscriptions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Prod", "Dev"]);
+
+        assert_eq!(identities[1].user.as_ref().unwrap().kind, "servicePrincipal");
+        assert!(identities[1].active);
+        assert!(identities[1].subscriptions[0].is_default);
+
+        assert!(group_identities(b"[]").unwrap().is_empty());
+        assert!(group_identities(b"").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use std::process::Stdio;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+use crate::az::{LocationsCache, TokenStatusCache};
+use crate::preferences::PreferencesState;
+use crate::tool_runner::OutputChunk;
+
+/// The code and page for a device-code login, emitted as a `device-code` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceCode {
+    pub code: String,
+    pub url: String,
+}
+
+// az prints e.g. "To sign in, use a web browser to open the page https://microsoft.com/devicelogin
+// and enter the code F4GQ5ZLTN to authenticate."
+fn device_code_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)(https?://\S+?)[.,]?\s+and enter the code\s+([A-Z0-9-]+)").unwrap()
+    })
+}
+
+fn parse_device_code(line: &str) -> Option<DeviceCode> {
+    let captures = device_code_pattern().captures(line)?;
+    Some(DeviceCode {
+        code: captures[2].to_string(),
+        url: captures[1].to_string(),
+    })
+}
+
+// Runs `az login --use-device-code`, streaming its stderr line by line as `az-login-output`
+// events. The code and verification page are pulled out and emitted once as a `device-code`
+// event as soon as az prints them, and streaming carries on until the login completes. Returns
+// the subscriptions az lists for the new login.
+#[tauri::command]
+pub async fn run_azure_login(
+    app: AppHandle,
+    prefs: tauri::State<'_, PreferencesState>,
+    locations: tauri::State<'_, LocationsCache>,
+    tokens: tauri::State<'_, TokenStatusCache>,
+    tenant: Option<String>,
+    azure_config_dir: Option<String>,
+    create_config_dir: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let tool_info = crate::locate_tool("az".to_string(), &prefs.get()).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure CLI not available".to_string()));
+    }
+    let az_path = tool_info.path.unwrap();
+    let env = crate::azure_env_with_config_dir(azure_config_dir.as_deref(), create_config_dir.unwrap_or(false))?;
+
+    let mut args = vec!["login", "--use-device-code", "--output", "json"];
+    if let Some(tenant) = tenant.as_deref() {
+        args.extend(["--tenant", tenant]);
+    }
+    let mut child = tokio::process::Command::new(&az_path)
+        .args(&args)
+        .envs(&env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute az login: {}", e))?;
+
+    let mut stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let read_stdout = async {
+        let mut buffer = Vec::new();
+        let _ = stdout.read_to_end(&mut buffer).await;
+        buffer
+    };
+    let stream_stderr = async {
+        let mut lines = BufReader::new(stderr).lines();
+        let mut received = Vec::new();
+        let mut code_sent = false;
+        while let Ok(Some(line)) = lines.next_line().await {
+            if !code_sent {
+                if let Some(device_code) = parse_device_code(&line) {
+                    let _ = app.emit("device-code", device_code);
+                    code_sent = true;
+                }
+            }
+            let _ = app.emit(
+                "az-login-output",
+                OutputChunk {
+                    stream: "stderr",
+                    data: line.clone(),
+                },
+            );
+            received.push(line);
+        }
+        received
+    };
+    let (stdout, stderr) = tokio::join!(read_stdout, stream_stderr);
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to wait for az login: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("az login exited with {}: {}", status, stderr.join("\n").trim()));
+    }
+    // The new login may have a different account and subscription behind it
+    locations.clear();
+    tokens.clear();
+    let (stdout, _) = crate::az::split_az_json(&stdout);
+    if stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(serde_json::Value::Array(Vec::new()));
+    }
+    serde_json::from_slice(stdout).map_err(|e| format!("Failed to parse az login output: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_code_and_url_are_extracted_from_az_output() {
+        let line = "WARNING: To sign in, use a web browser to open the page https://microsoft.com/devicelogin \
+                    and enter the code F4GQ5ZLTN to authenticate.";
+        assert_eq!(
+            parse_device_code(line),
+            Some(DeviceCode {
+                code: "F4GQ5ZLTN".to_string(),
+                url: "https://microsoft.com/devicelogin".to_string(),
+            })
+        );
+        assert_eq!(parse_device_code("Retrieving tenants and subscriptions for the selection..."), None);
+    }
+}
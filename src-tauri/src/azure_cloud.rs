@@ -0,0 +1,71 @@
+use serde::Serialize;
+
+/// The Azure cloud an `az` login belongs to, with the endpoints that differ between clouds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AzureCloudInfo {
+    pub cloud: String,
+    pub arm_base_url: String,
+    pub portal_base_url: String,
+}
+
+// `environmentName` from `az account show` -> (cloud, ARM endpoint, portal)
+const CLOUDS: [(&str, &str, &str, &str); 3] = [
+    ("AzureCloud", "Public", "https://management.azure.com", "https://portal.azure.com"),
+    (
+        "AzureUSGovernment",
+        "USGovernment",
+        "https://management.usgovcloudapi.net",
+        "https://portal.azure.us",
+    ),
+    (
+        "AzureChinaCloud",
+        "China",
+        "https://management.chinacloudapi.cn",
+        "https://portal.azure.cn",
+    ),
+];
+
+impl AzureCloudInfo {
+    /// Reads `environmentName` from an `az account show` payload. Missing or unrecognised
+    /// names fall back to the public cloud, which is what az itself defaults to.
+    pub fn from_account(account: &serde_json::Value) -> Self {
+        let environment = account
+            .get("environmentName")
+            .and_then(|name| name.as_str())
+            .unwrap_or_default();
+        let (_, cloud, arm, portal) = CLOUDS
+            .iter()
+            .find(|(name, ..)| name.eq_ignore_ascii_case(environment))
+            .unwrap_or(&CLOUDS[0]);
+        AzureCloudInfo {
+            cloud: cloud.to_string(),
+            arm_base_url: arm.to_string(),
+            portal_base_url: portal.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn us_government_account_uses_gov_endpoints() {
+        let account = serde_json::json!({
+            "environmentName": "AzureUSGovernment",
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "Gov Subscription"
+        });
+        let info = AzureCloudInfo::from_account(&account);
+        assert_eq!(info.cloud, "USGovernment");
+        assert_eq!(info.arm_base_url, "https://management.usgovcloudapi.net");
+        assert_eq!(info.portal_base_url, "https://portal.azure.us");
+    }
+
+    #[test]
+    fn missing_environment_defaults_to_public() {
+        let info = AzureCloudInfo::from_account(&serde_json::json!({}));
+        assert_eq!(info.cloud, "Public");
+        assert_eq!(info.arm_base_url, "https://management.azure.com");
+    }
+}
@@ -0,0 +1,232 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::preferences::PreferencesState;
+
+// Upper bound on benchmark runs; az startup alone can take seconds each
+const MAX_ITERATIONS: u32 = 20;
+
+// Caps on HTTP benchmarks so a typo can't turn into a flood against someone's API
+const MAX_HTTP_REQUESTS: u32 = 1000;
+const MAX_HTTP_CONCURRENCY: u32 = 32;
+
+/// Timing summary of repeated runs, in milliseconds
+#[derive(Debug, Serialize)]
+pub struct LatencyStats {
+    pub iterations: u32,
+    // Runs where the command ran but exited non-zero; still timed
+    pub failures: u32,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+fn latency_stats(samples: &[Duration], failures: u32) -> LatencyStats {
+    let millis: Vec<f64> = samples.iter().map(|d| d.as_micros() as f64 / 1000.0).collect();
+    let min_ms = millis.iter().copied().fold(f64::INFINITY, f64::min);
+    let max_ms = millis.iter().copied().fold(0.0, f64::max);
+    let mean_ms = millis.iter().sum::<f64>() / millis.len().max(1) as f64;
+    LatencyStats {
+        iterations: samples.len() as u32,
+        failures,
+        min_ms: if millis.is_empty() { 0.0 } else { min_ms },
+        mean_ms,
+        max_ms,
+    }
+}
+
+// Times `az account show` with the same env the auth check uses. Runs are serial so
+// concurrent CLIs don't contend for the az config files and skew the numbers.
+#[tauri::command]
+pub async fn benchmark_az_auth(
+    prefs: tauri::State<'_, PreferencesState>,
+    iterations: u32,
+) -> Result<LatencyStats, String> {
+    let iterations = iterations.clamp(1, MAX_ITERATIONS);
+    let tool_info = crate::locate_tool("az".to_string(), &prefs.get()).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure CLI not available".to_string()));
+    }
+    let az_path = tool_info.path.unwrap();
+    let env = crate::azure_tool_env();
+
+    let mut samples = Vec::with_capacity(iterations as usize);
+    let mut failures = 0;
+    for _ in 0..iterations {
+        let started = Instant::now();
+        let output = tokio::process::Command::new(&az_path)
+            .args(["account", "show"])
+            .envs(&env)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to execute az: {}", e))?;
+        samples.push(started.elapsed());
+        if !output.status.success() {
+            failures += 1;
+        }
+    }
+
+    Ok(latency_stats(&samples, failures))
+}
+
+/// Throughput and latency of a burst of GETs; latencies are of successful requests only
+#[derive(Debug, Serialize)]
+pub struct HttpBenchmark {
+    pub requests: u32,
+    pub concurrency: u32,
+    // Transport failures and non-2xx responses
+    pub errors: u32,
+    pub elapsed_ms: f64,
+    pub requests_per_sec: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+// Nearest-rank percentile of sorted millisecond samples
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// Sends `total` GETs to `url` from `concurrency` workers sharing one client
+async fn run_http_benchmark(client: reqwest::Client, url: &str, concurrency: u32, total: u32) -> HttpBenchmark {
+    let remaining = Arc::new(AtomicU32::new(total));
+    let started = Instant::now();
+    let workers: Vec<_> = (0..concurrency.min(total))
+        .map(|_| {
+            let client = client.clone();
+            let url = url.to_string();
+            let remaining = remaining.clone();
+            tokio::spawn(async move {
+                let mut samples = Vec::new();
+                let mut errors = 0u32;
+                while remaining.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                    let sent = Instant::now();
+                    let ok = match client.get(&url).send().await {
+                        Ok(response) => response.status().is_success() && response.bytes().await.is_ok(),
+                        Err(_) => false,
+                    };
+                    if ok {
+                        samples.push(sent.elapsed().as_micros() as f64 / 1000.0);
+                    } else {
+                        errors += 1;
+                    }
+                }
+                (samples, errors)
+            })
+        })
+        .collect();
+
+    let mut samples = Vec::with_capacity(total as usize);
+    let mut errors = 0;
+    for worker in workers {
+        match worker.await {
+            Ok((worker_samples, worker_errors)) => {
+                samples.extend(worker_samples);
+                errors += worker_errors;
+            }
+            Err(_) => errors += 1,
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    samples.sort_by(f64::total_cmp);
+
+    HttpBenchmark {
+        requests: total,
+        concurrency: concurrency.min(total),
+        errors,
+        elapsed_ms: elapsed * 1000.0,
+        requests_per_sec: if elapsed > 0.0 { total as f64 / elapsed } else { 0.0 },
+        p50_ms: percentile(&samples, 50.0),
+        p95_ms: percentile(&samples, 95.0),
+        p99_ms: percentile(&samples, 99.0),
+    }
+}
+
+// Fires `total_requests` GETs at `url`, `concurrency` at a time, to see how hard an API can be
+// polled. Both are clamped (1000 requests, 32 at once) and each request uses the HTTP timeout
+// preference.
+#[tauri::command]
+pub async fn benchmark_http(
+    prefs: tauri::State<'_, PreferencesState>,
+    url: String,
+    concurrency: u32,
+    total_requests: u32,
+) -> Result<HttpBenchmark, String> {
+    let parsed = reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Only http and https URLs can be benchmarked, got {}", url));
+    }
+    let total = total_requests.clamp(1, MAX_HTTP_REQUESTS);
+    let concurrency = concurrency.clamp(1, MAX_HTTP_CONCURRENCY);
+    let client = crate::http::build_client(Duration::from_secs(prefs.get().http_timeout_secs), false)?;
+    Ok(run_http_benchmark(client, &url, concurrency, total).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_are_returned_in_order() {
+        let samples = [
+            Duration::from_millis(900),
+            Duration::from_millis(300),
+            Duration::from_millis(600),
+        ];
+        let stats = latency_stats(&samples, 1);
+        assert_eq!(stats.iterations, 3);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.min_ms, 300.0);
+        assert_eq!(stats.mean_ms, 600.0);
+        assert_eq!(stats.max_ms, 900.0);
+        assert!(stats.min_ms <= stats.mean_ms && stats.mean_ms <= stats.max_ms);
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let samples: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&samples, 50.0), 50.0);
+        assert_eq!(percentile(&samples, 95.0), 95.0);
+        assert_eq!(percentile(&samples, 99.0), 99.0);
+        assert_eq!(percentile(&[7.0], 99.0), 7.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn http_benchmark_counts_requests_and_errors() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            // Every third request fails
+            for n in 0.. {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let status = if n % 3 == 2 { "500 Internal Server Error" } else { "200 OK" };
+                tokio::spawn(async move {
+                    let mut request = [0u8; 4096];
+                    let _ = socket.read(&mut request).await;
+                    let response = format!("HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}", status);
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        let client = crate::http::build_client(Duration::from_secs(5), false).unwrap();
+        let result = run_http_benchmark(client, &url, 3, 9).await;
+        assert_eq!(result.requests, 9);
+        assert_eq!(result.concurrency, 3);
+        assert_eq!(result.errors, 3);
+        assert!(result.requests_per_sec > 0.0);
+        assert!(result.p50_ms > 0.0);
+        assert!(result.p50_ms <= result.p95_ms && result.p95_ms <= result.p99_ms);
+    }
+}
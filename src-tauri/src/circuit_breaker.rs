@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::CommandError;
+
+// Consecutive failures before a host's circuit opens
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+// How long an open circuit rejects requests before letting a trial request through
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct HostCircuit {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+/// Per-host circuit breakers for outgoing HTTP requests, kept in managed state.
+pub struct CircuitBreakers {
+    failure_threshold: u32,
+    cooldown: Duration,
+    hosts: Mutex<HashMap<String, HostCircuit>>,
+}
+
+impl Default for CircuitBreakers {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN)
+    }
+}
+
+impl CircuitBreakers {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreakers {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fails with `CircuitOpen` while the host's circuit is open. Once the cooldown has
+    /// passed, a single trial request is let through; its outcome closes or re-opens the circuit.
+    /// The returned permit reports that outcome.
+    pub fn check(&self, host: &str) -> Result<CircuitPermit<'_>, CommandError> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let Some(circuit) = hosts.get_mut(host) else {
+            return Ok(self.permit(host, false));
+        };
+
+        match state_of(circuit, self.cooldown) {
+            CircuitState::Closed => Ok(self.permit(host, false)),
+            CircuitState::Open => {
+                let elapsed = circuit.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+                let remaining = self.cooldown.saturating_sub(elapsed);
+                Err(CommandError::CircuitOpen(format!(
+                    "Circuit open for {} after {} consecutive failures; retry in {}s",
+                    host,
+                    circuit.consecutive_failures,
+                    remaining.as_secs().max(1)
+                )))
+            }
+            CircuitState::HalfOpen => {
+                if circuit.trial_in_flight {
+                    return Err(CommandError::CircuitOpen(format!(
+                        "Circuit half-open for {}; a trial request is already in flight",
+                        host
+                    )));
+                }
+                circuit.trial_in_flight = true;
+                Ok(self.permit(host, true))
+            }
+        }
+    }
+
+    fn permit(&self, host: &str, trial: bool) -> CircuitPermit<'_> {
+        CircuitPermit {
+            breakers: self,
+            host: host.to_string(),
+            trial,
+        }
+    }
+
+    pub fn record_success(&self, host: &str) {
+        self.hosts.lock().unwrap().remove(host);
+    }
+
+    pub fn record_failure(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let circuit = hosts.entry(host.to_string()).or_default();
+        circuit.consecutive_failures += 1;
+        circuit.trial_in_flight = false;
+        if circuit.consecutive_failures >= self.failure_threshold {
+            circuit.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Permission from `check` to send one request to a host. A half-open trial dropped without an
+/// outcome, e.g. because its request was cancelled, frees the trial slot so the next request can
+/// try the host instead of the circuit staying stuck.
+#[must_use]
+pub struct CircuitPermit<'a> {
+    breakers: &'a CircuitBreakers,
+    host: String,
+    trial: bool,
+}
+
+impl CircuitPermit<'_> {
+    pub fn record_success(mut self) {
+        self.breakers.record_success(&self.host);
+        self.trial = false;
+    }
+
+    pub fn record_failure(mut self) {
+        self.breakers.record_failure(&self.host);
+        self.trial = false;
+    }
+}
+
+impl Drop for CircuitPermit<'_> {
+    fn drop(&mut self) {
+        if self.trial {
+            if let Some(circuit) = self.breakers.hosts.lock().unwrap().get_mut(&self.host) {
+                circuit.trial_in_flight = false;
+            }
+        }
+    }
+}
+
+fn state_of(circuit: &HostCircuit, cooldown: Duration) -> CircuitState {
+    match circuit.opened_at {
+        None => CircuitState::Closed,
+        Some(opened_at) if opened_at.elapsed() >= cooldown => CircuitState::HalfOpen,
+        Some(_) => CircuitState::Open,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_open<T>(result: Result<T, CommandError>) -> bool {
+        matches!(result, Err(CommandError::CircuitOpen(_)))
+    }
+
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let breakers = CircuitBreakers::new(3, Duration::from_secs(60));
+        breakers.record_failure("https://a:443");
+        breakers.record_failure("https://a:443");
+        assert!(breakers.check("https://a:443").is_ok());
+
+        breakers.record_failure("https://a:443");
+        assert!(is_open(breakers.check("https://a:443")));
+        // Other hosts are unaffected
+        assert!(breakers.check("https://b:443").is_ok());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breakers = CircuitBreakers::new(2, Duration::from_secs(60));
+        breakers.record_failure("h");
+        breakers.record_success("h");
+        breakers.record_failure("h");
+        assert!(breakers.check("h").is_ok());
+    }
+
+    #[test]
+    fn half_open_lets_a_single_trial_through() {
+        let breakers = CircuitBreakers::new(1, Duration::ZERO);
+        breakers.record_failure("h");
+        let trial = breakers.check("h").unwrap();
+        assert!(is_open(breakers.check("h")));
+
+        // A successful trial closes the circuit again
+        trial.record_success();
+        assert!(breakers.check("h").is_ok());
+        assert!(breakers.check("h").is_ok());
+    }
+
+    #[test]
+    fn failed_trial_reopens_the_circuit() {
+        let cooldown = Duration::from_millis(50);
+        let breakers = CircuitBreakers::new(1, cooldown);
+        breakers.record_failure("h");
+        std::thread::sleep(cooldown);
+        breakers.check("h").unwrap().record_failure();
+        assert!(is_open(breakers.check("h")));
+
+        // The trial is no longer in flight, so the next one may go once the cooldown passes
+        std::thread::sleep(cooldown);
+        assert!(breakers.check("h").is_ok());
+    }
+
+    #[test]
+    fn abandoned_trial_frees_the_host() {
+        let breakers = CircuitBreakers::new(1, Duration::ZERO);
+        breakers.record_failure("h");
+        let trial = breakers.check("h").unwrap();
+        assert!(is_open(breakers.check("h")));
+        drop(trial);
+
+        let retry = breakers.check("h").unwrap();
+        retry.record_success();
+        assert!(breakers.check("h").is_ok());
+    }
+
+    #[test]
+    fn state_follows_the_cooldown() {
+        let closed = HostCircuit::default();
+        assert_eq!(state_of(&closed, DEFAULT_COOLDOWN), CircuitState::Closed);
+
+        let opened = HostCircuit {
+            consecutive_failures: 5,
+            opened_at: Some(Instant::now()),
+            trial_in_flight: false,
+        };
+        assert_eq!(state_of(&opened, DEFAULT_COOLDOWN), CircuitState::Open);
+        assert_eq!(state_of(&opened, Duration::ZERO), CircuitState::HalfOpen);
+    }
+}
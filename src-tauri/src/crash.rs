@@ -0,0 +1,102 @@
+use std::process::ExitStatus;
+
+use serde::{Deserialize, Serialize};
+
+/// How a tool died when a signal, rather than its own exit, ended it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashInfo {
+    pub signal: i32,
+    // e.g. "SIGSEGV"; "signal N" for numbers without a known name
+    pub signal_name: String,
+    pub core_dumped: bool,
+    // Where to look for the core dump, or how to get one next time
+    pub hint: Option<String>,
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGSYS => "SIGSYS",
+        _ => return format!("signal {}", signal),
+    };
+    name.to_string()
+}
+
+// Signals whose default action writes a core dump
+#[cfg(unix)]
+fn dumps_core(signal: i32) -> bool {
+    [
+        libc::SIGQUIT,
+        libc::SIGILL,
+        libc::SIGTRAP,
+        libc::SIGABRT,
+        libc::SIGBUS,
+        libc::SIGFPE,
+        libc::SIGSEGV,
+        libc::SIGXCPU,
+        libc::SIGXFSZ,
+        libc::SIGSYS,
+    ]
+    .contains(&signal)
+}
+
+#[cfg(unix)]
+fn core_dump_hint(signal: i32, core_dumped: bool) -> Option<String> {
+    if core_dumped {
+        // Linux says where cores go in core_pattern; a leading `|` pipes them to a handler
+        // such as systemd-coredump instead of writing a file
+        let pattern = std::fs::read_to_string("/proc/sys/kernel/core_pattern").ok();
+        return Some(match pattern.as_deref().map(str::trim) {
+            Some(pattern) if pattern.contains("systemd-coredump") => {
+                "A core dump was written; list it with `coredumpctl list`".to_string()
+            }
+            Some(pattern) if pattern.starts_with('|') => {
+                format!("A core dump was handed to `{}`", pattern.trim_start_matches('|'))
+            }
+            Some(pattern) if !pattern.is_empty() => {
+                format!("A core dump was written following the core pattern `{}`", pattern)
+            }
+            _ => "A core dump was written to the tool's working directory".to_string(),
+        });
+    }
+    dumps_core(signal)
+        .then(|| "No core dump was written; run `ulimit -c unlimited` before starting the app to get one".to_string())
+}
+
+/// Crash details for a run that a signal ended, or None when the process exited normally.
+/// Always None off Unix, where there are no signals.
+#[cfg(unix)]
+pub fn crash_info(status: &ExitStatus) -> Option<CrashInfo> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let signal = status.signal()?;
+    let core_dumped = status.core_dumped();
+    Some(CrashInfo {
+        signal,
+        signal_name: signal_name(signal),
+        core_dumped,
+        hint: core_dump_hint(signal, core_dumped),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn crash_info(_status: &ExitStatus) -> Option<CrashInfo> {
+    None
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Error returned by commands where the frontend needs to tell failure kinds apart.
+/// Serialized as `{ "kind": "...", "message": "..." }`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum CommandError {
+    /// Requests to a host are being short-circuited after repeated failures
+    CircuitOpen(String),
+    /// The caller cancelled the operation before it finished
+    Cancelled(String),
+    /// The Azure CLI isn't logged in; the user needs to run `az login`
+    AuthRequired(String),
+    /// A tool run took longer than its allowed time and was killed
+    Timeout(String),
+    /// A tool run hit a resource cap, e.g. the `max_buffered_output_bytes` preference or an
+    /// eval's memory/CPU limit
+    ResourceLimit(String),
+    /// Any other failure, carrying the same message a plain `String` error would
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::CircuitOpen(message)
+            | CommandError::Cancelled(message)
+            | CommandError::AuthRequired(message)
+            | CommandError::Timeout(message)
+            | CommandError::ResourceLimit(message)
+            | CommandError::Failed(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_kind_and_message() {
+        let error = CommandError::CircuitOpen("Circuit open for h".to_string());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "kind": "CircuitOpen", "message": "Circuit open for h" })
+        );
+    }
+
+    #[test]
+    fn plain_string_errors_become_failed() {
+        let error: CommandError = "boom".to_string().into();
+        assert!(matches!(&error, CommandError::Failed(message) if message == "boom"));
+        assert_eq!(error.to_string(), "boom");
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::Serialize;
+
+// Largest slice handed to the viewer in one call
+const MAX_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+
+/// A slice of a file for the virtualized viewer
+#[derive(Debug, Serialize)]
+pub struct FileChunk {
+    pub offset: u64,
+    // Decoded text of the slice; empty when `bytes` was requested instead
+    pub text: String,
+    // Set when the slice wasn't valid UTF-8, usually because it starts or ends mid-character
+    pub lossy: bool,
+    // Raw slice, only when the caller asked for it
+    pub bytes: Option<Vec<u8>>,
+    pub bytes_read: usize,
+    pub total_size: u64,
+}
+
+fn read_chunk(path: &Path, offset: u64, length: usize, raw: bool) -> Result<FileChunk, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let total_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if offset > total_size {
+        return Err(format!(
+            "Offset {} is beyond the end of {} ({} bytes)",
+            offset,
+            path.display(),
+            total_size
+        ));
+    }
+
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek in {}: {}", path.display(), e))?;
+    let mut buffer = Vec::with_capacity(length.min(MAX_CHUNK_BYTES));
+    file.take(length.min(MAX_CHUNK_BYTES) as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let bytes_read = buffer.len();
+    let (text, lossy, bytes) = if raw {
+        (String::new(), false, Some(buffer))
+    } else {
+        match String::from_utf8(buffer) {
+            Ok(text) => (text, false, None),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true, None),
+        }
+    };
+    Ok(FileChunk {
+        offset,
+        text,
+        lossy,
+        bytes,
+        bytes_read,
+        total_size,
+    })
+}
+
+// Reads up to `length` bytes (capped at 4 MiB) from `offset`, so multi-GB finder exports can be
+// previewed without loading them whole. `raw` returns the bytes instead of UTF-8 text.
+#[tauri::command]
+pub fn read_file_chunk(path: String, offset: u64, length: usize, raw: Option<bool>) -> Result<FileChunk, String> {
+    read_chunk(Path::new(&path), offset, length, raw.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_is_read_from_the_middle_of_a_file() {
+        let path = std::env::temp_dir().join(format!("skanyxx-chunk-{}.json", std::process::id()));
+        std::fs::write(&path, br#"[{"name":"vm-01"},{"name":"vm-02"},{"name":"vm-03"}]"#).unwrap();
+
+        let chunk = read_chunk(&path, 18, 16, false).unwrap();
+        assert_eq!(chunk.text, r#"{"name":"vm-02"}"#);
+        assert!(!chunk.lossy);
+        assert_eq!(chunk.total_size, 52);
+
+        let tail = read_chunk(&path, 50, 100, true).unwrap();
+        assert_eq!(tail.bytes.as_deref(), Some(&b"}]"[..]));
+        assert_eq!(tail.bytes_read, 2);
+
+        assert!(read_chunk(&path, 53, 1, false).unwrap_err().contains("beyond the end"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
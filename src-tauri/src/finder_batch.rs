@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::preferences::{Preferences, PreferencesState};
+use crate::CommandOutput;
+
+/// Outcome of one query in a finder batch; exactly one of the fields is set
+#[derive(Debug, Serialize)]
+pub struct FinderBatchResult {
+    pub output: Option<CommandOutput>,
+    pub error: Option<String>,
+}
+
+/// Results of the queries run before the batch finished or ran out of time
+#[derive(Debug, Serialize)]
+pub struct FinderBatch {
+    pub results: Vec<FinderBatchResult>,
+    pub deadline_exceeded: bool,
+    // Index of the query that was cancelled at the deadline; equals the number of queries when
+    // all of them ran
+    pub index_reached: usize,
+}
+
+// Runs the queries one after another. Once `deadline` passes the running finder is killed and
+// the rest are skipped.
+async fn run_queries(
+    azure_finder_path: &str,
+    queries: &[Vec<String>],
+    env: &HashMap<String, String>,
+    deadline: Option<Instant>,
+    prefs: &Preferences,
+) -> FinderBatch {
+    let mut results = Vec::with_capacity(queries.len());
+    for (index, args) in queries.iter().enumerate() {
+        let mut command = tokio::process::Command::from(crate::finder_command(
+            azure_finder_path,
+            args,
+            env.clone(),
+            false,
+            None,
+        ));
+        // Dropping the run at the deadline then kills the finder
+        command.kill_on_drop(true);
+        let cap = prefs.max_buffered_output_bytes;
+        let run = async {
+            let child = command
+                .spawn()
+                .map_err(|e| format!("Failed to execute azure-resource-finder: {}", e))?;
+            crate::output_limit::collect_output_async(child, cap)
+                .await
+                .map_err(|e| e.to_string())
+        };
+        let output = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, run).await {
+                Ok(output) => output,
+                Err(_) => {
+                    return FinderBatch {
+                        results,
+                        deadline_exceeded: true,
+                        index_reached: index,
+                    }
+                }
+            },
+            None => run.await,
+        };
+        results.push(match output {
+            Ok(output) => FinderBatchResult {
+                output: Some(crate::finder_output(&output, prefs)),
+                error: None,
+            },
+            Err(error) => FinderBatchResult {
+                output: None,
+                error: Some(error),
+            },
+        });
+    }
+    FinderBatch {
+        results,
+        deadline_exceeded: false,
+        index_reached: queries.len(),
+    }
+}
+
+// Runs several finder queries in order for a dashboard refresh, within an overall
+// `deadline_secs` budget for the whole batch rather than per query. When the budget runs out
+// the running query is cancelled and the results so far come back with `deadline_exceeded`.
+// Each query gets the finder's `default_args` unless `skip_defaults` is set.
+#[tauri::command]
+pub async fn run_azure_finder_batch(
+    prefs: tauri::State<'_, PreferencesState>,
+    queries: Vec<Vec<String>>,
+    deadline_secs: Option<u64>,
+    skip_defaults: Option<bool>,
+) -> Result<FinderBatch, String> {
+    let prefs = prefs.get();
+    let tool_info = crate::locate_tool("azure-resource-finder".to_string(), &prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure Resource Finder not available".to_string()));
+    }
+    let azure_finder_path = tool_info.path.unwrap();
+
+    let skip_defaults = skip_defaults.unwrap_or(false);
+    let queries: Vec<Vec<String>> = queries
+        .into_iter()
+        .map(|args| prefs.args_for("azure-resource-finder", args, skip_defaults))
+        .collect();
+    let deadline = deadline_secs.map(|secs| Instant::now() + Duration::from_secs(secs));
+    let env = crate::azure_tool_env();
+    Ok(run_queries(&azure_finder_path, &queries, &env, deadline, &prefs).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn batch_stops_at_the_deadline_with_partial_results() {
+        let query = vec!["-c".to_string(), "sleep 0.2; echo '[]'".to_string()];
+        let queries = vec![query; 5];
+        let env = HashMap::new();
+        let prefs = Preferences::default();
+
+        let started = Instant::now();
+        let deadline = Some(started + Duration::from_millis(500));
+        let batch = run_queries("sh", &queries, &env, deadline, &prefs).await;
+        assert!(started.elapsed() < Duration::from_millis(900));
+        assert!(batch.deadline_exceeded);
+        assert!(batch.index_reached >= 1 && batch.index_reached < queries.len());
+        assert_eq!(batch.results.len(), batch.index_reached);
+        assert!(batch.results.iter().all(|result| result.output.as_ref().is_some_and(|output| output.success)));
+
+        let batch = run_queries("sh", &queries[..2], &env, None, &prefs).await;
+        assert!(!batch.deadline_exceeded);
+        assert_eq!(batch.index_reached, 2);
+        assert_eq!(batch.results.len(), 2);
+    }
+}
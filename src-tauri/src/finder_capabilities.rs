@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::help_text::{help_output, parse_help};
+use crate::preferences::PreferencesState;
+
+// Flags that select an output format; any of them means the build can be asked for JSON
+const OUTPUT_FORMAT_FLAGS: [&str; 4] = ["--output", "-o", "--format", "--json"];
+
+/// Whether each resolved finder binary takes an output format flag. Kept in managed state.
+#[derive(Default)]
+pub struct FinderJsonSupport {
+    by_path: Mutex<HashMap<String, bool>>,
+}
+
+// False for help without any recognisable option rows, e.g. when `--help` itself failed
+fn supports_json(help: &str) -> bool {
+    parse_help(help)
+        .flags
+        .iter()
+        .any(|flag| OUTPUT_FORMAT_FLAGS.contains(&flag.as_str()))
+}
+
+// Probes `azure-resource-finder --help` for an output/format flag, so the app only asks for
+// parsed output from builds that can produce it and otherwise treats the output as raw text
+#[tauri::command]
+pub async fn finder_supports_json(
+    prefs: tauri::State<'_, PreferencesState>,
+    cache: tauri::State<'_, FinderJsonSupport>,
+) -> Result<bool, String> {
+    let tool_info = crate::locate_tool("azure-resource-finder".to_string(), &prefs.get()).await?;
+    let path = tool_info
+        .path
+        .ok_or_else(|| tool_info.error.unwrap_or_else(|| "Azure Resource Finder not available".to_string()))?;
+
+    if let Some(supported) = cache.by_path.lock().unwrap().get(&path) {
+        return Ok(*supported);
+    }
+    let supported = supports_json(&help_output(&path, &["--help"]).await);
+    cache.by_path.lock().unwrap().insert(path, supported);
+    Ok(supported)
+}
+
+/// Flags from `azure-resource-finder --help`, per binary path and the version it reports, so an
+/// upgrade in place is re-read. Kept in managed state.
+#[derive(Default)]
+pub struct FinderFlags {
+    by_version: Mutex<HashMap<String, Vec<String>>>,
+}
+
+/// Result of checking finder args against its help
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FinderArgCheck {
+    // Flags the help doesn't list, in the order given; warnings, since help can be incomplete
+    pub unrecognized: Vec<String>,
+    // False when the help had no readable flags, so nothing could be checked
+    pub checked: bool,
+}
+
+// Dash-led args missing from `known`, ignoring values such as negative numbers and everything
+// after a `--` separator. `--flag=value` is checked as `--flag`.
+fn check_args(args: &[String], known: &[String]) -> FinderArgCheck {
+    if known.is_empty() {
+        return FinderArgCheck {
+            unrecognized: Vec::new(),
+            checked: false,
+        };
+    }
+    let mut unrecognized = Vec::new();
+    for arg in args {
+        if arg == "--" {
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" || arg.parse::<f64>().is_ok() {
+            continue;
+        }
+        let flag = arg.split('=').next().unwrap_or(arg);
+        if !known.iter().any(|k| k == flag) && !unrecognized.iter().any(|u: &String| u == flag) {
+            unrecognized.push(flag.to_string());
+        }
+    }
+    FinderArgCheck {
+        unrecognized,
+        checked: true,
+    }
+}
+
+// Checks `args` against the flags `azure-resource-finder --help` lists, to catch typos before a
+// failed run. Unknown flags come back as warnings; the run isn't blocked.
+#[tauri::command]
+pub async fn validate_finder_args(
+    prefs: tauri::State<'_, PreferencesState>,
+    cache: tauri::State<'_, FinderFlags>,
+    args: Vec<String>,
+) -> Result<FinderArgCheck, String> {
+    let tool_info = crate::locate_tool("azure-resource-finder".to_string(), &prefs.get()).await?;
+    let path = tool_info
+        .path
+        .ok_or_else(|| tool_info.error.unwrap_or_else(|| "Azure Resource Finder not available".to_string()))?;
+
+    let version = crate::warmup::version_of(&path).await.unwrap_or_default();
+    let key = format!("{}@{}", path, version);
+    let cached = cache.by_version.lock().unwrap().get(&key).cloned();
+    let known = match cached {
+        Some(known) => known,
+        None => {
+            let known = parse_help(&help_output(&path, &["--help"]).await).flags;
+            cache.by_version.lock().unwrap().insert(key, known.clone());
+            known
+        }
+    };
+    Ok(check_args(&args, &known))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_flag_in_help_means_json_support() {
+        let with_output = "\
+Usage: azure-resource-finder [OPTIONS]
+
+Options:
+  -s, --subscription <ID>  Subscription to scan
+  -o, --output <FORMAT>    Output format: json, table [default: table]
+  -h, --help               Print help
+";
+        assert!(supports_json(with_output));
+
+        let without_output = "\
+Usage: azure-resource-finder [OPTIONS]
+
+Options:
+  -s, --subscription <ID>  Subscription to scan
+  -h, --help               Print help
+";
+        assert!(!supports_json(without_output));
+        assert!(!supports_json("error: unexpected argument"));
+    }
+
+    #[test]
+    fn unknown_flags_are_reported() {
+        let help = "\
+Usage: azure-resource-finder [OPTIONS]
+
+Options:
+  -s, --subscription <ID>  Subscription to scan
+  -o, --output <FORMAT>    Output format
+  -h, --help               Print help
+";
+        let known = parse_help(help).flags;
+        let args: Vec<String> = ["--subscription=abc", "-o", "json", "--subscripton", "x", "--", "--anything"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(
+            check_args(&args, &known),
+            FinderArgCheck {
+                unrecognized: vec!["--subscripton".to_string()],
+                checked: true,
+            }
+        );
+        assert!(!check_args(&args, &[]).checked);
+    }
+}
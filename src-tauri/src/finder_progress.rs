@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::jobs::JobRegistry;
+
+// How often the log is checked for new lines once the end has been reached
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Phases after which the finder writes no more progress
+const FINAL_PHASES: [&str; 4] = ["done", "complete", "completed", "failed"];
+
+/// One JSON line of the finder's progress log, emitted as a `finder-progress` event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinderProgress {
+    pub phase: String,
+    #[serde(default)]
+    pub processed: u64,
+    // Unknown until the finder has enumerated what it will scan
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default, alias = "currentItem")]
+    pub current_item: Option<String>,
+}
+
+impl FinderProgress {
+    fn is_final(&self) -> bool {
+        FINAL_PHASES.iter().any(|phase| self.phase.eq_ignore_ascii_case(phase))
+    }
+}
+
+// Splits appended log bytes into progress entries. A line still being written at the end of
+// the file is held back until its newline arrives; lines that aren't progress JSON are skipped.
+#[derive(Debug, Default)]
+struct ProgressLines {
+    pending: Vec<u8>,
+}
+
+impl ProgressLines {
+    fn feed(&mut self, chunk: &[u8]) -> Vec<FinderProgress> {
+        self.pending.extend_from_slice(chunk);
+        let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let complete: Vec<u8> = self.pending.drain(..=end).collect();
+        complete
+            .split(|&b| b == b'\n')
+            .filter_map(|line| serde_json::from_str(String::from_utf8_lossy(line).trim()).ok())
+            .collect()
+    }
+}
+
+// Tails `path` from the start, passing each progress entry to `emit`, until a final phase is
+// logged. Waits for the file if the finder hasn't created it yet, and starts over if it's
+// truncated. Returns how many entries were emitted.
+async fn follow(path: &Path, poll: Duration, mut emit: impl FnMut(FinderProgress)) -> Result<usize, String> {
+    let mut file = loop {
+        match tokio::fs::File::open(path).await {
+            Ok(file) => break file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => tokio::time::sleep(poll).await,
+            Err(e) => return Err(format!("Failed to open {}: {}", path.display(), e)),
+        }
+    };
+
+    let mut lines = ProgressLines::default();
+    let mut offset = 0u64;
+    let mut emitted = 0;
+    let mut chunk = vec![0u8; 8192];
+    loop {
+        let read = file
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            let len = file.metadata().await.map(|metadata| metadata.len()).unwrap_or(offset);
+            if len < offset {
+                file.seek(std::io::SeekFrom::Start(0))
+                    .await
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                offset = 0;
+                lines = ProgressLines::default();
+            } else {
+                tokio::time::sleep(poll).await;
+            }
+            continue;
+        }
+        offset += read as u64;
+        for progress in lines.feed(&chunk[..read]) {
+            let done = progress.is_final();
+            emit(progress);
+            emitted += 1;
+            if done {
+                return Ok(emitted);
+            }
+        }
+    }
+}
+
+// Follows a JSON-lines progress log written by azure-resource-finder, emitting each line as a
+// typed `finder-progress` event, until the log reports a final phase (`done`, `completed`,
+// `failed`). Runs as a job, so `cancel_all_jobs` stops it early.
+#[tauri::command]
+pub async fn follow_finder_progress(
+    app: AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    log_path: String,
+) -> Result<usize, String> {
+    let path = PathBuf::from(log_path);
+    jobs.run(
+        "follow_finder_progress",
+        follow(&path, POLL_INTERVAL, |progress| {
+            let _ = app.emit("finder-progress", progress);
+        }),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_lines_wait_for_their_newline() {
+        let mut lines = ProgressLines::default();
+        let parsed = lines.feed(b"{\"phase\":\"enumerating\",\"processed\":0}\n{\"phase\":\"scan");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].phase, "enumerating");
+        assert_eq!(parsed[0].total, None);
+
+        let parsed = lines.feed(b"ning\",\"processed\":3,\"total\":10,\"currentItem\":\"rg-web\"}\nnot json\n");
+        assert_eq!(
+            parsed,
+            [FinderProgress {
+                phase: "scanning".to_string(),
+                processed: 3,
+                total: Some(10),
+                current_item: Some("rg-web".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn log_is_followed_until_the_final_phase() {
+        let path = std::env::temp_dir().join(format!("skanyxx-progress-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let writer_path = path.clone();
+        let writer = tokio::spawn(async move {
+            // Written in pieces after the reader has started, like a running finder
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            std::fs::write(&writer_path, "{\"phase\":\"scanning\",\"processed\":1,\"total\":2}\n{\"pha").unwrap();
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            let mut log = std::fs::OpenOptions::new().append(true).open(&writer_path).unwrap();
+            std::io::Write::write_all(&mut log, b"se\":\"done\",\"processed\":2,\"total\":2}\n").unwrap();
+        });
+
+        let mut events = Vec::new();
+        let emitted = follow(&path, Duration::from_millis(5), |progress| events.push(progress))
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(emitted, 2);
+        let phases: Vec<&str> = events.iter().map(|progress| progress.phase.as_str()).collect();
+        assert_eq!(phases, ["scanning", "done"]);
+        assert_eq!(events[1].processed, 2);
+    }
+}
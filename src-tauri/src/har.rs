@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::recording::{is_secret_key, REDACTED};
+
+/// The request half of a recorded `http_request` call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpEntryRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpEntryResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// One request/response pair, as returned under `entry` by `http_request` with `record` set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpEntry {
+    // Unix time the request started, in milliseconds
+    pub started_ms: u64,
+    pub time_ms: f64,
+    pub request: HttpEntryRequest,
+    pub response: HttpEntryResponse,
+}
+
+fn is_credential_header(name: &str) -> bool {
+    name.eq_ignore_ascii_case("authorization")
+        || name.eq_ignore_ascii_case("proxy-authorization")
+        || is_secret_key(name)
+}
+
+fn har_headers<'a>(headers: impl Iterator<Item = (&'a String, &'a String)>) -> Vec<Value> {
+    let mut headers: Vec<Value> = headers
+        .map(|(name, value)| {
+            let value = if is_credential_header(name) { REDACTED } else { value.as_str() };
+            json!({ "name": name, "value": value })
+        })
+        .collect();
+    headers.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    headers
+}
+
+fn header_value<'a>(
+    headers: impl IntoIterator<Item = (&'a String, &'a String)>,
+    name: &str,
+) -> Option<&'a str> {
+    headers
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+// RFC 3339 UTC timestamp from Unix milliseconds (days-to-civil from Howard Hinnant's algorithms)
+fn iso8601(ms: u64) -> String {
+    let secs = ms / 1000;
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        ms % 1000
+    )
+}
+
+fn har_entry(entry: &HttpEntry) -> Value {
+    let request = &entry.request;
+    let response = &entry.response;
+    let query: Vec<Value> = reqwest::Url::parse(&request.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut har_request = json!({
+        "method": request.method,
+        "url": request.url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": har_headers(request.headers.iter()),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": request.body.as_ref().map_or(0, |body| body.len() as i64),
+    });
+    if let Some(body) = &request.body {
+        let mime = header_value(&request.headers, "content-type").unwrap_or("application/json");
+        har_request["postData"] = json!({ "mimeType": mime, "text": body });
+    }
+
+    let response_headers = response.headers.iter().map(|(name, value)| (name, value));
+    let mime = header_value(response_headers.clone(), "content-type").unwrap_or("application/json");
+    let redirect = header_value(response_headers.clone(), "location").unwrap_or_default();
+    let status_text = reqwest::StatusCode::from_u16(response.status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    json!({
+        "startedDateTime": iso8601(entry.started_ms),
+        "time": entry.time_ms,
+        "request": har_request,
+        "response": {
+            "status": response.status,
+            "statusText": status_text,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": har_headers(response_headers),
+            "content": { "size": response.body.len(), "mimeType": mime, "text": response.body },
+            "redirectURL": redirect,
+            "headersSize": -1,
+            "bodySize": -1,
+        },
+        "cache": {},
+        // Only the total is measured, so it all counts as waiting on the server
+        "timings": { "send": 0, "wait": entry.time_ms, "receive": 0 },
+    })
+}
+
+// A HAR 1.2 document for `entries`, with credential headers redacted
+fn to_har(entries: &[HttpEntry]) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "skanyxx", "version": env!("CARGO_PKG_VERSION") },
+            "entries": entries.iter().map(har_entry).collect::<Vec<_>>(),
+        }
+    })
+}
+
+// Saves recorded `http_request` calls as a HAR file for HAR viewers and browser dev tools.
+// Authorization and other credential headers are written as `[REDACTED]`.
+#[tauri::command]
+pub fn export_http_har(entries: Vec<HttpEntry>, path: String) -> Result<usize, String> {
+    let path = Path::new(&path);
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let har = serde_json::to_string_pretty(&to_har(&entries))
+        .map_err(|e| format!("Failed to serialize HAR: {}", e))?;
+    fs::write(path, har).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_file_is_har_with_credentials_redacted() {
+        let entry = HttpEntry {
+            started_ms: 1_704_110_400_123,
+            time_ms: 42.5,
+            request: HttpEntryRequest {
+                method: "GET".to_string(),
+                url: "https://management.azure.com/subscriptions?api-version=2020-01-01".to_string(),
+                headers: HashMap::from([("Authorization".to_string(), "Bearer secret-token".to_string())]),
+                body: None,
+            },
+            response: HttpEntryResponse {
+                status: 200,
+                headers: vec![("content-type".to_string(), "application/json; charset=utf-8".to_string())],
+                body: r#"{"value":[]}"#.to_string(),
+            },
+        };
+        let path = std::env::temp_dir().join(format!("skanyxx-{}.har", std::process::id()));
+        let count = export_http_har(vec![entry.clone(), entry], path.to_string_lossy().into_owned()).unwrap();
+        assert_eq!(count, 2);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(!contents.contains("secret-token"));
+        let har: Value = serde_json::from_str(&contents).unwrap();
+        let log = &har["log"];
+        assert_eq!(log["version"], "1.2");
+        assert_eq!(log["entries"].as_array().unwrap().len(), 2);
+
+        let first = &log["entries"][0];
+        assert_eq!(first["startedDateTime"], "2024-01-01T12:00:00.123Z");
+        assert_eq!(first["request"]["method"], "GET");
+        assert_eq!(first["request"]["headers"][0]["value"], REDACTED);
+        assert_eq!(first["request"]["queryString"][0]["name"], "api-version");
+        assert_eq!(first["response"]["status"], 200);
+        assert_eq!(first["response"]["statusText"], "OK");
+        assert_eq!(first["response"]["content"]["mimeType"], "application/json; charset=utf-8");
+        assert_eq!(first["response"]["content"]["text"], r#"{"value":[]}"#);
+        for field in ["startedDateTime", "time", "request", "response", "cache", "timings"] {
+            assert!(first.get(field).is_some(), "{} missing", field);
+        }
+    }
+}
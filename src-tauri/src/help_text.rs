@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+/// A subcommand listed in a tool's `--help` output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HelpSubcommand {
+    pub name: String,
+    pub description: String,
+}
+
+/// Subcommands and flags read from a tool's `--help` output
+#[derive(Debug, Default, PartialEq)]
+pub struct HelpText {
+    pub subcommands: Vec<HelpSubcommand>,
+    pub flags: Vec<String>,
+}
+
+// Reads clap-style help: a `Commands:` (or `SUBCOMMANDS:`) section of indented `name  description`
+// rows, and option rows starting with `-`
+pub fn parse_help(help: &str) -> HelpText {
+    let mut parsed = HelpText::default();
+    let mut in_commands = false;
+    for line in help.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            let header = trimmed.to_ascii_lowercase();
+            in_commands = header == "commands:" || header == "subcommands:";
+            continue;
+        }
+
+        if trimmed.starts_with('-') {
+            // `-e, --eval <EXPR>  Evaluate...`: every dash-led token before the description
+            let spec = trimmed.split("  ").next().unwrap_or(trimmed);
+            for token in spec.split([',', ' ']) {
+                let flag = token.split(['=', '[']).next().unwrap_or(token);
+                if flag.starts_with('-') && flag.len() > 1 && !parsed.flags.iter().any(|f| f == flag) {
+                    parsed.flags.push(flag.to_string());
+                }
+            }
+        } else if in_commands {
+            let (name, description) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+            // clap lists its own `help` pseudo-command
+            if name != "help" {
+                parsed.subcommands.push(HelpSubcommand {
+                    name: name.to_string(),
+                    description: description.trim().to_string(),
+                });
+            }
+        }
+    }
+    parsed
+}
+
+// Help text from stdout, or stderr for builds that print it there. Errors become empty help.
+pub async fn help_output(path: &str, args: &[&str]) -> String {
+    match tokio::process::Command::new(path).args(args).output().await {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if stdout.trim().is_empty() {
+                String::from_utf8_lossy(&output.stderr).into_owned()
+            } else {
+                stdout.into_owned()
+            }
+        }
+        Err(_) => String::new(),
+    }
+}
@@ -0,0 +1,881 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::circuit_breaker::CircuitBreakers;
+use crate::error::CommandError;
+use crate::har::{HttpEntry, HttpEntryRequest, HttpEntryResponse};
+use crate::http_presets::fill_placeholders;
+use crate::inflight::InFlight;
+use crate::preferences::PreferencesState;
+
+// Upper bound on retries so a single call can't turn into a flood against a struggling host
+const MAX_RETRIES: u32 = 5;
+// Linear backoff step between retry attempts
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+// Same cap reqwest's default redirect policy uses
+const MAX_REDIRECTS: usize = 10;
+
+pub(crate) const SUPPORTED_METHODS: [&str; 5] = ["GET", "POST", "PUT", "DELETE", "PATCH"];
+// Methods whose concurrent identical calls are coalesced; only reads are safe to share
+const DEDUPED_METHODS: [&str; 1] = ["GET"];
+
+/// In-flight `http_request` calls shared between identical concurrent callers, kept in managed state
+pub type InFlightRequests = InFlight<serde_json::Value>;
+
+/// `http_request` calls started with a `request_id`, so `cancel_http_request` can abort them.
+/// Kept in managed state.
+#[derive(Default)]
+pub struct ActiveRequests {
+    requests: Mutex<HashMap<String, oneshot::Sender<()>>>,
+}
+
+impl ActiveRequests {
+    // Runs `request`, resolving with `Cancelled` if `cancel` is called for its id first.
+    // Without an id the request just runs; it can't be cancelled.
+    async fn run<F>(&self, request_id: Option<String>, request: F) -> Result<serde_json::Value, CommandError>
+    where
+        F: Future<Output = Result<serde_json::Value, CommandError>>,
+    {
+        let Some(request_id) = request_id else {
+            return request.await;
+        };
+
+        let (cancel, cancelled) = oneshot::channel();
+        {
+            let mut requests = self.requests.lock().unwrap();
+            if requests.contains_key(&request_id) {
+                return Err(format!("A request with id {} is already in flight", request_id).into());
+            }
+            requests.insert(request_id.clone(), cancel);
+        }
+
+        let result = tokio::select! {
+            result = request => result,
+            _ = cancelled => Err(CommandError::Cancelled(format!("Request {} was cancelled", request_id))),
+        };
+        self.requests.lock().unwrap().remove(&request_id);
+        result
+    }
+
+    /// False when no request with that id is in flight
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.requests.lock().unwrap().remove(request_id) {
+            Some(cancel) => cancel.send(()).is_ok(),
+            None => false,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn cancel_http_request(active: tauri::State<'_, ActiveRequests>, request_id: String) -> bool {
+    active.cancel(&request_id)
+}
+
+/// What `http_request` supports, so the UI doesn't hardcode it
+#[derive(Debug, Serialize)]
+pub struct HttpCapabilities {
+    pub methods: Vec<String>,
+    // Methods whose identical concurrent calls share one request
+    pub deduplicated_methods: Vec<String>,
+    pub max_retries: u32,
+    pub default_timeout_secs: u64,
+    // Responses are buffered and parsed as JSON; there's no streaming or file download yet
+    pub streaming: bool,
+    pub download: bool,
+    // None when request bodies aren't size-limited
+    pub max_body_bytes: Option<u64>,
+    // Response encodings accepted and decoded
+    pub content_encodings: Vec<String>,
+}
+
+fn capabilities(timeout_secs: u64) -> HttpCapabilities {
+    HttpCapabilities {
+        methods: SUPPORTED_METHODS.iter().map(|m| m.to_string()).collect(),
+        deduplicated_methods: DEDUPED_METHODS.iter().map(|m| m.to_string()).collect(),
+        max_retries: MAX_RETRIES,
+        default_timeout_secs: timeout_secs,
+        streaming: false,
+        download: false,
+        max_body_bytes: None,
+        content_encodings: CONTENT_ENCODINGS.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[tauri::command]
+pub fn http_capabilities(prefs: tauri::State<'_, PreferencesState>) -> HttpCapabilities {
+    capabilities(prefs.get().http_timeout_secs)
+}
+
+const CONTENT_ENCODINGS: [&str; 3] = ["gzip", "deflate", "br"];
+
+// A successful response's JSON body and how it came over the wire
+#[derive(Debug)]
+struct HttpResponse {
+    json: serde_json::Value,
+    status: u16,
+    headers: Vec<(String, String)>,
+    // As sent by the server; only seen when the client isn't decoding transparently
+    content_encoding: Option<String>,
+    encoded_bytes: u64,
+    decoded_bytes: u64,
+    // When the response headers arrived and when the body had been read
+    first_byte_at: Instant,
+    finished_at: Instant,
+}
+
+impl HttpResponse {
+    fn into_value(
+        self,
+        report_encoding: bool,
+        timing: Option<RequestTiming>,
+        entry: Option<HttpEntry>,
+    ) -> serde_json::Value {
+        if !report_encoding && timing.is_none() && entry.is_none() {
+            return self.json;
+        }
+        let mut value = serde_json::json!({ "body": self.json });
+        if report_encoding {
+            value["content_encoding"] = self.content_encoding.into();
+            value["encoded_bytes"] = self.encoded_bytes.into();
+            value["decoded_bytes"] = self.decoded_bytes.into();
+        }
+        if let Some(timing) = timing {
+            value["timing"] = serde_json::to_value(timing).unwrap_or_default();
+        }
+        if let Some(entry) = entry {
+            value["entry"] = serde_json::to_value(entry).unwrap_or_default();
+        }
+        value
+    }
+
+    // The call as a request/response pair for `export_http_har`
+    fn entry(&self, started_at: SystemTime, started: Instant, request: HttpEntryRequest) -> HttpEntry {
+        HttpEntry {
+            started_ms: started_at
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_millis() as u64)
+                .unwrap_or_default(),
+            time_ms: self.finished_at.duration_since(started).as_secs_f64() * 1000.0,
+            request,
+            response: HttpEntryResponse {
+                status: self.status,
+                headers: self.headers.clone(),
+                body: self.json.to_string(),
+            },
+        }
+    }
+}
+
+/// Where a request's time went, in milliseconds from its start; each phase ends no earlier than
+/// the one before it
+#[derive(Debug, Serialize)]
+pub struct RequestTiming {
+    // Null when the client didn't look the host up itself: an IP literal, or a proxy that does
+    // its own lookup
+    pub dns_ms: Option<f64>,
+    // reqwest doesn't expose the connect or TLS handshake phases, so these are always null; their
+    // time is counted in `first_byte_ms`
+    pub connect_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub first_byte_ms: f64,
+    pub total_ms: f64,
+    // The requested URL followed by every redirect target
+    pub redirect_chain: Vec<String>,
+}
+
+// Times a request from the client it builds: DNS through the client's resolver, first byte and
+// total from the response. Nothing touches the network until the request itself is sent.
+struct TimingProbe {
+    started: Instant,
+    // A proxy's own lookup isn't the target's, so the resolver's timing is left out then
+    proxied: bool,
+    dns_done: Arc<Mutex<Option<Instant>>>,
+    redirects: Arc<Mutex<Vec<String>>>,
+}
+
+// Whether reqwest's system proxy, read from HTTP_PROXY/HTTPS_PROXY/ALL_PROXY in either case,
+// covers `scheme`. NO_PROXY isn't consulted, so an exempt host just goes without DNS timing.
+fn env_proxy_applies(scheme: &str, var: impl Fn(&str) -> Option<String>) -> bool {
+    let scheme_var = format!("{}_proxy", scheme);
+    [scheme_var.as_str(), "all_proxy"].iter().any(|name| {
+        [name.to_string(), name.to_uppercase()]
+            .iter()
+            .any(|name| var(name).is_some_and(|value| !value.trim().is_empty()))
+    })
+}
+
+// The system resolver, noting when the first lookup finishes. Redirects to another host resolve
+// again, but only the first lookup counts as the request's DNS phase.
+struct TimedResolver {
+    dns_done: Arc<Mutex<Option<Instant>>>,
+}
+
+impl reqwest::dns::Resolve for TimedResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let dns_done = self.dns_done.clone();
+        Box::pin(async move {
+            // The connector fills in the real port on every address
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            dns_done.lock().unwrap().get_or_insert_with(Instant::now);
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(addrs)
+        })
+    }
+}
+
+impl TimingProbe {
+    fn start(url: &str) -> Self {
+        let proxied = reqwest::Url::parse(url)
+            .is_ok_and(|parsed| env_proxy_applies(parsed.scheme(), |name| std::env::var(name).ok()));
+        TimingProbe {
+            started: Instant::now(),
+            proxied,
+            dns_done: Arc::new(Mutex::new(None)),
+            redirects: Arc::new(Mutex::new(vec![url.to_string()])),
+        }
+    }
+
+    // A client that records its lookups and each redirect it follows into this probe
+    fn client(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::Client, String> {
+        let redirects = self.redirects.clone();
+        builder
+            .dns_resolver(Arc::new(TimedResolver { dns_done: self.dns_done.clone() }))
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() > MAX_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+                redirects.lock().unwrap().push(attempt.url().to_string());
+                attempt.follow()
+            }))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+
+    fn timing(&self, response: &HttpResponse) -> RequestTiming {
+        let ms = |at: Instant| at.duration_since(self.started).as_secs_f64() * 1000.0;
+        RequestTiming {
+            dns_ms: (*self.dns_done.lock().unwrap()).filter(|_| !self.proxied).map(ms),
+            connect_ms: None,
+            tls_ms: None,
+            first_byte_ms: ms(response.first_byte_at),
+            total_ms: ms(response.finished_at),
+            redirect_chain: self.redirects.lock().unwrap().clone(),
+        }
+    }
+}
+
+// Normally reqwest decompresses transparently, which also strips Content-Encoding. To report
+// the encoding the client leaves bodies alone and `decode_body` does the work instead.
+fn client_builder(timeout: Duration, report_encoding: bool) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder().timeout(timeout);
+    if report_encoding {
+        builder.no_gzip().no_deflate().no_brotli()
+    } else {
+        builder
+    }
+}
+
+pub(crate) fn build_client(timeout: Duration, report_encoding: bool) -> Result<reqwest::Client, String> {
+    client_builder(timeout, report_encoding)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// Proxy, CA and timeout settings to check with `validate_http_config`
+#[derive(Debug, Default, Deserialize)]
+pub struct HttpClientConfig {
+    // Used for every scheme, e.g. http://proxy.corp:8080
+    #[serde(default)]
+    pub proxy: Option<String>,
+    // PEM file with an extra root certificate, e.g. a corporate TLS-inspection CA
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Whether a client could be built from an `HttpClientConfig`, with every problem found
+#[derive(Debug, Serialize)]
+pub struct HttpConfigValidation {
+    pub valid: bool,
+    pub errors: Vec<String>,
+}
+
+fn load_ca_cert(path: &str) -> Result<reqwest::Certificate, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA certificate {}: {}", path, e))?;
+    reqwest::Certificate::from_pem(&pem).map_err(|e| format!("Invalid CA certificate {}: {}", path, e))
+}
+
+// Checks each setting on its own so all problems are reported at once, then builds a client
+// from them to catch anything only construction rejects. Nothing is sent.
+fn validate_client_config(config: &HttpClientConfig) -> HttpConfigValidation {
+    let mut errors = Vec::new();
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = config.proxy.as_deref() {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => errors.push(format!("Invalid proxy URL {}: {}", proxy, e)),
+        }
+    }
+    if let Some(path) = config.ca_cert_path.as_deref() {
+        match load_ca_cert(path) {
+            Ok(cert) => builder = builder.add_root_certificate(cert),
+            Err(e) => errors.push(e),
+        }
+    }
+    match config.timeout_secs {
+        Some(0) => errors.push("Timeout must be at least 1 second".to_string()),
+        Some(secs) => builder = builder.timeout(Duration::from_secs(secs)),
+        None => {}
+    }
+    if errors.is_empty() {
+        if let Err(e) = builder.build() {
+            errors.push(format!("Failed to build HTTP client: {}", e));
+        }
+    }
+    HttpConfigValidation {
+        valid: errors.is_empty(),
+        errors,
+    }
+}
+
+// Checks proxy, CA certificate and timeout settings by building a client from them, without
+// making a request, so misconfiguration shows up before anything relies on it
+#[tauri::command]
+pub fn validate_http_config(config: HttpClientConfig) -> HttpConfigValidation {
+    validate_client_config(&config)
+}
+
+// Content-Encoding lists codings in the order they were applied, e.g. `gzip, br`, so they're
+// undone last first
+fn decode_body(bytes: &[u8], encoding: Option<&str>) -> Result<Vec<u8>, String> {
+    let mut decoded = bytes.to_vec();
+    for coding in encoding.unwrap_or_default().split(',').rev() {
+        decoded = decode_coding(&decoded, &coding.trim().to_ascii_lowercase())?;
+    }
+    Ok(decoded)
+}
+
+fn decode_coding(bytes: &[u8], encoding: &str) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    let result = match encoding {
+        "" | "identity" => return Ok(bytes.to_vec()),
+        "gzip" | "x-gzip" => flate2::read::GzDecoder::new(bytes).read_to_end(&mut decoded),
+        "deflate" => flate2::read::ZlibDecoder::new(bytes).read_to_end(&mut decoded),
+        "br" => brotli::Decompressor::new(bytes, 4096).read_to_end(&mut decoded),
+        other => return Err(format!("Unsupported Content-Encoding: {}", other)),
+    };
+    result.map_err(|e| format!("Failed to decode {} response: {}", encoding, e))?;
+    Ok(decoded)
+}
+
+struct RequestFailure {
+    message: String,
+    // Network errors and 5xx/429 responses count against the host's circuit and may be retried
+    transient: bool,
+}
+
+// Circuit breakers are tracked per scheme+host+port
+fn host_key(url: &str) -> Result<String, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("URL has no host: {}", url))?;
+    Ok(match parsed.port_or_known_default() {
+        Some(port) => format!("{}://{}:{}", parsed.scheme(), host, port),
+        None => format!("{}://{}", parsed.scheme(), host),
+    })
+}
+
+// Identifies calls that can share one in-flight request: method, URL and a hash of the headers.
+// None for methods that mustn't be coalesced.
+fn dedup_key(method: &str, url: &str, headers: &HashMap<String, String>) -> Option<String> {
+    if !DEDUPED_METHODS.contains(&method) {
+        return None;
+    }
+    // Hash in a stable order so the same headers always give the same key
+    let mut hasher = DefaultHasher::new();
+    headers.iter().collect::<BTreeMap<_, _>>().hash(&mut hasher);
+    Some(format!("{} {} {:x}", method, url, hasher.finish()))
+}
+
+async fn send_once(
+    client: &reqwest::Client,
+    method: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Option<&String>,
+) -> Result<HttpResponse, RequestFailure> {
+    let mut request = match method {
+        "GET" => client.get(url),
+        "POST" => client.post(url),
+        "PUT" => client.put(url),
+        "DELETE" => client.delete(url),
+        "PATCH" => client.patch(url),
+        _ => {
+            return Err(RequestFailure {
+                message: format!("Unsupported HTTP method: {}", method),
+                transient: false,
+            })
+        }
+    };
+
+    // Add headers
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+
+    // Add body for POST/PUT/PATCH requests
+    if let Some(body_data) = body {
+        if ["POST", "PUT", "PATCH"].contains(&method) {
+            request = request.body(body_data.clone());
+        }
+    }
+
+    let response = request.send().await.map_err(|e| RequestFailure {
+        message: format!("Request failed: {}", e),
+        transient: true,
+    })?;
+    let first_byte_at = Instant::now();
+    let status_code = response.status().as_u16();
+    let response_headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect();
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(RequestFailure {
+            message: format!("HTTP error: {}", status),
+            transient: status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+        });
+    }
+
+    let content_encoding = response
+        .headers()
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let bytes = response.bytes().await.map_err(|e| RequestFailure {
+        message: format!("Failed to read response: {}", e),
+        transient: true,
+    })?;
+    let finished_at = Instant::now();
+    let decoded = decode_body(&bytes, content_encoding.as_deref()).map_err(|message| RequestFailure {
+        message,
+        transient: false,
+    })?;
+    let json = serde_json::from_slice(&decoded).map_err(|e| RequestFailure {
+        message: format!("Failed to parse JSON: {}", e),
+        transient: false,
+    })?;
+
+    Ok(HttpResponse {
+        json,
+        status: status_code,
+        headers: response_headers,
+        content_encoding,
+        encoded_bytes: bytes.len() as u64,
+        decoded_bytes: decoded.len() as u64,
+        first_byte_at,
+        finished_at,
+    })
+}
+
+// Fills `{{name}}` placeholders in the URL and header values. Placeholders without a variable
+// fail the request with their names rather than sending a malformed URL.
+fn substitute_variables(
+    url: &str,
+    headers: HashMap<String, String>,
+    variables: &HashMap<String, String>,
+) -> Result<(String, HashMap<String, String>), String> {
+    let mut missing = BTreeSet::new();
+    let url = fill_placeholders(url, variables, &mut missing);
+    let headers = headers
+        .into_iter()
+        .map(|(name, value)| {
+            let value = fill_placeholders(&value, variables, &mut missing);
+            (name, value)
+        })
+        .collect();
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.into_iter().collect();
+        return Err(format!("Missing values for template variables: {}", missing.join(", ")));
+    }
+    Ok((url, headers))
+}
+
+// `report_encoding` returns `{ body, content_encoding, encoded_bytes, decoded_bytes }` instead
+// of the bare JSON body, for seeing how well large responses compress.
+// `variables` fill `{{name}}` placeholders in the URL and headers. `report_timing` adds a
+// `timing` breakdown with the redirect chain, also wrapping the body as `body`. `record` adds the
+// call as an `entry` that `export_http_har` can save.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn http_request(
+    breakers: tauri::State<'_, CircuitBreakers>,
+    prefs: tauri::State<'_, PreferencesState>,
+    inflight: tauri::State<'_, InFlightRequests>,
+    active: tauri::State<'_, ActiveRequests>,
+    url: String,
+    method: Option<String>,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    retries: Option<u32>,
+    request_id: Option<String>,
+    report_encoding: Option<bool>,
+    variables: Option<HashMap<String, String>>,
+    report_timing: Option<bool>,
+    record: Option<bool>,
+) -> Result<serde_json::Value, CommandError> {
+    let (url, headers) = substitute_variables(&url, headers, &variables.unwrap_or_default())?;
+    let report_encoding = report_encoding.unwrap_or(false);
+    let report_timing = report_timing.unwrap_or(false);
+    let record = record.unwrap_or(false);
+    let timeout = Duration::from_secs(prefs.get().http_timeout_secs);
+    let probe = report_timing.then(|| TimingProbe::start(&url));
+    let client = match &probe {
+        Some(probe) => probe.client(client_builder(timeout, report_encoding))?,
+        None => build_client(timeout, report_encoding)?,
+    };
+    let method = method.unwrap_or_else(|| "GET".to_string()).to_uppercase();
+    if !SUPPORTED_METHODS.contains(&method.as_str()) {
+        return Err(format!("Unsupported HTTP method: {}", method).into());
+    }
+    let retries = retries.unwrap_or(0).min(MAX_RETRIES);
+    let mut headers = headers;
+    if report_encoding && !headers.keys().any(|key| key.eq_ignore_ascii_case("accept-encoding")) {
+        headers.insert("Accept-Encoding".to_string(), CONTENT_ENCODINGS.join(", "));
+    }
+
+    let (started_at, started) = (SystemTime::now(), Instant::now());
+    let request = async {
+        send_with_retries(&breakers, &client, &method, &url, &headers, body.as_ref(), retries)
+            .await
+            .map(|response| {
+                let timing = probe.as_ref().map(|probe| probe.timing(&response));
+                let entry = record.then(|| {
+                    let request = HttpEntryRequest {
+                        method: method.clone(),
+                        url: url.clone(),
+                        headers: headers.clone(),
+                        body: body.clone(),
+                    };
+                    response.entry(started_at, started, request)
+                });
+                response.into_value(report_encoding, timing, entry)
+            })
+    };
+    // Cancelling the caller that started a coalesced request aborts it for everyone sharing it.
+    // Encoding, timing and recorded calls aren't shared, since a plain call's response can't
+    // fill them in.
+    let shareable = !report_encoding && !report_timing && !record;
+    let request = async {
+        match dedup_key(&method, &url, &headers).filter(|_| shareable) {
+            Some(key) => inflight.run(key, request).await,
+            None => request.await,
+        }
+    };
+    active.run(request_id, request).await
+}
+
+async fn send_with_retries(
+    breakers: &CircuitBreakers,
+    client: &reqwest::Client,
+    method: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Option<&String>,
+    retries: u32,
+) -> Result<HttpResponse, CommandError> {
+    let host = host_key(url)?;
+    let mut attempt = 0;
+    loop {
+        // Every attempt, including retries, goes through the breaker so an open circuit stops them
+        // The permit frees a half-open trial slot if this future is dropped by a cancellation
+        let permit = breakers.check(&host)?;
+
+        match send_once(client, method, url, headers, body).await {
+            Ok(response) => {
+                permit.record_success();
+                return Ok(response);
+            }
+            Err(failure) if failure.transient => {
+                permit.record_failure();
+                if attempt >= retries {
+                    return Err(failure.message.into());
+                }
+            }
+            Err(failure) => {
+                // The host answered, it just didn't like the request
+                permit.record_success();
+                return Err(failure.message.into());
+            }
+        }
+
+        attempt += 1;
+        tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancelled_half_open_trial_lets_the_host_recover() {
+        let breakers = CircuitBreakers::new(1, std::time::Duration::ZERO);
+        breakers.record_failure("h");
+        let active = ActiveRequests::default();
+
+        let trial = active.run(Some("trial".to_string()), async {
+            let _permit = breakers.check("h").unwrap();
+            std::future::pending::<Result<serde_json::Value, CommandError>>().await
+        });
+        let cancel = async {
+            tokio::task::yield_now().await;
+            assert!(matches!(breakers.check("h"), Err(CommandError::CircuitOpen(_))));
+            assert!(active.cancel("trial"));
+        };
+        let (result, ()) = tokio::join!(trial, cancel);
+        assert!(matches!(result, Err(CommandError::Cancelled(_))));
+
+        breakers.check("h").unwrap().record_success();
+        assert!(breakers.check("h").is_ok());
+    }
+
+    #[test]
+    fn host_key_includes_scheme_and_default_port() {
+        assert_eq!(host_key("https://example.com/a?b=c").unwrap(), "https://example.com:443");
+        assert_eq!(host_key("http://example.com:8080/").unwrap(), "http://example.com:8080");
+        assert!(host_key("not a url").is_err());
+    }
+
+    #[test]
+    fn variables_fill_the_url_and_headers() {
+        let variables = HashMap::from([
+            ("sub".to_string(), "s1".to_string()),
+            ("token".to_string(), "abc".to_string()),
+        ]);
+        let headers = HashMap::from([("Authorization".to_string(), "Bearer {{token}}".to_string())]);
+        let (url, headers) =
+            substitute_variables("https://example.com/subscriptions/{{sub}}/x?y={{ sub }}", headers, &variables)
+                .unwrap();
+        assert_eq!(url, "https://example.com/subscriptions/s1/x?y=s1");
+        assert_eq!(headers["Authorization"], "Bearer abc");
+    }
+
+    #[test]
+    fn unfilled_placeholders_are_listed() {
+        let variables = HashMap::from([("sub".to_string(), "s1".to_string())]);
+        let headers = HashMap::from([("X-Group".to_string(), "{{group}}".to_string())]);
+        let error = substitute_variables("https://example.com/{{sub}}/{{api}}", headers, &variables).unwrap_err();
+        assert_eq!(error, "Missing values for template variables: api, group");
+    }
+
+    #[test]
+    fn capabilities_list_the_standard_methods() {
+        let caps = capabilities(60);
+        for method in ["GET", "POST", "PUT", "DELETE", "PATCH"] {
+            assert!(caps.methods.iter().any(|m| m == method), "{} missing", method);
+        }
+        assert_eq!(caps.default_timeout_secs, 60);
+        assert_eq!(caps.max_retries, MAX_RETRIES);
+    }
+
+    #[tokio::test]
+    async fn in_flight_request_can_be_cancelled() {
+        let active = ActiveRequests::default();
+        let (result, cancelled) = tokio::join!(
+            active.run(Some("r1".to_string()), std::future::pending()),
+            async {
+                tokio::task::yield_now().await;
+                active.cancel("r1")
+            }
+        );
+        assert!(cancelled);
+        assert!(matches!(result, Err(CommandError::Cancelled(_))));
+        // Once finished the id is free again and there's nothing left to cancel
+        assert!(!active.cancel("r1"));
+    }
+
+    #[test]
+    fn stacked_encodings_are_undone_in_reverse() {
+        use std::io::Write;
+
+        let json = br#"{"value":[]}"#;
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(json).unwrap();
+        let gzipped = gzip.finish().unwrap();
+        let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        brotli.write_all(&gzipped).unwrap();
+        let stacked = brotli.into_inner();
+
+        assert_eq!(decode_body(&stacked, Some("gzip, br")).unwrap(), json);
+        assert_eq!(decode_body(&gzipped, Some("identity,gzip")).unwrap(), json);
+        assert_eq!(decode_body(json, None).unwrap(), json);
+        let error = decode_body(&stacked, Some("zstd, br")).unwrap_err();
+        assert_eq!(error, "Unsupported Content-Encoding: zstd");
+    }
+
+    #[tokio::test]
+    async fn gzip_response_reports_encoding_and_decoded_length() {
+        use std::io::Write;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let json = br#"{"value":[{"name":"rg-a"},{"name":"rg-b"}]}"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let body = gzipped.clone();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+        });
+
+        let client = build_client(Duration::from_secs(5), true).unwrap();
+        let response = send_once(&client, "GET", &url, &HashMap::new(), None)
+            .await
+            .unwrap_or_else(|failure| panic!("{}", failure.message));
+        assert_eq!(response.content_encoding.as_deref(), Some("gzip"));
+        assert_eq!(response.decoded_bytes, json.len() as u64);
+        assert_eq!(response.encoded_bytes, gzipped.len() as u64);
+        assert_eq!(response.json["value"][1]["name"], "rg-b");
+    }
+
+    #[tokio::test]
+    async fn timing_is_reported_in_order_with_the_redirect_chain() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        // A name rather than an IP literal, so the client's resolver runs
+        let url = format!("http://localhost:{}/start", listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            // The request and its redirect; nothing else connects
+            let responses = [
+                "HTTP/1.1 302 Found\r\nLocation: /final\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let probe = TimingProbe::start(&url);
+        let client = probe.client(client_builder(Duration::from_secs(5), false)).unwrap();
+        let response = send_once(&client, "GET", &url, &HashMap::new(), None)
+            .await
+            .unwrap_or_else(|failure| panic!("{}", failure.message));
+        let timing = probe.timing(&response);
+
+        let dns_ms = timing.dns_ms.unwrap();
+        assert!(dns_ms >= 0.0);
+        assert!(dns_ms <= timing.first_byte_ms);
+        assert!(timing.first_byte_ms <= timing.total_ms);
+        assert_eq!(timing.redirect_chain, [url.clone(), url.replace("/start", "/final")]);
+
+        let value = response.into_value(false, Some(timing), None);
+        assert_eq!(value["body"], serde_json::json!({}));
+        assert!(value["timing"]["total_ms"].is_f64());
+        assert!(value["timing"]["connect_ms"].is_null());
+        assert!(value["timing"]["tls_ms"].is_null());
+    }
+
+    #[test]
+    fn env_proxy_is_detected_per_scheme() {
+        let vars = |set: &'static [(&'static str, &'static str)]| {
+            move |name: &str| set.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        };
+
+        assert!(env_proxy_applies("https", vars(&[("HTTPS_PROXY", "http://proxy.corp:8080")])));
+        assert!(env_proxy_applies("http", vars(&[("http_proxy", "http://proxy.corp:8080")])));
+        assert!(env_proxy_applies("http", vars(&[("ALL_PROXY", "socks5://proxy.corp:1080")])));
+        assert!(!env_proxy_applies("http", vars(&[("HTTPS_PROXY", "http://proxy.corp:8080")])));
+        assert!(!env_proxy_applies("https", vars(&[("HTTPS_PROXY", " ")])));
+        assert!(!env_proxy_applies("https", vars(&[])));
+    }
+
+    #[test]
+    fn dedup_key_covers_headers_and_skips_writes() {
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), "application/json".to_string());
+        let key = dedup_key("GET", "https://a/b", &headers).unwrap();
+        assert_eq!(dedup_key("GET", "https://a/b", &headers.clone()).unwrap(), key);
+
+        headers.insert("Authorization".to_string(), "Bearer t".to_string());
+        assert_ne!(dedup_key("GET", "https://a/b", &headers).unwrap(), key);
+        assert_eq!(dedup_key("POST", "https://a/b", &headers), None);
+    }
+
+    #[test]
+    fn http_config_is_validated_without_a_request() {
+        const CA_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBizCCATGgAwIBAgIUMyEAPxJIw+wV5NlspxkLjnvjbugwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPc2thbnl4eC10ZXN0LWNhMCAXDTI2MTAxNjA2NTc0OFoYDzIx
+MjYwOTIyMDY1NzQ4WjAaMRgwFgYDVQQDDA9za2FueXh4LXRlc3QtY2EwWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAAQdXxJUoS+eqo65FMtmhxW98yuQMYcDvz1Qrx0p
+feGhF/g0+qdjM5/z/BPgsbAmb3P+apGnvnso8ZD3RqjyZZKJo1MwUTAdBgNVHQ4E
+FgQU5WWrKesSTK1QOIbn064V+IY/yZgwHwYDVR0jBBgwFoAU5WWrKesSTK1QOIbn
+064V+IY/yZgwDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiEA8cmb
+335DOWk/V938UOhWmbvIw8vMNhtEnWY8kUWBd3cCIHlMC2DmkGVfDA++TZ51FMnt
+LCn1FRZMlYUps7JGdvhV
+-----END CERTIFICATE-----
+";
+        let dir = std::env::temp_dir().join(format!("skanyxx-http-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let good_cert = dir.join("ca.pem");
+        let bad_cert = dir.join("bad.pem");
+        std::fs::write(&good_cert, CA_PEM).unwrap();
+        std::fs::write(&bad_cert, "-----BEGIN CERTIFICATE-----\nnot base64 at all\n-----END CERTIFICATE-----\n").unwrap();
+
+        let valid = validate_client_config(&HttpClientConfig {
+            proxy: Some("http://proxy.example.com:8080".to_string()),
+            ca_cert_path: Some(good_cert.to_string_lossy().into_owned()),
+            timeout_secs: Some(30),
+        });
+        assert!(valid.valid, "{:?}", valid.errors);
+        assert!(valid.errors.is_empty());
+
+        let bad_proxy = validate_client_config(&HttpClientConfig {
+            proxy: Some("http://proxy.example.com:notaport".to_string()),
+            ..Default::default()
+        });
+        assert!(!bad_proxy.valid);
+        assert_eq!(bad_proxy.errors.len(), 1);
+        assert!(bad_proxy.errors[0].starts_with("Invalid proxy URL"), "{}", bad_proxy.errors[0]);
+
+        let bad = validate_client_config(&HttpClientConfig {
+            proxy: None,
+            ca_cert_path: Some(bad_cert.to_string_lossy().into_owned()),
+            timeout_secs: Some(0),
+        });
+        assert!(!bad.valid);
+        assert_eq!(bad.errors.len(), 2);
+        assert!(bad.errors[0].starts_with("Invalid CA certificate"), "{}", bad.errors[0]);
+        assert!(bad.errors[1].contains("Timeout"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circuit_breaker::CircuitBreakers;
+use crate::error::CommandError;
+use crate::http::{ActiveRequests, InFlightRequests};
+use crate::preferences::PreferencesState;
+
+pub const HTTP_PRESETS_FILE: &str = "http_presets.json";
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// A saved request. The URL, header values and body may hold `{{name}}` placeholders that are
+/// filled in when the preset is run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpPreset {
+    #[serde(default = "default_method")]
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+// Replaces each `{{name}}` in `template` from `variables`, collecting names that have no value.
+// Whitespace inside the braces is ignored, so `{{ name }}` works too.
+pub(crate) fn fill_placeholders(
+    template: &str,
+    variables: &HashMap<String, String>,
+    missing: &mut BTreeSet<String>,
+) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + 2 + end + 2];
+        let name = rest[start + 2..start + 2 + end].trim();
+        match variables.get(name) {
+            Some(value) => filled.push_str(value),
+            None => {
+                missing.insert(name.to_string());
+                filled.push_str(placeholder);
+            }
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+impl HttpPreset {
+    // The preset with every placeholder filled in, or an error naming the variables left over
+    fn resolve(&self, variables: &HashMap<String, String>) -> Result<HttpPreset, String> {
+        let mut missing = BTreeSet::new();
+        let resolved = HttpPreset {
+            method: self.method.clone(),
+            url: fill_placeholders(&self.url, variables, &mut missing),
+            headers: self
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), fill_placeholders(value, variables, &mut missing)))
+                .collect(),
+            body: self.body.as_deref().map(|body| fill_placeholders(body, variables, &mut missing)),
+        };
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing.into_iter().collect();
+            return Err(format!("Missing values for template variables: {}", missing.join(", ")));
+        }
+        Ok(resolved)
+    }
+}
+
+fn load_presets(path: &Path) -> Result<BTreeMap<String, HttpPreset>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_presets(path: &Path, presets: &BTreeMap<String, HttpPreset>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(presets)
+        .map_err(|e| format!("Failed to serialize HTTP presets: {}", e))?;
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Managed state pointing at the presets file. The file is re-read on each use, and an unreadable
+// one fails the call rather than being saved over.
+pub struct HttpPresets {
+    path: PathBuf,
+    // Serialises read-modify-write cycles on the file
+    lock: Mutex<()>,
+}
+
+impl HttpPresets {
+    pub fn new(path: PathBuf) -> Self {
+        HttpPresets {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn list(&self) -> Result<BTreeMap<String, HttpPreset>, String> {
+        let _guard = self.lock.lock().unwrap();
+        load_presets(&self.path)
+    }
+
+    pub fn get(&self, name: &str) -> Result<HttpPreset, String> {
+        self.list()?
+            .remove(name)
+            .ok_or_else(|| format!("No HTTP preset named '{}'", name))
+    }
+
+    pub fn save(&self, name: String, preset: HttpPreset) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap();
+        let mut presets = load_presets(&self.path)?;
+        presets.insert(name, preset);
+        save_presets(&self.path, &presets)
+    }
+}
+
+// Saves `request_spec` under `name`, replacing any preset already called that
+#[tauri::command]
+pub fn save_http_preset(
+    presets: tauri::State<'_, HttpPresets>,
+    name: String,
+    request_spec: HttpPreset,
+) -> Result<HttpPreset, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Preset name must not be empty".to_string());
+    }
+    let mut preset = request_spec;
+    preset.method = preset.method.to_uppercase();
+    if !crate::http::SUPPORTED_METHODS.contains(&preset.method.as_str()) {
+        return Err(format!("Unsupported HTTP method: {}", preset.method));
+    }
+    presets.save(name, preset.clone())?;
+    Ok(preset)
+}
+
+#[tauri::command]
+pub fn list_http_presets(
+    presets: tauri::State<'_, HttpPresets>,
+) -> Result<BTreeMap<String, HttpPreset>, String> {
+    presets.list()
+}
+
+// Sends the named preset through `http_request` with its placeholders filled from `overrides`.
+// A placeholder without a value fails the call before anything is sent.
+#[tauri::command]
+pub async fn run_http_preset(
+    breakers: tauri::State<'_, CircuitBreakers>,
+    prefs: tauri::State<'_, PreferencesState>,
+    inflight: tauri::State<'_, InFlightRequests>,
+    active: tauri::State<'_, ActiveRequests>,
+    presets: tauri::State<'_, HttpPresets>,
+    name: String,
+    overrides: Option<HashMap<String, String>>,
+) -> Result<serde_json::Value, CommandError> {
+    let preset = presets.get(&name)?.resolve(&overrides.unwrap_or_default())?;
+    crate::http::http_request(
+        breakers,
+        prefs,
+        inflight,
+        active,
+        preset.url,
+        Some(preset.method),
+        preset.headers,
+        preset.body,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("skanyxx-presets-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(HTTP_PRESETS_FILE)
+    }
+
+    #[test]
+    fn saved_preset_runs_with_variables_substituted() {
+        let path = temp_path("substitute");
+        let store = HttpPresets::new(path.clone());
+        let preset = HttpPreset {
+            method: "GET".to_string(),
+            url: "https://management.azure.com/subscriptions/{{subscription}}/resourcegroups?api-version={{ api }}"
+                .to_string(),
+            headers: HashMap::from([("Authorization".to_string(), "Bearer {{token}}".to_string())]),
+            body: None,
+        };
+        store.save("groups".to_string(), preset.clone()).unwrap();
+
+        // A fresh store reads it back from the file
+        let saved = HttpPresets::new(path.clone()).get("groups").unwrap();
+        assert_eq!(saved, preset);
+
+        let variables = HashMap::from([
+            ("subscription".to_string(), "sub-1".to_string()),
+            ("api".to_string(), "2021-04-01".to_string()),
+            ("token".to_string(), "abc".to_string()),
+        ]);
+        let resolved = saved.resolve(&variables).unwrap();
+        assert_eq!(
+            resolved.url,
+            "https://management.azure.com/subscriptions/sub-1/resourcegroups?api-version=2021-04-01"
+        );
+        assert_eq!(resolved.headers["Authorization"], "Bearer abc");
+
+        let error = saved.resolve(&HashMap::new()).unwrap_err();
+        assert_eq!(error, "Missing values for template variables: api, subscription, token");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
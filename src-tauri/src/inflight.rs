@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use tokio::sync::oneshot;
+
+use crate::error::CommandError;
+
+type Waiters<T> = Vec<oneshot::Sender<Result<T, CommandError>>>;
+
+/// Coalesces identical concurrent calls: the first caller for a key does the work and every
+/// caller that arrives while it's running gets a clone of the same result.
+pub struct InFlight<T> {
+    pending: Mutex<HashMap<String, Waiters<T>>>,
+}
+
+impl<T> Default for InFlight<T> {
+    fn default() -> Self {
+        InFlight {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+// Clears the key if the leading call is dropped before finishing, so followers aren't left
+// waiting on work nobody is doing
+struct LeaderGuard<'a, T> {
+    inflight: &'a InFlight<T>,
+    key: &'a str,
+    finished: bool,
+}
+
+impl<T> LeaderGuard<'_, T> {
+    // Takes the waiters that queued up behind the leader; later calls start fresh
+    fn finish(mut self) -> Waiters<T> {
+        self.finished = true;
+        self.inflight.pending.lock().unwrap().remove(self.key).unwrap_or_default()
+    }
+}
+
+impl<T> Drop for LeaderGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.inflight.pending.lock().unwrap().remove(self.key);
+        }
+    }
+}
+
+impl<T: Clone> InFlight<T> {
+    pub async fn run<F>(&self, key: String, work: F) -> Result<T, CommandError>
+    where
+        F: Future<Output = Result<T, CommandError>>,
+    {
+        let follower = {
+            let mut pending = self.pending.lock().unwrap();
+            match pending.get_mut(&key) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    pending.insert(key.clone(), Vec::new());
+                    None
+                }
+            }
+        };
+
+        if let Some(receiver) = follower {
+            return receiver.await.unwrap_or_else(|_| {
+                Err(CommandError::Failed(
+                    "The shared request was cancelled before it finished".to_string(),
+                ))
+            });
+        }
+
+        let guard = LeaderGuard {
+            inflight: self,
+            key: &key,
+            finished: false,
+        };
+        let result = work.await;
+        let waiters = guard.finish();
+        for waiter in waiters {
+            // A follower that gave up waiting has dropped its receiver; nothing to deliver
+            let _ = waiter.send(result.clone());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn concurrent_identical_calls_share_one_execution() {
+        let inflight = InFlight::<u32>::default();
+        let calls = AtomicUsize::new(0);
+        let calls = &calls;
+        let work = move || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<u32, CommandError>(7)
+        };
+
+        let (a, b) = tokio::join!(
+            inflight.run("GET https://a".to_string(), work()),
+            inflight.run("GET https://a".to_string(), work()),
+        );
+        assert_eq!(a.unwrap(), 7);
+        assert_eq!(b.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Once finished, the next call does the work again
+        inflight.run("GET https://a".to_string(), work()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn different_keys_run_separately() {
+        let inflight = InFlight::<u32>::default();
+        let (a, b) = tokio::join!(
+            inflight.run("a".to_string(), async { Ok(1) }),
+            inflight.run("b".to_string(), async { Ok(2) }),
+        );
+        assert_eq!(a.unwrap(), 1);
+        assert_eq!(b.unwrap(), 2);
+    }
+}
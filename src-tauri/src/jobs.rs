@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::future::Future;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+tokio::task_local! {
+    // Id of the job whose future is being polled, so children it spawns can be tied to it
+    static CURRENT_JOB: u64;
+}
+
+/// File a job writes its results to. If the job is cancelled the file is incomplete, so it's
+/// deleted, or renamed with a `.partial` suffix when `keep_partial` is set.
+#[derive(Debug, Clone)]
+pub struct JobOutput {
+    pub path: PathBuf,
+    pub keep_partial: bool,
+}
+
+impl JobOutput {
+    fn partial_path(&self) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(".partial");
+        PathBuf::from(name)
+    }
+
+    // Runs once the cancelled job's future is dropped, so its file handle is already closed
+    fn discard(&self) {
+        let result = if self.keep_partial {
+            std::fs::rename(&self.path, self.partial_path())
+        } else {
+            std::fs::remove_file(&self.path)
+        };
+        match result {
+            // Cancelled before anything was written
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("Failed to clean up partial output {}: {}", self.path.display(), e),
+            Ok(()) => {}
+        }
+    }
+}
+
+struct Job {
+    label: String,
+    // Taken when the job is cancelled; it stays listed until its future has stopped
+    cancel: Option<oneshot::Sender<()>>,
+    output: Option<JobOutput>,
+}
+
+struct TrackedChild {
+    job: u64,
+    tool: String,
+    args: Vec<String>,
+    started: Instant,
+}
+
+/// A child process the app is running, as listed by `list_app_children`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppChild {
+    pub pid: u32,
+    pub tool: String,
+    pub args: Vec<String>,
+    pub job: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Keeps a child listed while it runs; dropping it (the child exited or its job was cancelled)
+/// takes it off the list
+pub struct ChildGuard<'a> {
+    registry: &'a JobRegistry,
+    pid: Option<u32>,
+}
+
+impl Drop for ChildGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid {
+            self.registry.children.lock().unwrap().remove(&pid);
+        }
+    }
+}
+
+/// Long-running work (tool runs, streams) that can be cancelled from the UI, kept in managed
+/// state. Work runs through `run`; cancelling drops its future, so child processes must be
+/// spawned with `kill_on_drop(true)` to be terminated along with it.
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, Job>>,
+    // Child processes spawned by running jobs, by pid
+    children: Mutex<HashMap<u32, TrackedChild>>,
+}
+
+impl JobRegistry {
+    /// Runs `work` as a registered job until it finishes or is cancelled
+    pub async fn run<T, F>(&self, label: &str, work: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        self.run_with_output(label, None, work).await
+    }
+
+    /// Like `run`, for work writing to `output`; cancelling the job cleans up the partial file
+    pub async fn run_with_output<T, F>(&self, label: &str, output: Option<JobOutput>, work: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (cancel, cancelled) = oneshot::channel();
+        self.jobs.lock().unwrap().insert(
+            id,
+            Job {
+                label: label.to_string(),
+                cancel: Some(cancel),
+                output,
+            },
+        );
+
+        let (result, was_cancelled) = tokio::select! {
+            result = CURRENT_JOB.scope(id, work) => (result, false),
+            _ = cancelled => (Err(format!("Job {} ({}) was cancelled", id, label)), true),
+        };
+        let job = self.jobs.lock().unwrap().remove(&id);
+        if was_cancelled {
+            if let Some(output) = job.and_then(|job| job.output) {
+                output.discard();
+            }
+        }
+        result
+    }
+
+    /// Ids of the jobs currently running, oldest first
+    pub fn running(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.jobs.lock().unwrap().keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Cancels every running job and returns their ids; empty when nothing is running
+    pub fn cancel_all(&self) -> Vec<u64> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut cancelled: Vec<u64> = jobs
+            .iter_mut()
+            .filter_map(|(id, job)| Self::cancel_job(*id, job).then_some(*id))
+            .collect();
+        cancelled.sort_unstable();
+        cancelled
+    }
+
+    /// Cancels one job; false if it isn't running or is already being cancelled
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.get_mut(&id).is_some_and(|job| Self::cancel_job(id, job))
+    }
+
+    fn cancel_job(id: u64, job: &mut Job) -> bool {
+        let Some(cancel) = job.cancel.take() else {
+            return false;
+        };
+        // The job may have finished between locking and sending; nothing to stop then
+        if cancel.send(()).is_err() {
+            log::debug!("Job {} ({}) finished before it could be cancelled", id, job.label);
+        }
+        true
+    }
+
+    /// Lists a child spawned by the current job until the returned guard is dropped. Outside a
+    /// job, or without a pid (already exited), nothing is listed.
+    pub fn track_child(&self, pid: Option<u32>, tool: &str, args: &[String]) -> ChildGuard<'_> {
+        let pid = pid.zip(CURRENT_JOB.try_with(|id| *id).ok()).map(|(pid, job)| {
+            let child = TrackedChild {
+                job,
+                tool: tool.to_string(),
+                args: args.to_vec(),
+                started: Instant::now(),
+            };
+            self.children.lock().unwrap().insert(pid, child);
+            pid
+        });
+        ChildGuard { registry: self, pid }
+    }
+
+    /// Children of running jobs, oldest first
+    pub fn children(&self) -> Vec<AppChild> {
+        let children = self.children.lock().unwrap();
+        let mut listed: Vec<(Instant, AppChild)> = children
+            .iter()
+            .map(|(pid, child)| {
+                let entry = AppChild {
+                    pid: *pid,
+                    tool: child.tool.clone(),
+                    args: child.args.clone(),
+                    job: child.job,
+                    elapsed_ms: child.started.elapsed().as_millis() as u64,
+                };
+                (child.started, entry)
+            })
+            .collect();
+        listed.sort_by_key(|(started, _)| *started);
+        listed.into_iter().map(|(_, child)| child).collect()
+    }
+
+    /// Stops a listed child by cancelling the job that spawned it, which kills the child as its
+    /// future is dropped
+    pub fn kill_child(&self, pid: u32) -> Result<(), String> {
+        let job = self
+            .children
+            .lock()
+            .unwrap()
+            .get(&pid)
+            .map(|child| child.job)
+            .ok_or_else(|| format!("No child process {} is running", pid))?;
+        if !self.cancel(job) {
+            return Err(format!("Job {} running process {} is already stopping", job, pid));
+        }
+        Ok(())
+    }
+}
+
+/// Sent as a `command-exit` event the moment a streamed child exits, before the command that
+/// ran it has finished reading its output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandExit {
+    // None when the child wasn't run by a job
+    pub job: Option<u64>,
+    // None when the child was killed by a signal
+    pub exit_code: Option<i32>,
+    // The signal that killed the child; always None off Unix
+    pub signal: Option<i32>,
+    pub duration_ms: u64,
+}
+
+impl CommandExit {
+    pub fn new(status: &ExitStatus, started: Instant) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(status);
+        #[cfg(not(unix))]
+        let signal = None;
+        CommandExit {
+            job: CURRENT_JOB.try_with(|id| *id).ok(),
+            exit_code: status.code(),
+            signal,
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+// Waits for `child` (spawned at `started`) and reports its exit to `notify` as soon as it happens.
+// Run it alongside the readers of the child's pipes rather than after them: a process the child
+// left running in the background can hold the pipes open long after the child has exited.
+pub async fn wait_reporting_exit(
+    child: &mut tokio::process::Child,
+    started: Instant,
+    notify: impl FnOnce(CommandExit),
+) -> std::io::Result<ExitStatus> {
+    let status = child.wait().await?;
+    notify(CommandExit::new(&status, started));
+    Ok(status)
+}
+
+// Aborts everything in flight, e.g. before switching subscriptions
+#[tauri::command]
+pub fn cancel_all_jobs(jobs: tauri::State<'_, JobRegistry>) -> Vec<u64> {
+    jobs.cancel_all()
+}
+
+// Every child process the app's jobs are running, for the process panel
+#[tauri::command]
+pub fn list_app_children(jobs: tauri::State<'_, JobRegistry>) -> Vec<AppChild> {
+    jobs.children()
+}
+
+#[tauri::command]
+pub fn kill_app_child(jobs: tauri::State<'_, JobRegistry>, pid: u32) -> Result<(), String> {
+    jobs.kill_child(pid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn cancel_all_with_nothing_running_is_empty() {
+        assert!(JobRegistry::default().cancel_all().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancel_all_stops_every_running_job() {
+        let registry = Arc::new(JobRegistry::default());
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let registry = registry.clone();
+                tokio::spawn(async move {
+                    registry
+                        .run("sleep", async {
+                            tokio::process::Command::new("sleep")
+                                .arg("30")
+                                .kill_on_drop(true)
+                                .status()
+                                .await
+                                .map_err(|e| e.to_string())
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        while registry.running().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(registry.cancel_all(), [1, 2]);
+
+        for handle in handles {
+            let result = tokio::time::timeout(Duration::from_secs(5), handle)
+                .await
+                .expect("cancelled job should finish promptly")
+                .unwrap();
+            assert!(result.unwrap_err().contains("was cancelled"));
+        }
+        assert!(registry.running().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn spawned_child_is_listed_until_killed() {
+        let registry = Arc::new(JobRegistry::default());
+        let job = {
+            let registry = registry.clone();
+            tokio::spawn(async move {
+                let registry = &*registry;
+                registry
+                    .run("sleep", async move {
+                        let args = vec!["30".to_string()];
+                        let mut child = tokio::process::Command::new("sleep")
+                            .args(&args)
+                            .kill_on_drop(true)
+                            .spawn()
+                            .map_err(|e| e.to_string())?;
+                        let _tracked = registry.track_child(child.id(), "sleep", &args);
+                        child.wait().await.map_err(|e| e.to_string())
+                    })
+                    .await
+            })
+        };
+
+        while registry.children().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let children = registry.children();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].tool, "sleep");
+        assert_eq!(children[0].args, ["30"]);
+        assert_eq!(children[0].job, 1);
+
+        registry.kill_child(children[0].pid).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), job)
+            .await
+            .expect("killed child's job should finish promptly")
+            .unwrap();
+        assert!(result.unwrap_err().contains("was cancelled"));
+        assert!(registry.children().is_empty());
+        assert!(registry.kill_child(children[0].pid).is_err());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_writing_job_cleans_up_its_file() {
+        let dir = std::env::temp_dir().join(format!("skanyxx-jobs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for keep_partial in [false, true] {
+            let output = JobOutput {
+                path: dir.join(format!("export-{}.json", keep_partial)),
+                keep_partial,
+            };
+            let registry = Arc::new(JobRegistry::default());
+            let job = {
+                let registry = registry.clone();
+                let output = output.clone();
+                tokio::spawn(async move {
+                    registry
+                        .run_with_output("export", Some(output.clone()), async move {
+                            tokio::fs::write(&output.path, b"[{\"id\": 1},").await.unwrap();
+                            std::future::pending::<Result<(), String>>().await
+                        })
+                        .await
+                })
+            };
+
+            while !output.path.exists() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(registry.cancel_all(), [1]);
+            assert!(job.await.unwrap().unwrap_err().contains("was cancelled"));
+
+            assert!(!output.path.exists());
+            assert_eq!(output.partial_path().exists(), keep_partial);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn quick_exit_is_reported_with_its_code() {
+        let registry = JobRegistry::default();
+        let exit = registry
+            .run("exit", async {
+                let started = Instant::now();
+                let mut child = tokio::process::Command::new("sh")
+                    .args(["-c", "exit 3"])
+                    .spawn()
+                    .map_err(|e| e.to_string())?;
+                let mut reported = None;
+                let status = wait_reporting_exit(&mut child, started, |exit| reported = Some(exit))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                assert_eq!(status.code(), Some(3));
+                Ok(reported)
+            })
+            .await
+            .unwrap()
+            .expect("exit should be reported");
+        assert_eq!(exit.job, Some(1));
+        assert_eq!(exit.exit_code, Some(3));
+        assert_eq!(exit.signal, None);
+        assert!(exit.duration_ms < 5000);
+    }
+}
@@ -0,0 +1,183 @@
+// Incremental splitter for a top-level JSON array. Bytes are fed as they arrive and each
+// complete element is parsed on its own, so the whole array never has to be buffered.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    BeforeArray,
+    ExpectElement { first: bool },
+    InElement,
+    AfterElement,
+    Done,
+}
+
+pub struct JsonArrayStream {
+    state: State,
+    element: Vec<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Default for JsonArrayStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonArrayStream {
+    pub fn new() -> Self {
+        JsonArrayStream {
+            state: State::BeforeArray,
+            element: Vec::new(),
+            depth: 0,
+            in_string: false,
+            escaped: false,
+        }
+    }
+
+    // Feed the next chunk, returning every element completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<serde_json::Value>, String> {
+        let mut values = Vec::new();
+        for &byte in chunk {
+            self.push_byte(byte, &mut values)?;
+        }
+        Ok(values)
+    }
+
+    // Call once the input is exhausted; fails if the array was never closed
+    pub fn finish(&self) -> Result<(), String> {
+        match self.state {
+            State::Done => Ok(()),
+            State::BeforeArray => Err("Expected a JSON array but the output was empty".to_string()),
+            _ => Err("Unexpected end of output inside JSON array".to_string()),
+        }
+    }
+
+    fn push_byte(&mut self, byte: u8, values: &mut Vec<serde_json::Value>) -> Result<(), String> {
+        match self.state {
+            State::BeforeArray => match byte {
+                b'[' => self.state = State::ExpectElement { first: true },
+                b if b.is_ascii_whitespace() => {}
+                b => return Err(format!("Expected a JSON array but found '{}'", b as char)),
+            },
+            State::ExpectElement { first } => match byte {
+                b if b.is_ascii_whitespace() => {}
+                b']' if first => self.state = State::Done,
+                b => {
+                    self.element.clear();
+                    self.element.push(b);
+                    self.depth = usize::from(b == b'{' || b == b'[');
+                    self.in_string = b == b'"';
+                    self.escaped = false;
+                    self.state = State::InElement;
+                }
+            },
+            State::InElement => {
+                if self.in_string {
+                    self.element.push(byte);
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if byte == b'\\' {
+                        self.escaped = true;
+                    } else if byte == b'"' {
+                        self.in_string = false;
+                        if self.depth == 0 {
+                            self.complete_element(values)?;
+                        }
+                    }
+                } else if self.depth == 0 {
+                    // Bare scalar (number, true, null...) ends at the next separator
+                    if byte == b',' || byte == b']' || byte.is_ascii_whitespace() {
+                        self.complete_element(values)?;
+                        return self.push_byte(byte, values);
+                    }
+                    self.element.push(byte);
+                } else {
+                    self.element.push(byte);
+                    match byte {
+                        b'"' => self.in_string = true,
+                        b'{' | b'[' => self.depth += 1,
+                        b'}' | b']' => {
+                            self.depth -= 1;
+                            if self.depth == 0 {
+                                self.complete_element(values)?;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            State::AfterElement => match byte {
+                b',' => self.state = State::ExpectElement { first: false },
+                b']' => self.state = State::Done,
+                b if b.is_ascii_whitespace() => {}
+                b => return Err(format!("Expected ',' or ']' but found '{}'", b as char)),
+            },
+            State::Done => {
+                if !byte.is_ascii_whitespace() {
+                    return Err("Unexpected data after the end of the JSON array".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn complete_element(&mut self, values: &mut Vec<serde_json::Value>) -> Result<(), String> {
+        let value = serde_json::from_slice(&self.element)
+            .map_err(|e| format!("Malformed JSON element: {}", e))?;
+        values.push(value);
+        self.element.clear();
+        self.state = State::AfterElement;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn feed_bytewise(input: &str) -> Result<Vec<serde_json::Value>, String> {
+        let mut stream = JsonArrayStream::new();
+        let mut values = Vec::new();
+        for byte in input.as_bytes() {
+            values.extend(stream.feed(std::slice::from_ref(byte))?);
+        }
+        stream.finish()?;
+        Ok(values)
+    }
+
+    #[test]
+    fn splits_elements_across_chunk_boundaries() {
+        let input = r#" [{"name": "a]\"{", "tags": [1, 2]}, "s", 42 , true,null] "#;
+        let expected = vec![
+            json!({ "name": "a]\"{", "tags": [1, 2] }),
+            json!("s"),
+            json!(42),
+            json!(true),
+            json!(null),
+        ];
+        assert_eq!(feed_bytewise(input).unwrap(), expected);
+
+        let mut stream = JsonArrayStream::new();
+        assert_eq!(stream.feed(input.as_bytes()).unwrap(), expected);
+        assert!(stream.finish().is_ok());
+    }
+
+    #[test]
+    fn empty_array_has_no_elements() {
+        assert_eq!(
+            feed_bytewise("[ ]").unwrap(),
+            Vec::<serde_json::Value>::new()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(feed_bytewise("").is_err());
+        assert!(feed_bytewise("{}").is_err());
+        assert!(feed_bytewise("[1, 2").is_err());
+        assert!(feed_bytewise("[1 2]").is_err());
+        assert!(feed_bytewise("[1] x").is_err());
+    }
+}
@@ -1,12 +1,148 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use azure_cloud::AzureCloudInfo;
+use error::CommandError;
+use preferences::{Preferences, PreferencesState};
+use process_limits::{output_with_limits, ProcessLimits, StdinDelivery};
+use spawn_retry::with_spawn_retry;
+
+mod ansi_html;
+mod az;
+mod az_login;
+mod azure_cloud;
+mod benchmark;
+mod circuit_breaker;
+mod crash;
+mod error;
+mod file_preview;
+mod finder_batch;
+mod finder_capabilities;
+mod finder_progress;
+mod har;
+mod help_text;
+mod http;
+mod http_presets;
+mod inflight;
+mod jobs;
+mod json_stream;
+mod locale;
+mod managed_identity;
+mod output_limit;
+mod ports;
+mod preferences;
+mod preflight;
+mod priority;
+mod process_limits;
+mod recording;
+mod resource_cache;
+mod resource_stream;
+mod resource_views;
+mod ruchy_capabilities;
+mod ruchy_purity;
+mod ruchy_session;
+mod sandbox;
+mod schema_validation;
+mod schema_version;
+mod shell_quote;
+mod spawn_retry;
+mod terminal_format;
+mod timing;
+mod tool_runner;
+mod update_check;
+mod warmup;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CommandOutput {
     pub stdout: String,
     pub stderr: String,
     pub success: bool,
+    // Set when the tool succeeded but printed nothing, so the UI can show an empty state
+    // instead of guessing whether something silently failed
+    #[serde(default)]
+    pub no_results: bool,
+    // stderr lines sorted by their level prefix, so warnings don't read as failures.
+    // Lines matching neither list's prefixes stay only in `stderr`.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+    // Set on Unix when a signal killed the tool, e.g. a segfault or an abort
+    #[serde(default)]
+    pub crash: Option<crash::CrashInfo>,
+    // `stdout` is gzip-compressed and base64-encoded, for large outputs when asked for
+    #[serde(default)]
+    pub compressed: bool,
+    // Set when a stream was written to a file instead of returned
+    #[serde(default)]
+    pub output_files: Option<OutputFileBytes>,
+    // See schema_version::SCHEMA_VERSION; 0 in payloads from before it was added
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+/// Where each stream's file is and how many bytes went into it; None for a stream that
+/// wasn't redirected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputFileBytes {
+    pub stdout_bytes: Option<u64>,
+    pub stderr_bytes: Option<u64>,
+    #[serde(default)]
+    pub stdout_path: Option<String>,
+    #[serde(default)]
+    pub stderr_path: Option<String>,
+}
+
+// A REPL run split into what the UI renders separately. The plain output fields stay
+// alongside so callers reading `stdout` keep working.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuchyResult {
+    #[serde(flatten)]
+    pub output: CommandOutput,
+    // Result of the evaluated expression: the last line the REPL echoed, unless it was unit
+    pub value: Option<String>,
+    // Anything printed before the result
+    pub printed_output: String,
+    pub error: Option<String>,
+    // Whether `error` came from compiling the snippet or from running it; None when there's
+    // no error or it matched neither pattern list
+    pub error_kind: Option<RuchyErrorKind>,
+    // Debug info on how the expression reached the REPL's stdin
+    pub stdin_delivery: Option<StdinDelivery>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuchyErrorKind {
+    // Parse and type errors: the snippet never ran
+    Compile,
+    // Panics and runtime exceptions raised while it ran
+    Runtime,
+}
+
+impl RuchyResult {
+    // Sets `error_kind` from the configured patterns, matched case-insensitively anywhere in the
+    // error. Runtime patterns are tried first since runtime messages often name the failed
+    // operation's type ("Runtime error: type mismatch").
+    pub(crate) fn classify_error(&mut self, prefs: &Preferences) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let error = error.to_lowercase();
+        let matches = |patterns: &[String]| {
+            patterns.iter().any(|pattern| error.contains(&pattern.to_lowercase()))
+        };
+        self.error_kind = if matches(&prefs.ruchy_runtime_error_patterns) {
+            Some(RuchyErrorKind::Runtime)
+        } else if matches(&prefs.ruchy_compile_error_patterns) {
+            Some(RuchyErrorKind::Compile)
+        } else {
+            None
+        };
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,8 +151,30 @@ pub struct ToolInfo {
     pub available: bool,
     pub path: Option<String>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolSymlink {
+    // Path the tool resolved to, as `which` or the preferences reported it
+    pub path: String,
+    // Final target after following every link; same as `path` when it isn't a symlink
+    pub target: String,
+    pub is_symlink: bool,
 }
 
+// Tools check_tool_availability knows how to locate; add new integrations here
+const KNOWN_TOOLS: [&str; 3] = ["azure-resource-finder", "ruchy", "az"];
+
+// Common installation paths for Azure CLI, checked before PATH
+const AZ_COMMON_PATHS: [&str; 4] = [
+    "/usr/local/bin/az",
+    "/opt/homebrew/bin/az",
+    "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
+    "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
+];
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -24,14 +182,39 @@ fn greet(name: &str) -> String {
 }
 
 // Cross-platform tool detection
-fn find_tool_in_path(tool_name: &str) -> Result<Option<String>, String> {
+async fn find_tool_in_path(tool_name: &str) -> Result<Option<String>, String> {
     // Use 'which' on Unix systems, 'where' on Windows
     let command = if cfg!(target_os = "windows") { "where" } else { "which" };
-    
-    let output = Command::new(command)
+    lookup_in_path(command, tool_name, PATH_LOOKUP_TIMEOUT).await
+}
+
+// `which`/`where` can take seconds on a long PATH or one with network drives, so detection
+// gives up after this rather than holding up startup
+const PATH_LOOKUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Runs `command tool_name` and takes the first path it prints. A lookup still running after
+// `timeout` is killed and treated as not found, with a warning logged.
+async fn lookup_in_path(command: &str, tool_name: &str, timeout: std::time::Duration) -> Result<Option<String>, String> {
+    let child = tokio::process::Command::new(command)
         .arg(tool_name)
-        .output()
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
         .map_err(|e| format!("Failed to execute {}: {}", command, e))?;
+
+    // Dropping the timed-out future drops the child, and kill_on_drop takes the lookup down with it
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|e| format!("Failed to execute {}: {}", command, e))?,
+        Err(_) => {
+            log::warn!(
+                "{} {} timed out after {:?}; treating it as not found",
+                command, tool_name, timeout
+            );
+            return Ok(None);
+        }
+    };
     
     if output.status.success() {
         let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -47,20 +230,150 @@ fn find_tool_in_path(tool_name: &str) -> Result<Option<String>, String> {
     Ok(None)
 }
 
+// Every match for the tool: existing `common_paths` first, then each PATH hit in order, without
+// duplicates. Unlike find_tool_in_path this keeps going past the first match.
+fn find_all_tool_paths(tool_name: &str, common_paths: &[&str]) -> Vec<String> {
+    let mut paths: Vec<String> = common_paths
+        .iter()
+        .filter(|path| check_tool_at_path(path))
+        .map(|path| path.to_string())
+        .collect();
+
+    // 'where' already lists every match; 'which' needs -a
+    let mut command = if cfg!(target_os = "windows") {
+        Command::new("where")
+    } else {
+        let mut which = Command::new("which");
+        which.arg("-a");
+        which
+    };
+    if let Ok(output) = command.arg(tool_name).envs(azure_tool_env()).output() {
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let path = line.trim();
+            if !path.is_empty() && !paths.iter().any(|p| p == path) {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    paths
+}
+
 // Check if a specific path exists and is executable
 fn check_tool_at_path(path: &str) -> bool {
-    std::path::Path::new(path).exists()
+    check_executable(std::path::Path::new(path)).is_ok()
+}
+
+// Why `path` can't be run as a tool: missing, a directory, or (on Unix) without an execute bit
+fn check_executable(path: &std::path::Path) -> Result<(), String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("{} does not exist: {}", path.display(), e))?;
+    if metadata.is_dir() {
+        return Err(format!("{} is a directory, not an executable", path.display()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(format!("{} is not executable", path.display()));
+        }
+    }
+    Ok(())
+}
+
+// Environment for spawning Azure tooling: the user's env with common install dirs added
+// to PATH and AZURE_CONFIG_DIR pointing at the user's Azure CLI profile
+fn azure_tool_env() -> HashMap<String, String> {
+    let mut env = std::env::vars().collect::<HashMap<String, String>>();
+    
+    // Ensure common paths are in PATH for Azure CLI access
+    let common_paths = if cfg!(target_os = "windows") {
+        vec![
+            "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin",
+            "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin",
+        ]
+    } else {
+        vec![
+            "/opt/homebrew/bin",
+            "/opt/homebrew/sbin", 
+            "/usr/local/bin",
+            "/usr/local/sbin"
+        ]
+    };
+    
+    let current_path = env.get("PATH").unwrap_or(&String::new()).clone();
+    let mut new_path = current_path.clone();
+    for common_path in common_paths {
+        if !new_path.contains(common_path) {
+            if !new_path.is_empty() {
+                if cfg!(target_os = "windows") {
+                    new_path.push(';');
+                } else {
+                    new_path.push(':');
+                }
+            }
+            new_path.push_str(common_path);
+        }
+    }
+    env.insert("PATH".to_string(), new_path);
+    
+    // az is a Python program and otherwise writes in the console code page on Windows, which
+    // from_utf8_lossy turns into mojibake for non-ASCII resource names
+    if cfg!(target_os = "windows") {
+        env.insert("PYTHONUTF8".to_string(), "1".to_string());
+        env.insert("PYTHONIOENCODING".to_string(), "utf-8".to_string());
+    }
+    
+    // Add Azure-specific environment variables for authentication
+    if let Ok(home) = std::env::var(if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" }) {
+        env.insert("AZURE_CONFIG_DIR".to_string(), format!("{}/.azure", home));
+    }
+    
+    env
+}
+
+// azure_tool_env with AZURE_CONFIG_DIR pointed at `config_dir` when one is given, so a single
+// invocation can use a separate az login. The directory must already exist unless `create` is set.
+fn azure_env_with_config_dir(config_dir: Option<&str>, create: bool) -> Result<HashMap<String, String>, String> {
+    let mut env = azure_tool_env();
+    if let Some(dir) = config_dir {
+        let path = std::path::Path::new(dir);
+        if !path.is_dir() {
+            if !create {
+                return Err(format!("Azure config directory does not exist: {}", dir));
+            }
+            std::fs::create_dir_all(path)
+                .map_err(|e| format!("Failed to create Azure config directory {}: {}", dir, e))?;
+        }
+        env.insert("AZURE_CONFIG_DIR".to_string(), dir.to_string());
+    }
+    Ok(env)
 }
 
 #[tauri::command]
-async fn check_tool_availability(tool: String) -> Result<ToolInfo, String> {
+async fn check_tool_availability(prefs: tauri::State<'_, PreferencesState>, tool: String) -> Result<ToolInfo, String> {
+    locate_tool(tool, &prefs.get()).await
+}
+
+async fn locate_tool(tool: String, prefs: &Preferences) -> Result<ToolInfo, String> {
     let mut tool_info = ToolInfo {
         name: tool.clone(),
         available: false,
         path: None,
         error: None,
+        schema_version: schema_version::SCHEMA_VERSION,
     };
     
+    // A path configured in preferences wins over auto-detection
+    if let Some(custom_path) = prefs.tool_paths.get(&tool) {
+        match check_executable(std::path::Path::new(custom_path)) {
+            Ok(()) => {
+                tool_info.available = true;
+                tool_info.path = Some(custom_path.clone());
+            }
+            Err(e) => tool_info.error = Some(format!("Configured path for {} is unusable: {}", tool, e)),
+        }
+        return Ok(tool_info);
+    }
+    
     match tool.as_str() {
         "azure-resource-finder" => {
             // Check common installation paths
@@ -81,7 +394,7 @@ async fn check_tool_availability(tool: String) -> Result<ToolInfo, String> {
             }
             
             // Try to find in PATH
-            match find_tool_in_path("azure-resource-finder") {
+            match find_tool_in_path("azure-resource-finder").await {
                 Ok(Some(path)) => {
                     tool_info.available = true;
                     tool_info.path = Some(path);
@@ -115,7 +428,7 @@ async fn check_tool_availability(tool: String) -> Result<ToolInfo, String> {
             }
             
             // Try to find in PATH
-            match find_tool_in_path("ruchy") {
+            match find_tool_in_path("ruchy").await {
                 Ok(Some(path)) => {
                     tool_info.available = true;
                     tool_info.path = Some(path);
@@ -130,16 +443,8 @@ async fn check_tool_availability(tool: String) -> Result<ToolInfo, String> {
         }
         
         "az" => {
-            // Check common installation paths for Azure CLI
-            let common_paths = vec![
-                "/usr/local/bin/az",
-                "/opt/homebrew/bin/az",
-                "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
-                "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
-            ];
-            
             // Check common paths first
-            for path in common_paths {
+            for path in AZ_COMMON_PATHS {
                 if check_tool_at_path(path) {
                     tool_info.available = true;
                     tool_info.path = Some(path.to_string());
@@ -148,7 +453,7 @@ async fn check_tool_availability(tool: String) -> Result<ToolInfo, String> {
             }
             
             // Try to find in PATH
-            match find_tool_in_path("az") {
+            match find_tool_in_path("az").await {
                 Ok(Some(path)) => {
                     tool_info.available = true;
                     tool_info.path = Some(path);
@@ -171,139 +476,525 @@ async fn check_tool_availability(tool: String) -> Result<ToolInfo, String> {
 }
 
 #[tauri::command]
-async fn run_azure_resource_finder(args: Vec<String>) -> Result<CommandOutput, String> {
-    // Get tool info to find the correct path
-    let tool_info = check_tool_availability("azure-resource-finder".to_string()).await?;
+async fn known_tools(prefs: tauri::State<'_, PreferencesState>) -> Result<Vec<ToolInfo>, String> {
+    // Check every tool concurrently, then collect in KNOWN_TOOLS order
+    let prefs = prefs.get();
+    let handles: Vec<_> = KNOWN_TOOLS
+        .iter()
+        .map(|tool| {
+            let tool = tool.to_string();
+            let prefs = prefs.clone();
+            tokio::spawn(async move { locate_tool(tool, &prefs).await })
+        })
+        .collect();
     
-    if !tool_info.available {
-        return Err(tool_info.error.unwrap_or_else(|| "Azure Resource Finder not available".to_string()));
+    let mut tools = Vec::with_capacity(handles.len());
+    for handle in handles {
+        let tool_info = handle
+            .await
+            .map_err(|e| format!("Tool check task failed: {}", e))??;
+        tools.push(tool_info);
     }
     
-    let azure_finder_path = tool_info.path.unwrap();
-    
-    // Get the current PATH and ensure Azure CLI is accessible
-    let mut env = std::env::vars().collect::<HashMap<String, String>>();
-    let current_path = env.get("PATH").unwrap_or(&String::new()).clone();
-    
-    // Ensure common paths are in PATH for Azure CLI access
-    let common_paths = if cfg!(target_os = "windows") {
-        vec![
-            "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin",
-            "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin",
-        ]
+    Ok(tools)
+}
+
+fn resolve_symlink(path: &str) -> Result<ToolSymlink, String> {
+    let is_symlink = std::fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to inspect {}: {}", path, e))?
+        .file_type()
+        .is_symlink();
+    let target = if is_symlink {
+        std::fs::canonicalize(path)
+            .map_err(|e| format!("Failed to resolve {}: {}", path, e))?
+            .to_string_lossy()
+            .to_string()
     } else {
-        vec![
-            "/opt/homebrew/bin",
-            "/opt/homebrew/sbin", 
-            "/usr/local/bin",
-            "/usr/local/sbin"
-        ]
+        path.to_string()
     };
     
-    let mut new_path = current_path.clone();
-    for common_path in common_paths {
-        if !new_path.contains(common_path) {
-            if !new_path.is_empty() {
-                if cfg!(target_os = "windows") {
-                    new_path.push(';');
-                } else {
-                    new_path.push(':');
-                }
-            }
-            new_path.push_str(common_path);
+    Ok(ToolSymlink {
+        path: path.to_string(),
+        target,
+        is_symlink,
+    })
+}
+
+// Shows which concrete install a tool name maps to, e.g. behind a homebrew shim
+#[tauri::command]
+async fn resolve_tool_symlink(prefs: tauri::State<'_, PreferencesState>, tool: String) -> Result<ToolSymlink, String> {
+    let tool_info = locate_tool(tool.clone(), &prefs.get()).await?;
+    
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| format!("{} not available", tool)));
+    }
+    
+    resolve_symlink(&tool_info.path.unwrap())
+}
+
+// JMESPath filter flag understood by azure-resource-finder (and the az commands it wraps)
+const FINDER_QUERY_FLAG: &str = "--query";
+
+// Optional passthrough flags for a finder run
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FinderOptions {
+    // JMESPath filter passed as `--query` so the tool trims its own output; whether it's
+    // honoured depends on the installed finder build
+    query: Option<String>,
+    // Adds the configured debug flag and lets the wrapped az calls log more than errors
+    verbose: bool,
+    // Limits the scan to one group
+    resource_group: Option<String>,
+    // Widens the scan to every subscription under the group (a bare id or its full resource id)
+    management_group: Option<String>,
+    // Niceness for the finder process, e.g. 10 so a long scan doesn't make the UI sluggish;
+    // None runs it at normal priority
+    priority: Option<i32>,
+}
+
+// Per-call settings for `run_azure_resource_finder`, passed as one `options` object; every
+// field may be left out
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FinderRunOptions {
+    #[serde(flatten)]
+    finder: FinderOptions,
+    // Leaves out the tool's `default_args` from preferences
+    skip_defaults: bool,
+    // Replaces the usual ~/.azure for this run, e.g. to scan with another identity
+    azure_config_dir: Option<String>,
+    // Creates `azure_config_dir` when it's missing
+    create_config_dir: bool,
+    // Stores a successful run's resources in the resource cache
+    cache_results: bool,
+    // Returns stdout gzipped and base64-encoded with `compressed` set, keeping multi-megabyte
+    // results cheap to pass over IPC
+    compress_output: bool,
+    // Write that stream to a file (relative paths land under `workspace_dir`) instead of
+    // returning it, reporting paths and sizes in `output_files`
+    output_stdout_file: Option<String>,
+    output_stderr_file: Option<String>,
+}
+
+// ARM path prefix of a management group's full resource id
+const MANAGEMENT_GROUP_ID_PREFIX: &str = "/providers/Microsoft.Management/managementGroups/";
+
+// The management group id from a bare id or its full resource id. Ids are up to 90 letters,
+// digits, `-`, `_`, `(`, `)` and `.`, and can't end with a period.
+fn management_group_id(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let id = match value.get(..MANAGEMENT_GROUP_ID_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(MANAGEMENT_GROUP_ID_PREFIX) => {
+            value[MANAGEMENT_GROUP_ID_PREFIX.len()..].trim_end_matches('/')
         }
+        _ => value,
+    };
+    let valid = !id.is_empty()
+        && id.len() <= 90
+        && !id.ends_with('.')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || "-_().".contains(c));
+    if !valid {
+        return Err(format!("Not a valid management group id: '{}'", value));
+    }
+    Ok(id.to_string())
+}
+
+// Build the finder argv from the caller's args plus any optional passthrough flags. Flag names
+// other than `--query` come from preferences so they can match the installed finder build.
+fn build_finder_args(mut args: Vec<String>, options: &FinderOptions, prefs: &Preferences) -> Result<Vec<String>, String> {
+    if let Some(query) = &options.query {
+        if query.trim().is_empty() {
+            return Err("JMESPath query must not be empty".to_string());
+        }
+        args.push(FINDER_QUERY_FLAG.to_string());
+        args.push(query.clone());
     }
     
-    // Add the updated PATH to environment
-    env.insert("PATH".to_string(), new_path);
+    if let Some(resource_group) = &options.resource_group {
+        if resource_group.trim().is_empty() {
+            return Err("Resource group must not be empty".to_string());
+        }
+        if prefs.finder_resource_group_flag.trim().is_empty() {
+            return Err("finder_resource_group_flag must not be empty".to_string());
+        }
+        args.push(prefs.finder_resource_group_flag.clone());
+        args.push(resource_group.trim().to_string());
+    }
     
-    // Add Azure-specific environment variables for authentication
-    if let Ok(home) = std::env::var(if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" }) {
-        env.insert("AZURE_CONFIG_DIR".to_string(), format!("{}/.azure", home));
+    if let Some(management_group) = &options.management_group {
+        // A resource group sits inside one subscription, so it can't narrow a tenant-wide scope
+        if options.resource_group.is_some() {
+            return Err("Scope a scan to a resource group or a management group, not both".to_string());
+        }
+        if prefs.finder_management_group_flag.trim().is_empty() {
+            return Err("finder_management_group_flag must not be empty".to_string());
+        }
+        args.push(prefs.finder_management_group_flag.clone());
+        args.push(management_group_id(management_group)?);
     }
     
-    // Ensure we have the Azure CLI profile directory
-    if let Ok(home) = std::env::var(if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" }) {
-        let azure_dir = format!("{}/.azure", home);
-        if std::path::Path::new(&azure_dir).exists() {
-            env.insert("AZURE_CONFIG_DIR".to_string(), azure_dir);
+    if options.verbose {
+        let flag = &prefs.finder_verbose_flag;
+        if flag.trim().is_empty() {
+            return Err("finder_verbose_flag must not be empty".to_string());
+        }
+        if !args.iter().any(|arg| arg == flag) {
+            args.push(flag.clone());
         }
     }
     
-    let output = Command::new(&azure_finder_path)
-        .args(&args)
-        .envs(&env)
-        .output()
+    Ok(args)
+}
+
+// Runs azure-resource-finder with `args` (after the tool's `default_args`) and the optional
+// per-call `options`, see `FinderRunOptions`
+#[tauri::command]
+async fn run_azure_resource_finder(
+    prefs: tauri::State<'_, PreferencesState>,
+    recording: tauri::State<'_, recording::RecordingState>,
+    timing: tauri::State<'_, timing::TimingHistograms>,
+    cache: tauri::State<'_, resource_cache::ResourceCache>,
+    args: Vec<String>,
+    options: Option<FinderRunOptions>,
+) -> Result<CommandOutput, String> {
+    let prefs = prefs.get();
+    let FinderRunOptions {
+        finder: options,
+        skip_defaults,
+        azure_config_dir,
+        create_config_dir,
+        cache_results,
+        compress_output,
+        output_stdout_file,
+        output_stderr_file,
+    } = options.unwrap_or_default();
+    let args = prefs.args_for("azure-resource-finder", args, skip_defaults);
+    let args = build_finder_args(args, &options, &prefs)?;
+    
+    // Get tool info to find the correct path
+    let tool_info = locate_tool("azure-resource-finder".to_string(), &prefs).await?;
+    
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure Resource Finder not available".to_string()));
+    }
+    
+    let azure_finder_path = tool_info.path.unwrap();
+    
+    let env = azure_env_with_config_dir(azure_config_dir.as_deref(), create_config_dir)?;
+    let files = StreamFiles {
+        stdout: output_stdout_file.map(|path| prefs.resolve_output_path(&path)).transpose()?,
+        stderr: output_stderr_file.map(|path| prefs.resolve_output_path(&path)).transpose()?,
+    };
+    let started = std::time::Instant::now();
+    let mut result = execute_finder_to_files(
+        &azure_finder_path,
+        &args,
+        env,
+        options.verbose,
+        options.priority,
+        &prefs,
+        &files,
+    );
+    timing.record("azure-resource-finder", started.elapsed());
+    if let Ok(output) = &mut result {
+        if output.success && cache_results {
+            cache_finder_output(&cache, output, &args, &prefs);
+        }
+    }
+    recording.record("run_azure_resource_finder", &args, &result);
+    if compress_output {
+        if let Ok(output) = &mut result {
+            compress_stdout(output)?;
+        }
+    }
+    result
+}
+
+fn compress_stdout(output: &mut CommandOutput) -> Result<(), String> {
+    use base64::Engine;
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let gzipped = encoder
+        .write_all(output.stdout.as_bytes())
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress output: {}", e))?;
+    output.stdout = base64::engine::general_purpose::STANDARD.encode(gzipped);
+    output.compressed = true;
+    Ok(())
+}
+
+// The subscription a finder run was pointed at, from `--subscription`/`-s` in its args
+fn finder_subscription(args: &[String]) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix("--subscription=") {
+        Some(value) => Some(value.to_string()),
+        None if arg == "--subscription" || arg == "-s" => args.get(i + 1).cloned(),
+        None => None,
+    })
+}
+
+// Saves a successful run's resources to the cache. Output that isn't a JSON resource list, or a
+// cache failure, only adds a warning since the run itself worked.
+fn cache_finder_output(
+    cache: &resource_cache::ResourceCache,
+    output: &mut CommandOutput,
+    args: &[String],
+    prefs: &Preferences,
+) {
+    let Some(resources) = resource_cache::parse_finder_resources(&output.stdout) else {
+        let warning = "Results not cached: the finder output isn't a JSON resource list";
+        output.warnings.push(warning.to_string());
+        return;
+    };
+    let subscription = finder_subscription(args).or_else(|| prefs.default_subscription.clone());
+    if let Err(e) = cache.store_run(subscription.as_deref(), &resources, prefs.into()) {
+        output.warnings.push(format!("Results not cached: {}", e));
+    }
+}
+
+// Split stderr into (warnings, errors) by the configured line prefixes, ignoring case
+fn classify_stderr(stderr: &str, prefs: &Preferences) -> (Vec<String>, Vec<String>) {
+    let has_prefix = |line: &str, prefixes: &[String]| {
+        let line = line.to_lowercase();
+        prefixes.iter().any(|prefix| line.starts_with(&prefix.to_lowercase()))
+    };
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    for line in stderr.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if has_prefix(line, &prefs.stderr_error_prefixes) {
+            errors.push(line.to_string());
+        } else if has_prefix(line, &prefs.stderr_warning_prefixes) {
+            warnings.push(line.to_string());
+        }
+    }
+    (warnings, errors)
+}
+
+// `env` should come from azure_tool_env so Azure CLI is reachable and authenticated from the
+// finder's environment
+fn execute_finder(
+    azure_finder_path: &str,
+    args: &[String],
+    env: HashMap<String, String>,
+    verbose: bool,
+    priority: Option<i32>,
+    prefs: &Preferences,
+) -> Result<CommandOutput, String> {
+    let files = StreamFiles::default();
+    execute_finder_to_files(azure_finder_path, args, env, verbose, priority, prefs, &files)
+}
+
+/// Files to write the finder's stdout and stderr to instead of returning them
+#[derive(Debug, Default)]
+struct StreamFiles {
+    stdout: Option<PathBuf>,
+    stderr: Option<PathBuf>,
+}
+
+fn create_stream_file(path: &Path) -> Result<std::fs::File, String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))
+}
+
+fn written_bytes(path: Option<&PathBuf>) -> Result<Option<u64>, String> {
+    path.map(|path| {
+        std::fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    })
+    .transpose()
+}
+
+// Like execute_finder, with each stream in `files` going straight to its file so a huge output
+// is never buffered. The redirected streams come back empty, with their sizes in `output_files`.
+fn execute_finder_to_files(
+    azure_finder_path: &str,
+    args: &[String],
+    env: HashMap<String, String>,
+    verbose: bool,
+    priority: Option<i32>,
+    prefs: &Preferences,
+    files: &StreamFiles,
+) -> Result<CommandOutput, String> {
+    let mut command = finder_command(azure_finder_path, args, env, verbose, priority);
+    if let Some(path) = &files.stdout {
+        command.stdout(create_stream_file(path)?);
+    }
+    if let Some(path) = &files.stderr {
+        command.stderr(create_stream_file(path)?);
+    }
+    let child = with_spawn_retry(|| command.spawn())
         .map_err(|e| format!("Failed to execute azure-resource-finder: {}", e))?;
+    let output = output_limit::collect_output(child, prefs.max_buffered_output_bytes)
+        .map_err(|e| e.to_string())?;
+    let mut result = finder_output(&output, prefs);
+
+    if files.stdout.is_some() || files.stderr.is_some() {
+        let stdout_bytes = written_bytes(files.stdout.as_ref())?;
+        if let Some(bytes) = stdout_bytes {
+            result.no_results = result.success && bytes == 0;
+        }
+        let display = |path: &Option<PathBuf>| path.as_ref().map(|path| path.display().to_string());
+        result.output_files = Some(OutputFileBytes {
+            stdout_bytes,
+            stderr_bytes: written_bytes(files.stderr.as_ref())?,
+            stdout_path: display(&files.stdout),
+            stderr_path: display(&files.stderr),
+        });
+    }
+    Ok(result)
+}
+
+fn finder_command(
+    azure_finder_path: &str,
+    args: &[String],
+    mut env: HashMap<String, String>,
+    verbose: bool,
+    priority: Option<i32>,
+) -> Command {
+    if verbose {
+        // Overrides an `only_show_errors` setting in the user's az config
+        env.insert("AZURE_CORE_ONLY_SHOW_ERRORS".to_string(), "false".to_string());
+    }
     
+    let mut command = Command::new(azure_finder_path);
+    command
+        .args(args)
+        .envs(&env)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    if let Some(priority) = priority {
+        priority::apply_priority(&mut command, priority);
+    }
+    command
+}
+
+fn finder_output(output: &std::process::Output, prefs: &Preferences) -> CommandOutput {
     // If the command failed, provide more detailed error information
+    let (warnings, errors) = classify_stderr(&String::from_utf8_lossy(&output.stderr), prefs);
+    let crash = crash::crash_info(&output.status);
+    
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         let stdout = String::from_utf8_lossy(&output.stdout);
         
         // Check if it's an authentication error
         if stderr.contains("DefaultAzureCredential") || stderr.contains("failed to acquire a token") {
-            return Ok(CommandOutput {
+            return CommandOutput {
                 stdout: stdout.to_string(),
                 stderr: format!("Azure authentication failed. Please ensure you are logged in with 'az login' and have the necessary permissions.\n\nError details:\n{}", stderr),
                 success: false,
-            });
+                no_results: false,
+                warnings,
+                errors,
+                crash,
+                compressed: false,
+                output_files: None,
+                schema_version: schema_version::SCHEMA_VERSION,
+            };
         }
     }
     
-    Ok(CommandOutput {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    // An empty scope (e.g. a subscription with no resources) is a valid, successful answer
+    let no_results = output.status.success() && stdout.trim().is_empty();
+    
+    CommandOutput {
+        stdout,
         stderr: String::from_utf8_lossy(&output.stderr).to_string(),
         success: output.status.success(),
-    })
+        no_results,
+        warnings,
+        errors,
+        crash,
+        compressed: false,
+        output_files: None,
+        schema_version: schema_version::SCHEMA_VERSION,
+    }
 }
 
+// Each call runs in its own REPL instance. Snippets may be untrusted, so the run is killed
+// with a `Timeout` error after `timeout_secs` (the `ruchy_eval_timeout_secs` preference when
+// unset; 0 disables), and on Unix `max_memory_mb` / `max_cpu_secs` cap it with rlimits,
+// failing with `ResourceLimit` when hit.
 #[tauri::command]
-async fn run_ruchy_repl(command: String) -> Result<CommandOutput, String> {
-    use std::io::Write;
-    use std::process::{Command, Stdio};
-    
+async fn run_ruchy_repl(
+    prefs: tauri::State<'_, PreferencesState>,
+    recording: tauri::State<'_, recording::RecordingState>,
+    timing: tauri::State<'_, timing::TimingHistograms>,
+    command: String,
+    timeout_secs: Option<u64>,
+    max_memory_mb: Option<u64>,
+    max_cpu_secs: Option<u64>,
+) -> Result<RuchyResult, CommandError> {
+    let prefs = prefs.get();
     // Get tool info to find the correct path
-    let tool_info = check_tool_availability("ruchy".to_string()).await?;
+    let tool_info = locate_tool("ruchy".to_string(), &prefs).await?;
     
     if !tool_info.available {
-        return Err(tool_info.error.unwrap_or_else(|| "Ruchy not available".to_string()));
+        return Err(tool_info.error.unwrap_or_else(|| "Ruchy not available".to_string()).into());
     }
     
     let ruchy_path = tool_info.path.unwrap();
     
     // For now, we'll use a simpler approach - each command runs in its own REPL instance
     // but we'll format it to look like a continuous session
-    let mut child = Command::new(&ruchy_path)
-        .arg("repl")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn ruchy: {}", e))?;
+    let mut repl = tokio::process::Command::new(&ruchy_path);
+    repl.arg("repl");
+    let timeout_secs = timeout_secs.unwrap_or(prefs.ruchy_eval_timeout_secs);
+    let limits = ProcessLimits {
+        timeout: (timeout_secs > 0).then(|| std::time::Duration::from_secs(timeout_secs)),
+        max_memory_mb,
+        max_cpu_secs,
+    };
     
-    // Write command to stdin
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin.write_all(format!("{}\n", command).as_bytes())
-            .map_err(|e| format!("Failed to write to ruchy stdin: {}", e))?;
-        stdin.write_all(b":quit\n")
-            .map_err(|e| format!("Failed to write exit command: {}", e))?;
+    // Send the command, then quit so the REPL exits once it has answered
+    let input = format!("{}\n:quit\n", command).into_bytes();
+    let started = std::time::Instant::now();
+    let delivered = output_with_limits(repl, input, &limits).await;
+    timing.record("ruchy", started.elapsed());
+    let (output, stdin_delivery) = delivered?;
+    let result = ruchy_repl_result(&output, stdin_delivery).map(|mut result| {
+        result.classify_error(&prefs);
+        result
+    });
+    recording.record("run_ruchy_repl", &[command], &result);
+    result
+}
+
+// A REPL that quits on its own (e.g. the command ended the session) breaks the pipe before
+// `:quit` is written; that's a normal early exit and whatever it printed is still the answer.
+// Any other write failure means the command may never have arrived.
+fn ruchy_repl_result(
+    output: &std::process::Output,
+    stdin_delivery: StdinDelivery,
+) -> Result<RuchyResult, CommandError> {
+    if let Some(error) = &stdin_delivery.error {
+        return Err(format!("Failed to write to ruchy stdin: {}", error).into());
     }
-    
-    // Wait for the process to complete
-    let output = child.wait_with_output()
-        .map_err(|e| format!("Failed to read ruchy output: {}", e))?;
-    
     // Process the output to remove the welcome/goodbye messages for cleaner display
     let stdout_str = String::from_utf8_lossy(&output.stdout);
     let stderr_str = String::from_utf8_lossy(&output.stderr);
-    
+    let mut result = ruchy_command_output(&stdout_str, &stderr_str);
+    result.stdin_delivery = Some(stdin_delivery);
+    Ok(result)
+}
+
+// Turn raw REPL output into a RuchyResult, dropping the banner and prompt noise
+fn ruchy_command_output(stdout_str: &str, stderr_str: &str) -> RuchyResult {
     // Combine stdout and stderr for Ruchy (it sometimes outputs to stderr)
     let combined_output = format!("{}{}", stdout_str, stderr_str);
     let lines: Vec<&str> = combined_output.lines().collect();
     
-    // Filter out the welcome and goodbye messages and process the output
+    // Filter out the welcome and goodbye messages and process the output, sorting lines into
+    // results and errors as we go
     let mut filtered_output = Vec::new();
+    let mut result_lines = Vec::new();
+    let mut errors = Vec::new();
     for line in lines.iter() {
         if !line.contains("Welcome to Ruchy REPL") &&
            !line.contains("Type :help") &&
@@ -314,11 +1005,14 @@ async fn run_ruchy_repl(command: String) -> Result<CommandOutput, String> {
             if line.starts_with("Error: return:") {
                 // Extract the actual return value
                 let return_value = line.replace("Error: return:", "").trim().to_string();
+                result_lines.push(return_value.clone());
                 filtered_output.push(return_value);
             } else if line.starts_with("Error:") {
                 // Keep other errors as-is
+                errors.push(line.trim_start_matches("Error:").trim().to_string());
                 filtered_output.push(line.to_string());
             } else {
+                result_lines.push(line.to_string());
                 filtered_output.push(line.to_string());
             }
         }
@@ -330,17 +1024,43 @@ async fn run_ruchy_repl(command: String) -> Result<CommandOutput, String> {
     // Determine success based on whether we got a real error or just a return "error"
     let is_success = !clean_output.starts_with("Error:") || stderr_str.contains("Error: return:");
     
-    Ok(CommandOutput {
-        stdout: clean_output,
-        stderr: if is_success { String::new() } else { stderr_str.to_string() },
-        success: is_success,
-    })
+    // The REPL echoes the expression's value last; a failed eval has no value
+    let value = if errors.is_empty() {
+        result_lines.pop().filter(|last| last != "()")
+    } else {
+        None
+    };
+    
+    RuchyResult {
+        output: CommandOutput {
+            stdout: clean_output,
+            stderr: if is_success { String::new() } else { stderr_str.to_string() },
+            success: is_success,
+            no_results: false,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            crash: None,
+            compressed: false,
+            output_files: None,
+            schema_version: schema_version::SCHEMA_VERSION,
+        },
+        value,
+        printed_output: result_lines.join("\n"),
+        error: if errors.is_empty() { None } else { Some(errors.join("\n")) },
+        error_kind: None,
+        stdin_delivery: None,
+    }
 }
 
 #[tauri::command]
-async fn check_azure_auth_status() -> Result<serde_json::Value, String> {
+async fn check_azure_auth_status(
+    prefs: tauri::State<'_, PreferencesState>,
+    identity_probe: tauri::State<'_, managed_identity::ManagedIdentityProbe>,
+    azure_config_dir: Option<String>,
+    create_config_dir: Option<bool>,
+) -> Result<serde_json::Value, String> {
     // Check if Azure CLI is available
-    let tool_info = check_tool_availability("az".to_string()).await?;
+    let tool_info = locate_tool("az".to_string(), &prefs.get()).await?;
     let az_available = tool_info.available;
     
     if !az_available {
@@ -351,48 +1071,13 @@ async fn check_azure_auth_status() -> Result<serde_json::Value, String> {
             "error": "Azure CLI not found"
         }));
     }
+    let az_path = tool_info.path.unwrap();
     
-    // Set up environment variables for Azure CLI
-    let mut env = std::env::vars().collect::<HashMap<String, String>>();
-    
-    // Ensure common paths are in PATH for Azure CLI access
-    let common_paths = if cfg!(target_os = "windows") {
-        vec![
-            "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin",
-            "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin",
-        ]
-    } else {
-        vec![
-            "/opt/homebrew/bin",
-            "/opt/homebrew/sbin", 
-            "/usr/local/bin",
-            "/usr/local/sbin"
-        ]
-    };
-    
-    let current_path = env.get("PATH").unwrap_or(&String::new()).clone();
-    let mut new_path = current_path.clone();
-    for common_path in common_paths {
-        if !new_path.contains(common_path) {
-            if !new_path.is_empty() {
-                if cfg!(target_os = "windows") {
-                    new_path.push(';');
-                } else {
-                    new_path.push(':');
-                }
-            }
-            new_path.push_str(common_path);
-        }
-    }
-    env.insert("PATH".to_string(), new_path);
-    
-    // Add Azure-specific environment variables for authentication
-    if let Ok(home) = std::env::var(if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" }) {
-        env.insert("AZURE_CONFIG_DIR".to_string(), format!("{}/.azure", home));
-    }
+    // Set up environment variables for Azure CLI, optionally with a per-identity config dir
+    let env = azure_env_with_config_dir(azure_config_dir.as_deref(), create_config_dir.unwrap_or(false))?;
     
     // Check if user is logged in with proper environment
-    let account_output = Command::new("az")
+    let account_output = Command::new(&az_path)
         .arg("account")
         .arg("show")
         .envs(&env)
@@ -400,19 +1085,13 @@ async fn check_azure_auth_status() -> Result<serde_json::Value, String> {
     
     let is_logged_in = account_output.is_ok() && account_output.as_ref().unwrap().status.success();
     
-    // Get account info if logged in
-    let account_info = if is_logged_in {
-        if let Ok(output) = &account_output {
-            if let Ok(json_str) = String::from_utf8(output.stdout.clone()) {
-                serde_json::from_str(&json_str).unwrap_or(serde_json::json!({}))
-            } else {
-                serde_json::json!({})
-            }
-        } else {
-            serde_json::json!({})
+    // Get account info if logged in, keeping any notice az printed ahead of the JSON apart
+    let (account_info, stdout_warning) = match &account_output {
+        Ok(output) if is_logged_in => {
+            let (json, warning) = az::split_az_json(&output.stdout);
+            (serde_json::from_slice(json).unwrap_or(serde_json::json!({})), warning)
         }
-    } else {
-        serde_json::json!({})
+        _ => (serde_json::json!({}), None),
     };
     
     // Get error details if account check failed
@@ -441,109 +1120,43 @@ async fn check_azure_auth_status() -> Result<serde_json::Value, String> {
         "".to_string()
     };
     
+    // Which cloud the login belongs to decides the endpoints the app should call
+    let cloud = AzureCloudInfo::from_account(&account_info);
+
+    // Without an interactive login, a managed identity still lets az run headless on Azure. A
+    // machine found without one isn't probed again for a while.
+    let managed_identity = if is_logged_in {
+        None
+    } else {
+        identity_probe
+            .status(managed_identity::IMDS_BASE_URL, managed_identity::ARM_RESOURCE)
+            .await
+    };
+    
     Ok(serde_json::json!({
         "azure_cli_available": az_available,
         "is_logged_in": is_logged_in,
         "account_info": account_info,
+        "cloud": cloud.cloud,
+        "arm_base_url": cloud.arm_base_url,
+        "portal_base_url": cloud.portal_base_url,
         "error": if !is_logged_in { error_details } else { "".to_string() },
+        "managed_identity": managed_identity,
+        "stdout_warning": stdout_warning,
         "debug_info": {
             "path": env.get("PATH"),
             "azure_config_dir": env.get("AZURE_CONFIG_DIR"),
             "home": std::env::var(if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" }).ok(),
-            "platform": if cfg!(target_os = "windows") { "windows" } else { "unix" }
+            "platform": if cfg!(target_os = "windows") { "windows" } else { "unix" },
+            "locale": locale::current_locale()
         }
     }))
 }
 
-#[tauri::command]
-async fn http_request(
-    url: String, 
-    method: Option<String>, 
-    headers: HashMap<String, String>, 
-    body: Option<String>
-) -> Result<serde_json::Value, String> {
-    let client = reqwest::Client::new();
-    let method = method.unwrap_or_else(|| "GET".to_string());
-    
-    let mut request = match method.to_uppercase().as_str() {
-        "GET" => client.get(&url),
-        "POST" => client.post(&url),
-        "PUT" => client.put(&url),
-        "DELETE" => client.delete(&url),
-        "PATCH" => client.patch(&url),
-        _ => return Err(format!("Unsupported HTTP method: {}", method)),
-    };
-    
-    // Add headers
-    for (key, value) in headers {
-        request = request.header(&key, &value);
-    }
-    
-    // Add body for POST/PUT/PATCH requests
-    if let Some(body_data) = body {
-        if ["POST", "PUT", "PATCH"].contains(&method.to_uppercase().as_str()) {
-            request = request.body(body_data);
-        }
-    }
-    
-    let response = request
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    let status = response.status();
-    if !status.is_success() {
-        return Err(format!("HTTP error: {}", status));
-    }
-    
-    let json: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    
-    Ok(json)
-}
-
 #[tauri::command]
 async fn test_azure_cli() -> Result<serde_json::Value, String> {
     // Set up environment variables for Azure CLI
-    let mut env = std::env::vars().collect::<HashMap<String, String>>();
-    
-    // Ensure common paths are in PATH for Azure CLI access
-    let common_paths = if cfg!(target_os = "windows") {
-        vec![
-            "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin",
-            "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin",
-        ]
-    } else {
-        vec![
-            "/opt/homebrew/bin",
-            "/opt/homebrew/sbin", 
-            "/usr/local/bin",
-            "/usr/local/sbin"
-        ]
-    };
-    
-    let current_path = env.get("PATH").unwrap_or(&String::new()).clone();
-    let mut new_path = current_path.clone();
-    for common_path in common_paths {
-        if !new_path.contains(common_path) {
-            if !new_path.is_empty() {
-                if cfg!(target_os = "windows") {
-                    new_path.push(';');
-                } else {
-                    new_path.push(':');
-                }
-            }
-            new_path.push_str(common_path);
-        }
-    }
-    env.insert("PATH".to_string(), new_path);
-    
-    // Add Azure-specific environment variables for authentication
-    if let Ok(home) = std::env::var(if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" }) {
-        env.insert("AZURE_CONFIG_DIR".to_string(), format!("{}/.azure", home));
-    }
+    let env = azure_tool_env();
     
     // Test Azure CLI version
     let version_output = Command::new("az")
@@ -570,18 +1183,11 @@ async fn test_azure_cli() -> Result<serde_json::Value, String> {
         .output();
     
     let account_available = account_output.is_ok() && account_output.as_ref().unwrap().status.success();
-    let account_info = if account_available {
-        if let Ok(output) = &account_output {
-            if let Ok(json_str) = String::from_utf8(output.stdout.clone()) {
-                serde_json::from_str(&json_str).unwrap_or(serde_json::json!({}))
-            } else {
-                serde_json::json!({})
-            }
-        } else {
-            serde_json::json!({})
+    let account_info = match &account_output {
+        Ok(output) if account_available => {
+            serde_json::from_slice(az::split_az_json(&output.stdout).0).unwrap_or(serde_json::json!({}))
         }
-    } else {
-        serde_json::json!({})
+        _ => serde_json::json!({}),
     };
     
     // Get error details if account check failed
@@ -630,15 +1236,458 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
+        .manage(circuit_breaker::CircuitBreakers::default())
+        .manage(jobs::JobRegistry::default())
+        .manage(http::InFlightRequests::default())
+        .manage(http::ActiveRequests::default())
+        .manage(ruchy_session::RuchySessionState::default())
+        .manage(warmup::ToolVersions::default())
+        .manage(az::LocationsCache::default())
+        .manage(az::TokenStatusCache::default())
+        .manage(managed_identity::ManagedIdentityProbe::default())
+        .manage(recording::RecordingState::default())
+        .manage(timing::TimingHistograms::default())
+        .manage(ruchy_capabilities::RuchyCapabilitiesCache::default())
+        .manage(finder_capabilities::FinderJsonSupport::default())
+        .manage(finder_capabilities::FinderFlags::default())
+        .setup(|app| {
+            // Preferences live in the platform config dir, e.g. ~/Library/Application Support/<identifier>
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(PreferencesState::load(config_dir.join(preferences::PREFERENCES_FILE)));
+            app.manage(http_presets::HttpPresets::new(config_dir.join(http_presets::HTTP_PRESETS_FILE)));
+            app.manage(resource_cache::ResourceCache::new(config_dir.join(resource_cache::RESOURCE_CACHE_FILE)));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet, 
-            http_request,
+            http::http_request,
+            http::http_capabilities,
+            http::validate_http_config,
+            http::cancel_http_request,
+            har::export_http_har,
+            http_presets::save_http_preset,
+            http_presets::list_http_presets,
+            http_presets::run_http_preset,
             run_azure_resource_finder,
+            finder_batch::run_azure_finder_batch,
+            finder_capabilities::finder_supports_json,
+            finder_capabilities::validate_finder_args,
+            finder_progress::follow_finder_progress,
+            resource_stream::stream_azure_resources,
+            jobs::cancel_all_jobs,
+            jobs::list_app_children,
+            jobs::kill_app_child,
             run_ruchy_repl,
+            ruchy_session::ruchy_session_start,
+            ruchy_session::ruchy_session_eval,
+            ruchy_session::ruchy_session_status,
+            ruchy_session::ruchy_session_stop,
+            ruchy_session::ruchy_type_of,
+            ruchy_session::ruchy_transcript,
+            ruchy_session::clear_ruchy_transcript,
+            ruchy_session::run_ruchy_cells,
+            ruchy_capabilities::ruchy_capabilities,
             check_tool_availability,
+            known_tools,
+            shell_quote::shell_quote_command,
+            terminal_format::format_for_terminal,
+            file_preview::read_file_chunk,
+            resource_views::build_resource_tree,
+            resource_views::summarize_resources_by_type,
+            resource_views::export_resources_markdown,
+            resource_views::merge_resource_listings,
+            resource_views::parse_resource_id,
+            resource_views::build_resource_id,
+            schema_validation::validate_json_schema,
+            recording::start_recording,
+            recording::stop_recording,
+            recording::replay_recording,
+            resolve_tool_symlink,
+            warmup::warm_up_tools,
             check_azure_auth_status,
-            test_azure_cli
+            locale::locale_info,
+            output_limit::buffered_output_bytes,
+            update_check::check_for_update,
+            test_azure_cli,
+            benchmark::benchmark_az_auth,
+            benchmark::benchmark_http,
+            az::run_az,
+            az::run_az_batch,
+            az::list_azure_locations,
+            az::list_resource_groups,
+            az::count_azure_resources,
+            az::get_azure_resources,
+            az::resolve_subscription,
+            az::run_az_typed,
+            az::check_provider_registration,
+            az::register_provider,
+            az::verify_az_identity,
+            az::az_config_dump,
+            az::list_azure_identities,
+            az::set_azure_subscription,
+            az_login::run_azure_login,
+            az::az_install_diagnostics,
+            az::azure_token_status,
+            preferences::get_preferences,
+            preferences::set_preferences,
+            preferences::set_tool_path,
+            preflight::preflight_azure,
+            preferences::reset_preferences,
+            preferences::preferences_load_error,
+            preferences::effective_config,
+            resource_cache::list_cached_runs,
+            resource_cache::query_cached_resources,
+            resource_cache::diff_cached_runs,
+            resource_cache::cache_resources,
+            ports::is_port_available,
+            ports::find_free_port,
+            sandbox::sandbox_diagnostics,
+            ruchy_purity::ruchy_is_pure,
+            schema_version::schema_version,
+            timing::tool_timing_histograms,
+            timing::reset_timing_histograms,
+            tool_runner::run_tool,
+            tool_runner::run_tool_bytes,
+            tool_runner::run_tool_with_secret
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn known_tools_lists_every_built_in_tool() {
+        let app = tauri::test::mock_app();
+        app.manage(PreferencesState::load(std::env::temp_dir().join("skanyxx-no-such-prefs.json")));
+        let tools = known_tools(app.state()).await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, KNOWN_TOOLS);
+        for tool in &tools {
+            let error = tool.error.as_deref().unwrap_or_default();
+            assert!(!error.starts_with("Unknown tool"), "{}: {}", tool.name, error);
+        }
+    }
+
+    #[test]
+    fn query_is_passed_through_and_must_not_be_blank() {
+        let prefs = Preferences::default();
+        let options = FinderOptions {
+            query: Some("[].name".to_string()),
+            ..Default::default()
+        };
+        let args = build_finder_args(vec!["--subscription".to_string(), "s".to_string()], &options, &prefs).unwrap();
+        assert_eq!(args, ["--subscription", "s", "--query", "[].name"]);
+
+        assert_eq!(build_finder_args(vec!["a".to_string()], &FinderOptions::default(), &prefs).unwrap(), ["a"]);
+        let blank = FinderOptions {
+            query: Some("  ".to_string()),
+            ..Default::default()
+        };
+        assert!(build_finder_args(Vec::new(), &blank, &prefs).is_err());
+    }
+
+    #[test]
+    fn verbose_flag_is_appended_once_when_requested() {
+        let mut prefs = Preferences::default();
+        let verbose = FinderOptions {
+            verbose: true,
+            ..Default::default()
+        };
+        let args = build_finder_args(vec!["a".to_string()], &verbose, &prefs).unwrap();
+        assert_eq!(args, ["a", "--verbose"]);
+
+        let args = build_finder_args(vec!["--verbose".to_string()], &verbose, &prefs).unwrap();
+        assert_eq!(args, ["--verbose"]);
+        prefs.finder_verbose_flag = " ".to_string();
+        assert!(build_finder_args(Vec::new(), &verbose, &prefs).is_err());
+    }
+
+    #[test]
+    fn finder_run_options_default_whatever_is_left_out() {
+        let options: FinderRunOptions = serde_json::from_value(serde_json::json!({
+            "query": "[].name",
+            "verbose": true,
+            "priority": 10,
+            "compress_output": true,
+            "output_stdout_file": "scan.json",
+        }))
+        .unwrap();
+        assert_eq!(options.finder.query.as_deref(), Some("[].name"));
+        assert!(options.finder.verbose);
+        assert_eq!(options.finder.priority, Some(10));
+        assert!(options.compress_output);
+        assert_eq!(options.output_stdout_file.as_deref(), Some("scan.json"));
+        assert!(!options.skip_defaults && !options.cache_results && !options.create_config_dir);
+
+        let empty: FinderRunOptions = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(empty.finder.query.is_none() && !empty.finder.verbose && empty.azure_config_dir.is_none());
+    }
+
+    #[test]
+    fn resource_group_scope_is_appended_only_when_given() {
+        let prefs = Preferences::default();
+        let scoped = FinderOptions {
+            resource_group: Some("rg-web".to_string()),
+            ..Default::default()
+        };
+        let args = build_finder_args(vec!["a".to_string()], &scoped, &prefs).unwrap();
+        assert_eq!(args, ["a", "--resource-group", "rg-web"]);
+
+        let args = build_finder_args(vec!["a".to_string()], &FinderOptions::default(), &prefs).unwrap();
+        assert_eq!(args, ["a"]);
+
+        let blank = FinderOptions {
+            resource_group: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert!(build_finder_args(Vec::new(), &blank, &prefs).is_err());
+    }
+
+    #[test]
+    fn management_group_scope_is_validated_and_appended() {
+        let prefs = Preferences::default();
+        let scoped = FinderOptions {
+            management_group: Some("/providers/Microsoft.Management/managementGroups/corp-platform".to_string()),
+            ..Default::default()
+        };
+        let args = build_finder_args(vec!["a".to_string()], &scoped, &prefs).unwrap();
+        assert_eq!(args, ["a", "--management-group", "corp-platform"]);
+
+        for invalid in ["", "corp platform", "corp.", "m".repeat(91).as_str()] {
+            let options = FinderOptions {
+                management_group: Some(invalid.to_string()),
+                ..Default::default()
+            };
+            assert!(build_finder_args(Vec::new(), &options, &prefs).is_err(), "{:?} accepted", invalid);
+        }
+
+        let both = FinderOptions {
+            resource_group: Some("rg-web".to_string()),
+            management_group: Some("corp".to_string()),
+            ..Default::default()
+        };
+        assert!(build_finder_args(Vec::new(), &both, &prefs).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_tool_resolves_to_its_target() {
+        let dir = std::env::temp_dir().join(format!("skanyxx-symlink-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("ruchy-1.0");
+        std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+        let link = dir.join("ruchy");
+        std::os::unix::fs::symlink(&binary, &link).unwrap();
+
+        let resolved = resolve_symlink(link.to_str().unwrap()).unwrap();
+        assert!(resolved.is_symlink);
+        assert_eq!(resolved.path, link.to_str().unwrap());
+        assert_eq!(resolved.target, std::fs::canonicalize(&binary).unwrap().to_str().unwrap());
+
+        let plain = resolve_symlink(binary.to_str().unwrap()).unwrap();
+        assert!(!plain.is_symlink);
+        assert_eq!(plain.target, plain.path);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ruchy_expression_value_is_separated_out() {
+        let result = ruchy_command_output("Welcome to Ruchy REPL v1.0\nruchy> \n42\n", "");
+        assert_eq!(result.value.as_deref(), Some("42"));
+        assert_eq!(result.printed_output, "");
+        assert_eq!(result.error, None);
+        assert!(result.output.success);
+    }
+
+    #[test]
+    fn ruchy_prints_are_kept_apart_from_the_value() {
+        let result = ruchy_command_output("hello\nworld\n()\n", "");
+        assert_eq!(result.value, None);
+        assert_eq!(result.printed_output, "hello\nworld");
+
+        let result = ruchy_command_output("hello\n", "Error: return: 3\n");
+        assert_eq!(result.value.as_deref(), Some("3"));
+        assert_eq!(result.printed_output, "hello");
+    }
+
+    #[test]
+    fn ruchy_errors_are_reported_without_a_value() {
+        let result = ruchy_command_output("", "Error: Undefined variable: x\n");
+        assert_eq!(result.error.as_deref(), Some("Undefined variable: x"));
+        assert_eq!(result.value, None);
+        assert!(!result.output.success);
+    }
+
+    #[test]
+    fn ruchy_errors_are_classified_as_compile_or_runtime() {
+        let prefs = Preferences::default();
+        let mut parse = ruchy_command_output("", "Error: Parse error: Unexpected token RightBrace at line 1\n");
+        parse.classify_error(&prefs);
+        assert_eq!(parse.error_kind, Some(RuchyErrorKind::Compile));
+
+        let mut runtime = ruchy_command_output("", "Error: Runtime error: Division by zero\n");
+        runtime.classify_error(&prefs);
+        assert_eq!(runtime.error_kind, Some(RuchyErrorKind::Runtime));
+
+        let mut ok = ruchy_command_output("42\n", "");
+        ok.classify_error(&prefs);
+        assert_eq!(ok.error_kind, None);
+
+        // Patterns come from preferences, so other wordings can be taught
+        let prefs = Preferences {
+            ruchy_runtime_error_patterns: vec!["Undefined variable".to_string()],
+            ..Default::default()
+        };
+        let mut undefined = ruchy_command_output("", "Error: Undefined variable: x\n");
+        undefined.classify_error(&prefs);
+        assert_eq!(undefined.error_kind, Some(RuchyErrorKind::Runtime));
+    }
+
+    #[test]
+    fn stderr_lines_are_split_into_warnings_and_errors() {
+        let stderr = "WARNING: Command group 'graph' is in preview\n\
+                      ERROR: (AuthorizationFailed) no access to rg-a\n\
+                      Fetching resources...\n\
+                      warning: 3 resources skipped\n";
+        let (warnings, errors) = classify_stderr(stderr, &Preferences::default());
+        assert_eq!(
+            warnings,
+            ["WARNING: Command group 'graph' is in preview", "warning: 3 resources skipped"]
+        );
+        assert_eq!(errors, ["ERROR: (AuthorizationFailed) no access to rg-a"]);
+
+        let prefs = Preferences {
+            stderr_warning_prefixes: vec!["Fetching".to_string()],
+            ..Default::default()
+        };
+        let (warnings, _) = classify_stderr(stderr, &prefs);
+        assert_eq!(warnings, ["Fetching resources..."]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn silent_successful_finder_run_is_flagged_as_no_results() {
+        let output = execute_finder("true", &[], azure_tool_env(), false, None, &Preferences::default()).unwrap();
+        assert!(output.success);
+        assert!(output.no_results);
+        assert!(output.stdout.is_empty());
+
+        let output = execute_finder("false", &[], azure_tool_env(), false, None, &Preferences::default()).unwrap();
+        assert!(!output.success);
+        assert!(!output.no_results);
+        assert_eq!(output.crash, None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn slow_path_lookup_gives_up_after_the_timeout() {
+        // `sleep 5` stands in for a `which` stuck on a slow filesystem
+        let started = std::time::Instant::now();
+        let found = lookup_in_path("sleep", "5", std::time::Duration::from_millis(200)).await.unwrap();
+        assert_eq!(found, None);
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+
+        let found = lookup_in_path("echo", "/usr/bin/tool", PATH_LOOKUP_TIMEOUT).await.unwrap();
+        assert_eq!(found.as_deref(), Some("/usr/bin/tool"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finder_killed_by_a_signal_reports_the_crash() {
+        let args = ["-c".to_string(), "kill -ABRT $$".to_string()];
+        let output = execute_finder("sh", &args, azure_tool_env(), false, None, &Preferences::default()).unwrap();
+        assert!(!output.success);
+        let crash = output.crash.unwrap();
+        assert_eq!(crash.signal, libc::SIGABRT);
+        assert_eq!(crash.signal_name, "SIGABRT");
+        assert!(crash.hint.is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn repl_exiting_before_quit_still_returns_its_output() {
+        // Answers without reading its input, like a REPL that quits on its own
+        let mut repl = tokio::process::Command::new("sh");
+        repl.args(["-c", "echo 'Welcome to Ruchy REPL'; echo 42"]);
+        let mut input = "x".repeat(4 * 1024 * 1024);
+        input.push_str("\n:quit\n");
+        let (output, delivery) = output_with_limits(repl, input.into_bytes(), &ProcessLimits::default())
+            .await
+            .unwrap();
+
+        let result = ruchy_repl_result(&output, delivery).unwrap();
+        assert!(result.output.success);
+        assert_eq!(result.value.as_deref(), Some("42"));
+        assert!(result.stdin_delivery.unwrap().broken_pipe);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn azure_env_forces_utf8_python_output_on_windows() {
+        let env = azure_tool_env();
+        assert_eq!(env.get("PYTHONUTF8").map(String::as_str), Some("1"));
+        assert_eq!(env.get("PYTHONIOENCODING").map(String::as_str), Some("utf-8"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn config_dir_override_reaches_the_child() {
+        let dir = std::env::temp_dir().join(format!("skanyxx-azure-config-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_string_lossy().into_owned();
+        assert!(azure_env_with_config_dir(Some(&dir), false).is_err());
+
+        let env = azure_env_with_config_dir(Some(&dir), true).unwrap();
+        let args = ["-c".to_string(), "printf %s \"$AZURE_CONFIG_DIR\"".to_string()];
+        let output = execute_finder("sh", &args, env, false, None, &Preferences::default()).unwrap();
+        assert_eq!(output.stdout, dir);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn compressed_output_round_trips() {
+        use base64::Engine;
+        use std::io::Read;
+
+        let resource = r#"{"name":"vm-web","type":"Microsoft.Compute/virtualMachines"}"#;
+        let original = format!("[{}]", vec![resource; 500].join(","));
+        let mut output = ruchy_command_output("", "").output;
+        output.stdout = original.clone();
+        compress_stdout(&mut output).unwrap();
+        assert!(output.compressed);
+        assert!(output.stdout.len() < original.len());
+
+        let gzipped = base64::engine::general_purpose::STANDARD.decode(&output.stdout).unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(gzipped.as_slice()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn streams_are_written_to_their_own_files() {
+        let dir = std::env::temp_dir().join(format!("skanyxx-stream-files-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let files = StreamFiles {
+            stdout: Some(dir.join("out").join("resources.json")),
+            stderr: Some(dir.join("err").join("finder.log")),
+        };
+        let args = ["-c".to_string(), "printf '[]'; printf 'WARNING: slow\\n' >&2".to_string()];
+        let output =
+            execute_finder_to_files("sh", &args, HashMap::new(), false, None, &Preferences::default(), &files).unwrap();
+
+        assert!(output.success);
+        assert!(!output.no_results);
+        assert_eq!(output.stdout, "");
+        assert_eq!(std::fs::read_to_string(files.stdout.as_ref().unwrap()).unwrap(), "[]");
+        assert_eq!(std::fs::read_to_string(files.stderr.as_ref().unwrap()).unwrap(), "WARNING: slow\n");
+        let written = output.output_files.unwrap();
+        assert_eq!(written.stdout_bytes, Some(2));
+        assert_eq!(written.stderr_bytes, Some(14));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
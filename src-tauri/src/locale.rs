@@ -0,0 +1,94 @@
+use serde::Serialize;
+
+/// Locale settings tool output is decoded under. Garbled non-ASCII resource names usually mean
+/// a non-UTF-8 locale (Unix) or code page (Windows).
+#[derive(Debug, Serialize)]
+pub struct LocaleInfo {
+    pub lang: Option<String>,
+    pub lc_all: Option<String>,
+    pub lc_ctype: Option<String>,
+    // Active console code page on Windows, e.g. 65001 for UTF-8
+    pub code_page: Option<u32>,
+    pub utf8: bool,
+}
+
+// LC_ALL overrides LC_CTYPE, which overrides LANG
+fn is_utf8_locale(lang: Option<&str>, lc_all: Option<&str>, lc_ctype: Option<&str>) -> bool {
+    [lc_all, lc_ctype, lang]
+        .into_iter()
+        .flatten()
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+        .unwrap_or(false)
+}
+
+// `chcp` prints e.g. "Active code page: 65001"
+fn parse_code_page(output: &str) -> Option<u32> {
+    output
+        .trim()
+        .rsplit(|c: char| c.is_whitespace() || c == ':')
+        .next()
+        .and_then(|number| number.trim_end_matches('.').parse().ok())
+}
+
+fn code_page() -> Option<u32> {
+    if !cfg!(target_os = "windows") {
+        return None;
+    }
+    let output = std::process::Command::new("cmd").args(["/C", "chcp"]).output().ok()?;
+    parse_code_page(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn locale_from(var: impl Fn(&str) -> Option<String>, code_page: Option<u32>) -> LocaleInfo {
+    let lang = var("LANG");
+    let lc_all = var("LC_ALL");
+    let lc_ctype = var("LC_CTYPE");
+    let utf8 = match code_page {
+        Some(code_page) => code_page == 65001,
+        None => is_utf8_locale(lang.as_deref(), lc_all.as_deref(), lc_ctype.as_deref()),
+    };
+    LocaleInfo {
+        lang,
+        lc_all,
+        lc_ctype,
+        code_page,
+        utf8,
+    }
+}
+
+pub fn current_locale() -> LocaleInfo {
+    locale_from(|name| std::env::var(name).ok(), code_page())
+}
+
+// Locale variables on Unix and the console code page on Windows, to explain mis-decoded output
+#[tauri::command]
+pub fn locale_info() -> LocaleInfo {
+    current_locale()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_fields_are_populated() {
+        let env = |name: &str| match name {
+            "LANG" => Some("en_US.UTF-8".to_string()),
+            "LC_ALL" => Some("C".to_string()),
+            _ => None,
+        };
+        let info = locale_from(env, None);
+        assert_eq!(info.lang.as_deref(), Some("en_US.UTF-8"));
+        assert_eq!(info.lc_all.as_deref(), Some("C"));
+        assert_eq!(info.lc_ctype, None);
+        // LC_ALL wins over LANG
+        assert!(!info.utf8);
+
+        let windows = locale_from(|_| None, parse_code_page("Active code page: 65001\r\n"));
+        assert_eq!(windows.code_page, Some(65001));
+        assert!(windows.utf8);
+    }
+}
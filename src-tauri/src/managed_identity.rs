@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+// Azure's instance metadata service, reachable only from inside an Azure VM or container
+pub const IMDS_BASE_URL: &str = "http://169.254.169.254";
+
+const IMDS_API_VERSION: &str = "2018-02-01";
+
+// Scope requested for az, which talks to Resource Manager
+pub const ARM_RESOURCE: &str = "https://management.azure.com/";
+
+// Off Azure the address just doesn't answer, so don't wait long for it
+const IMDS_TIMEOUT: Duration = Duration::from_secs(2);
+
+// How long a failed probe is trusted; identities are rarely assigned to a machine mid-session
+const MISSING_IDENTITY_TTL: Duration = Duration::from_secs(600);
+
+/// What a managed identity token grants and until when. The token itself is never kept or
+/// returned.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManagedIdentityStatus {
+    pub resource: String,
+    // Unix seconds
+    pub expires_on: u64,
+    pub expires_in_secs: u64,
+    pub client_id: Option<String>,
+}
+
+// IMDS sends the numbers as strings
+fn number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u64),
+        String(String),
+    }
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+// Only the fields that are safe to report; `access_token` is deliberately not deserialized
+#[derive(Deserialize)]
+struct ImdsToken {
+    resource: String,
+    #[serde(deserialize_with = "number_or_string")]
+    expires_on: u64,
+    #[serde(default, deserialize_with = "number_or_string")]
+    expires_in: u64,
+    #[serde(default)]
+    client_id: Option<String>,
+}
+
+impl From<ImdsToken> for ManagedIdentityStatus {
+    fn from(token: ImdsToken) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let expires_in_secs = if token.expires_in > 0 {
+            token.expires_in
+        } else {
+            token.expires_on.saturating_sub(now)
+        };
+        ManagedIdentityStatus {
+            resource: token.resource,
+            expires_on: token.expires_on,
+            expires_in_secs,
+            client_id: token.client_id,
+        }
+    }
+}
+
+// Asks the metadata service at `base_url` for a token for `resource`, proving a managed
+// identity is assigned to this machine
+pub async fn token_status(base_url: &str, resource: &str) -> Result<ManagedIdentityStatus, String> {
+    // The link-local endpoint must never go through an HTTP(S)_PROXY
+    let client = reqwest::Client::builder()
+        .timeout(IMDS_TIMEOUT)
+        .no_proxy()
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let url = format!("{}/metadata/identity/oauth2/token", base_url.trim_end_matches('/'));
+    let response = client
+        .get(&url)
+        .query(&[("api-version", IMDS_API_VERSION), ("resource", resource)])
+        .header("Metadata", "true")
+        .send()
+        .await
+        .map_err(|e| format!("No managed identity endpoint: {}", e))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read managed identity response: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Managed identity token request failed with {}: {}", status, body.trim()));
+    }
+    let token: ImdsToken =
+        serde_json::from_str(&body).map_err(|e| format!("Failed to parse managed identity token: {}", e))?;
+    Ok(token.into())
+}
+
+/// When the metadata service last had no identity to offer, so status polls on a desktop don't
+/// each wait out `IMDS_TIMEOUT`. Kept in managed state.
+#[derive(Default)]
+pub struct ManagedIdentityProbe {
+    missing_since: Mutex<Option<Instant>>,
+}
+
+impl ManagedIdentityProbe {
+    // `token_status`, except that after a miss it answers None without asking again until
+    // `MISSING_IDENTITY_TTL` has passed
+    pub async fn status(&self, base_url: &str, resource: &str) -> Option<ManagedIdentityStatus> {
+        let missing = *self.missing_since.lock().unwrap();
+        if missing.is_some_and(|since| since.elapsed() < MISSING_IDENTITY_TTL) {
+            return None;
+        }
+        let status = token_status(base_url, resource).await.ok();
+        *self.missing_since.lock().unwrap() = status.is_none().then(Instant::now);
+        status
+    }
+}
+
+async fn is_logged_in(az_path: &str, env: &HashMap<String, String>) -> bool {
+    tokio::process::Command::new(az_path)
+        .args(["account", "show", "--output", "none"])
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+// Config dir the managed identity login is kept in, next to the user's own az profile but apart
+// from it, so signing in never replaces the user's accounts or active subscription
+fn isolated_config_dir(env: &HashMap<String, String>) -> Option<String> {
+    env.get("AZURE_CONFIG_DIR")
+        .map(|dir| format!("{}-managed-identity", dir.trim_end_matches(['/', '\\'])))
+}
+
+// Lets headless runs on Azure-hosted machines use the machine's managed identity when `env` has
+// no interactive login. The identity is signed in (once) in a separate config dir, and `env` is
+// pointed at it for this run. Returns the identity's token status when it's used, None when az
+// was already logged in.
+pub async fn ensure_login(
+    az_path: &str,
+    env: &mut HashMap<String, String>,
+) -> Result<Option<ManagedIdentityStatus>, String> {
+    if is_logged_in(az_path, env).await {
+        return Ok(None);
+    }
+    let status = token_status(IMDS_BASE_URL, ARM_RESOURCE).await?;
+    let config_dir = isolated_config_dir(env).ok_or_else(|| "AZURE_CONFIG_DIR is not set".to_string())?;
+    std::fs::create_dir_all(&config_dir).map_err(|e| format!("Failed to create {}: {}", config_dir, e))?;
+    env.insert("AZURE_CONFIG_DIR".to_string(), config_dir);
+    if is_logged_in(az_path, env).await {
+        return Ok(Some(status));
+    }
+
+    let output = tokio::process::Command::new(az_path)
+        .args(["login", "--identity", "--output", "none"])
+        .envs(&*env)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute az login --identity: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "az login --identity exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(Some(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn imds_token_is_reported_without_the_token() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let read = socket.read(&mut request).await.unwrap();
+            let body = r#"{"access_token":"eyJ0eXAiOiJKV1Qi.secret","client_id":"11111111-2222-3333-4444-555555555555","expires_in":"86399","expires_on":"1700086399","ext_expires_in":"86399","not_before":"1699999699","resource":"https://management.azure.com/","token_type":"Bearer"}"#;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(body.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        let status = token_status(&base_url, ARM_RESOURCE).await.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /metadata/identity/oauth2/token?api-version=2018-02-01&resource="));
+        assert!(request.to_ascii_lowercase().contains("metadata: true"));
+
+        assert_eq!(
+            status,
+            ManagedIdentityStatus {
+                resource: ARM_RESOURCE.to_string(),
+                expires_on: 1700086399,
+                expires_in_secs: 86399,
+                client_id: Some("11111111-2222-3333-4444-555555555555".to_string()),
+            }
+        );
+        let reported = serde_json::to_string(&status).unwrap();
+        assert!(!reported.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn missing_identity_is_not_probed_again() {
+        // A port nothing listens on answers like an off-Azure IMDS address, only faster
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+        let probe = ManagedIdentityProbe::default();
+        assert_eq!(probe.status(&closed_url, ARM_RESOURCE).await, None);
+
+        // Within the TTL the miss stands without another request, even to an endpoint that would answer
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        assert_eq!(probe.status(&base_url, ARM_RESOURCE).await, None);
+        let accepted = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+        assert!(accepted.is_err());
+    }
+
+    #[test]
+    fn identity_login_is_kept_apart_from_the_user_profile() {
+        let env = HashMap::from([("AZURE_CONFIG_DIR".to_string(), "/home/dev/.azure/".to_string())]);
+        assert_eq!(isolated_config_dir(&env).as_deref(), Some("/home/dev/.azure-managed-identity"));
+        assert_eq!(isolated_config_dir(&HashMap::new()), None);
+    }
+}
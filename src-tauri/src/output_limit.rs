@@ -0,0 +1,213 @@
+use std::io::Read;
+use std::process::{Child, Output};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error::CommandError;
+
+// Bytes of tool output currently held in memory across every in-flight run
+static BUFFERED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Output a run has buffered so far, counted against the global cap until it's dropped
+struct Reservation<'a> {
+    counter: &'a AtomicU64,
+    bytes: u64,
+}
+
+impl<'a> Reservation<'a> {
+    fn new(counter: &'a AtomicU64) -> Self {
+        Reservation { counter, bytes: 0 }
+    }
+
+    fn grow(&mut self, bytes: u64, cap: u64) -> Result<(), CommandError> {
+        let total = self.counter.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        if total > cap {
+            self.counter.fetch_sub(bytes, Ordering::SeqCst);
+            return Err(CommandError::ResourceLimit(format!(
+                "Tool output exceeded the {} byte buffer limit (max_buffered_output_bytes)",
+                cap
+            )));
+        }
+        self.bytes += bytes;
+        Ok(())
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(self.bytes, Ordering::SeqCst);
+    }
+}
+
+fn read_capped(mut pipe: impl Read, counter: &AtomicU64, cap: u64) -> Result<(Vec<u8>, Reservation<'_>), CommandError> {
+    let mut reservation = Reservation::new(counter);
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let read = pipe
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to read tool output: {}", e))?;
+        if read == 0 {
+            return Ok((buffer, reservation));
+        }
+        reservation.grow(read as u64, cap)?;
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+}
+
+// A stream that wasn't piped (e.g. redirected to a file) reads as empty
+fn read_pipe(pipe: Option<impl Read>, counter: &AtomicU64, cap: u64) -> Result<(Vec<u8>, Reservation<'_>), CommandError> {
+    match pipe {
+        Some(pipe) => read_capped(pipe, counter, cap),
+        None => Ok((Vec::new(), Reservation::new(counter))),
+    }
+}
+
+fn collect_output_counted(mut child: Child, counter: &AtomicU64, cap: u64) -> Result<Output, CommandError> {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (stdout, stderr) = std::thread::scope(|scope| {
+        let stderr = scope.spawn(|| read_pipe(stderr, counter, cap));
+        let stdout = read_pipe(stdout, counter, cap);
+        if stdout.is_err() {
+            // Stops the tool writing, which also lets the stderr reader finish
+            let _ = child.kill();
+        }
+        let stderr = stderr.join().unwrap_or_else(|_| Err("stderr reader panicked".to_string().into()));
+        (stdout, stderr)
+    });
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for tool: {}", e))?;
+    // Both reservations are released when this returns
+    let ((stdout, _), (stderr, _)) = (stdout?, stderr?);
+    Ok(Output { status, stdout, stderr })
+}
+
+/// Like `Command::output` for a child spawned with piped stdout and stderr, but fails with
+/// `ResourceLimit` (killing the child) once all runs together would buffer more than `cap` bytes.
+/// A stream that isn't piped comes back empty.
+pub fn collect_output(child: Child, cap: u64) -> Result<Output, CommandError> {
+    collect_output_counted(child, &BUFFERED_BYTES, cap)
+}
+
+async fn read_capped_async(
+    pipe: Option<impl AsyncRead + Unpin>,
+    counter: &AtomicU64,
+    cap: u64,
+) -> Result<(Vec<u8>, Reservation<'_>), CommandError> {
+    let mut reservation = Reservation::new(counter);
+    let mut buffer = Vec::new();
+    let Some(mut pipe) = pipe else {
+        return Ok((buffer, reservation));
+    };
+    let mut chunk = [0u8; 8192];
+    loop {
+        let read = pipe
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read tool output: {}", e))?;
+        if read == 0 {
+            return Ok((buffer, reservation));
+        }
+        reservation.grow(read as u64, cap)?;
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+}
+
+async fn collect_output_async_counted(
+    mut child: tokio::process::Child,
+    counter: &AtomicU64,
+    cap: u64,
+) -> Result<Output, CommandError> {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    // The first stream over the cap stops the other reader
+    let read = tokio::try_join!(
+        read_capped_async(stdout, counter, cap),
+        read_capped_async(stderr, counter, cap)
+    );
+    if read.is_err() {
+        let _ = child.kill().await;
+    }
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to wait for tool: {}", e))?;
+    let ((stdout, _), (stderr, _)) = read?;
+    Ok(Output { status, stdout, stderr })
+}
+
+/// `collect_output` for a tokio child, counted against the same cap. Dropping the future stops
+/// the reads; a child spawned with `kill_on_drop` goes with it.
+pub async fn collect_output_async(child: tokio::process::Child, cap: u64) -> Result<Output, CommandError> {
+    collect_output_async_counted(child, &BUFFERED_BYTES, cap).await
+}
+
+// Bytes of tool output buffered right now by in-flight runs
+#[tauri::command]
+pub fn buffered_output_bytes() -> u64 {
+    BUFFERED_BYTES.load(Ordering::SeqCst)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    fn spawn(script: &str) -> Child {
+        Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn exceeding_the_cap_is_a_resource_limit() {
+        let counter = AtomicU64::new(0);
+        let output = collect_output_counted(spawn("printf 0123; printf err >&2"), &counter, 16).unwrap();
+        assert_eq!(output.stdout, b"0123");
+        assert_eq!(output.stderr, b"err");
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        let result = collect_output_counted(spawn("printf 0123456789ABCDEFGHIJ"), &counter, 16);
+        assert!(matches!(result, Err(CommandError::ResourceLimit(_))));
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        // Output buffered by other runs counts against the same cap
+        let mut other_run = Reservation::new(&counter);
+        other_run.grow(14, 16).unwrap();
+        let result = collect_output_counted(spawn("printf 0123"), &counter, 16);
+        assert!(matches!(result, Err(CommandError::ResourceLimit(_))));
+        drop(other_run);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn async_collection_shares_the_cap() {
+        let spawn = |script: &str| {
+            tokio::process::Command::new("sh")
+                .args(["-c", script])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+        };
+        let counter = AtomicU64::new(0);
+        let output = collect_output_async_counted(spawn("printf 0123; printf err >&2"), &counter, 16)
+            .await
+            .unwrap();
+        assert_eq!(output.stdout, b"0123");
+        assert_eq!(output.stderr, b"err");
+
+        // Still writing when the cap is hit, so only the kill ends it
+        let result = collect_output_async_counted(spawn("printf 0123456789ABCDEFGHIJ; sleep 5"), &counter, 16).await;
+        assert!(matches!(result, Err(CommandError::ResourceLimit(_))));
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+}
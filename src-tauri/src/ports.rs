@@ -0,0 +1,29 @@
+use std::net::{Ipv4Addr, TcpListener};
+
+// Whether a local server could listen on `port` right now. The listener is dropped straight
+// away, so another process can still take the port before the tool starts.
+#[tauri::command]
+pub fn is_port_available(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+}
+
+// The first free localhost port from `start` upwards, for launching a local server tool
+#[tauri::command]
+pub fn find_free_port(start: u16) -> Result<u16, String> {
+    (start.max(1)..=u16::MAX)
+        .find(|&port| is_port_available(port))
+        .ok_or_else(|| format!("No free port at or above {}", start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_port_is_unavailable_and_skipped() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!is_port_available(port));
+        assert_ne!(find_free_port(port).unwrap(), port);
+    }
+}
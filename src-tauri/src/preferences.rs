@@ -0,0 +1,584 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+pub const PREFERENCES_FILE: &str = "preferences.json";
+pub const PREFERENCES_BACKUP_EXTENSION: &str = "json.bak";
+
+// User settings persisted as JSON in the app config dir. `serde(default)` lets files written
+// by older versions load with defaults for any fields added since.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    // Subscription to target when a command isn't given one explicitly
+    pub default_subscription: Option<String>,
+    // Tool name -> custom executable path, checked before the usual install locations
+    pub tool_paths: HashMap<String, String>,
+    // Timeout applied to http_request calls
+    pub http_timeout_secs: u64,
+    // Upper bound on tool runs and requests issued in parallel
+    pub max_concurrency: usize,
+    // Flag azure-resource-finder takes for debug output, added to verbose runs
+    pub finder_verbose_flag: String,
+    // Flag azure-resource-finder takes to limit a scan to one resource group
+    pub finder_resource_group_flag: String,
+    // Flag azure-resource-finder takes to scan every subscription under a management group
+    pub finder_management_group_flag: String,
+    // Idle time after which the persistent Ruchy session is stopped; 0 keeps it open
+    pub ruchy_idle_timeout_secs: u64,
+    // Wall-clock limit for a single run_ruchy_repl eval; 0 disables it
+    pub ruchy_eval_timeout_secs: u64,
+    // Tool name -> flags passed before the call-specific args on every run
+    pub default_args: HashMap<String, Vec<String>>,
+    // Case-insensitive line prefixes marking tool stderr lines as warnings or errors
+    pub stderr_warning_prefixes: Vec<String>,
+    pub stderr_error_prefixes: Vec<String>,
+    // Cap on tool output held in memory across all running commands; runs that would exceed
+    // it fail with a ResourceLimit error
+    pub max_buffered_output_bytes: u64,
+    // Case-insensitive substrings sorting Ruchy eval errors into compile or runtime errors
+    pub ruchy_compile_error_patterns: Vec<String>,
+    pub ruchy_runtime_error_patterns: Vec<String>,
+    // History kept in the finder resource cache: the newest runs, and none older than the age
+    // limit; 0 turns either bound off
+    pub resource_cache_max_runs: usize,
+    pub resource_cache_max_age_days: u64,
+    // Absolute directory that relative output file paths are resolved against; the app's
+    // working directory when unset
+    pub workspace_dir: Option<String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            default_subscription: None,
+            tool_paths: HashMap::new(),
+            http_timeout_secs: 60,
+            max_concurrency: 4,
+            finder_verbose_flag: "--verbose".to_string(),
+            finder_resource_group_flag: "--resource-group".to_string(),
+            finder_management_group_flag: "--management-group".to_string(),
+            ruchy_idle_timeout_secs: 300,
+            ruchy_eval_timeout_secs: 30,
+            default_args: HashMap::new(),
+            stderr_warning_prefixes: ["WARNING:", "WARN:", "[warn]", "level=warn"]
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+            stderr_error_prefixes: ["ERROR:", "FATAL:", "CRITICAL:", "[error]", "level=error"]
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+            max_buffered_output_bytes: 512 * 1024 * 1024,
+            ruchy_compile_error_patterns: [
+                "parse error",
+                "syntax error",
+                "unexpected token",
+                "unexpected end of input",
+                "type error",
+                "type mismatch",
+                "failed to parse",
+            ]
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect(),
+            ruchy_runtime_error_patterns: [
+                "runtime error",
+                "panicked",
+                "panic",
+                "division by zero",
+                "index out of bounds",
+                "stack overflow",
+            ]
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect(),
+            resource_cache_max_runs: 50,
+            resource_cache_max_age_days: 90,
+            workspace_dir: None,
+        }
+    }
+}
+
+impl Preferences {
+    // Values that would break the commands using them, rejected before anything is saved
+    pub fn validate(&self) -> Result<(), String> {
+        if self.http_timeout_secs == 0 {
+            return Err("http_timeout_secs must be at least 1".to_string());
+        }
+        if self.finder_verbose_flag.trim().is_empty() {
+            return Err("finder_verbose_flag must not be empty".to_string());
+        }
+        if self.finder_resource_group_flag.trim().is_empty() {
+            return Err("finder_resource_group_flag must not be empty".to_string());
+        }
+        if self.finder_management_group_flag.trim().is_empty() {
+            return Err("finder_management_group_flag must not be empty".to_string());
+        }
+        if self.max_buffered_output_bytes == 0 {
+            return Err("max_buffered_output_bytes must be at least 1".to_string());
+        }
+        if let Some(dir) = &self.workspace_dir {
+            if !Path::new(dir).is_absolute() {
+                return Err(format!("workspace_dir must be an absolute path, got '{}'", dir));
+            }
+        }
+        Ok(())
+    }
+
+    // Where an output file the user named should go: absolute paths as given, relative ones
+    // under `workspace_dir` (or the working directory when it isn't set)
+    pub fn resolve_output_path(&self, path: &str) -> Result<PathBuf, String> {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return Ok(path.to_path_buf());
+        }
+        let base = match &self.workspace_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::current_dir().map_err(|e| format!("Failed to read the working directory: {}", e))?,
+        };
+        Ok(base.join(path))
+    }
+
+    // Checks every custom tool path that differs from `previous` points at an executable and
+    // stores it canonicalized, so a broken path is rejected here instead of failing every run.
+    // Unchanged paths are kept as stored, so a tool that has since gone missing doesn't block
+    // saving unrelated settings.
+    pub fn canonicalize_tool_paths(&mut self, previous: &Preferences) -> Result<(), String> {
+        for (tool, path) in self.tool_paths.iter_mut() {
+            if previous.tool_paths.get(tool) == Some(path) {
+                continue;
+            }
+            *path = canonical_tool_path(path).map_err(|e| format!("Invalid path for {}: {}", tool, e))?;
+        }
+        Ok(())
+    }
+
+    // The tool's configured default args followed by `args`, or `args` alone when the caller
+    // asked to skip the defaults
+    pub fn args_for(&self, tool: &str, args: Vec<String>, skip_defaults: bool) -> Vec<String> {
+        match self.default_args.get(tool) {
+            Some(defaults) if !skip_defaults => defaults.iter().cloned().chain(args).collect(),
+            _ => args,
+        }
+    }
+}
+
+// `path` as an absolute path with symlinks resolved, if it's an existing executable file
+pub fn canonical_tool_path(path: &str) -> Result<String, String> {
+    let path = Path::new(path);
+    crate::check_executable(path)?;
+    let canonical = fs::canonicalize(path).map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+    Ok(canonical.to_string_lossy().into_owned())
+}
+
+pub fn load_preferences(path: &Path) -> Result<Preferences, String> {
+    if !path.exists() {
+        return Ok(Preferences::default());
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+pub fn save_preferences(path: &Path, prefs: &Preferences) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(prefs)
+        .map_err(|e| format!("Failed to serialize preferences: {}", e))?;
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Managed state holding the loaded preferences alongside the file they persist to
+pub struct PreferencesState {
+    path: PathBuf,
+    current: Mutex<Preferences>,
+    // Why the file couldn't be loaded, while the defaults used instead haven't been saved over it
+    load_error: Mutex<Option<String>>,
+}
+
+impl PreferencesState {
+    // A missing or unreadable file falls back to defaults so a bad file can't stop startup; the
+    // error is kept for `preferences_load_error`
+    pub fn load(path: PathBuf) -> Self {
+        let (prefs, load_error) = match load_preferences(&path) {
+            Ok(prefs) => (prefs, None),
+            Err(e) => (Preferences::default(), Some(e)),
+        };
+        PreferencesState {
+            path,
+            current: Mutex::new(prefs),
+            load_error: Mutex::new(load_error),
+        }
+    }
+
+    pub fn load_error(&self) -> Option<String> {
+        self.load_error.lock().unwrap().clone()
+    }
+
+    pub fn get(&self) -> Preferences {
+        self.current.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, prefs: Preferences) -> Result<(), String> {
+        save_preferences(&self.path, &prefs)?;
+        *self.current.lock().unwrap() = prefs;
+        *self.load_error.lock().unwrap() = None;
+        Ok(())
+    }
+
+    // Copies the current file to `preferences.json.bak` before writing the defaults over it, so
+    // the settings that broke the app can still be inspected afterwards
+    pub fn reset(&self) -> Result<Preferences, String> {
+        if self.path.exists() {
+            let backup = self.path.with_extension(PREFERENCES_BACKUP_EXTENSION);
+            fs::copy(&self.path, &backup).map_err(|e| {
+                format!("Failed to back up {} to {}: {}", self.path.display(), backup.display(), e)
+            })?;
+        }
+        let defaults = Preferences::default();
+        self.set(defaults.clone())?;
+        Ok(defaults)
+    }
+}
+
+#[tauri::command]
+pub fn get_preferences(state: tauri::State<'_, PreferencesState>) -> Preferences {
+    state.get()
+}
+
+#[tauri::command]
+pub fn set_preferences(
+    state: tauri::State<'_, PreferencesState>,
+    mut prefs: Preferences,
+) -> Result<Preferences, String> {
+    prefs.validate()?;
+    prefs.canonicalize_tool_paths(&state.get())?;
+    state.set(prefs.clone())?;
+    Ok(prefs)
+}
+
+// Sets the custom executable for `tool` (e.g. ruchy) after checking it exists and is
+// executable, and returns the canonical path that was stored
+#[tauri::command]
+pub fn set_tool_path(state: tauri::State<'_, PreferencesState>, tool: String, path: String) -> Result<String, String> {
+    let canonical = canonical_tool_path(&path)?;
+    let mut prefs = state.get();
+    prefs.tool_paths.insert(tool, canonical.clone());
+    state.set(prefs)?;
+    Ok(canonical)
+}
+
+// Escape hatch for a setting that breaks the app, e.g. a wrong custom tool path
+#[tauri::command]
+pub fn reset_preferences(state: tauri::State<'_, PreferencesState>) -> Result<Preferences, String> {
+    state.reset()
+}
+
+// Set when the preferences file couldn't be read or parsed at startup, so the UI can say the
+// defaults are in use; cleared once preferences are saved
+#[tauri::command]
+pub fn preferences_load_error(state: tauri::State<'_, PreferencesState>) -> Option<String> {
+    state.load_error()
+}
+
+/// Where an effective setting's value came from, lowest precedence first
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    Preferences,
+    Override,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EffectiveSetting {
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+}
+
+// Hides credentials: secret-looking fields entirely, and flag values in `default_args`
+fn mask_setting(key: &str, value: serde_json::Value) -> serde_json::Value {
+    if crate::recording::is_secret_key(key) {
+        return serde_json::Value::String(crate::recording::REDACTED.to_string());
+    }
+    match (key, value) {
+        ("default_args", serde_json::Value::Object(tools)) => tools
+            .into_iter()
+            .map(|(tool, args)| {
+                let args: Vec<String> = serde_json::from_value(args).unwrap_or_default();
+                (tool, serde_json::json!(crate::recording::redact_args(&args)))
+            })
+            .collect(),
+        (_, value) => value,
+    }
+}
+
+// Every setting with its resolved value: defaults, overridden by the saved preferences,
+// overridden by `overrides` (a partial preferences object, as a single call would pass)
+fn resolve_effective(
+    prefs: &Preferences,
+    overrides: serde_json::Map<String, serde_json::Value>,
+) -> Result<BTreeMap<String, EffectiveSetting>, String> {
+    let to_map = |prefs: &Preferences| match serde_json::to_value(prefs) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let defaults = to_map(&Preferences::default());
+    let mut merged = to_map(prefs);
+    for (key, value) in &overrides {
+        if !merged.contains_key(key) {
+            return Err(format!("Unknown setting: {}", key));
+        }
+        merged.insert(key.clone(), value.clone());
+    }
+    // Overrides have to make a valid configuration too
+    let resolved: Preferences = serde_json::from_value(serde_json::Value::Object(merged.clone()))
+        .map_err(|e| format!("Invalid override: {}", e))?;
+    resolved.validate()?;
+
+    Ok(merged
+        .into_iter()
+        .map(|(key, value)| {
+            let source = if overrides.contains_key(&key) {
+                ConfigSource::Override
+            } else if defaults.get(&key) == Some(&value) {
+                ConfigSource::Default
+            } else {
+                ConfigSource::Preferences
+            };
+            let value = mask_setting(&key, value);
+            (key, EffectiveSetting { value, source })
+        })
+        .collect())
+}
+
+// The configuration actually in effect, with each field's source, for answering "why is it
+// using this subscription". `overrides` previews per-call values on top. Credentials are masked.
+#[tauri::command]
+pub fn effective_config(
+    state: tauri::State<'_, PreferencesState>,
+    overrides: Option<serde_json::Map<String, serde_json::Value>>,
+) -> Result<BTreeMap<String, EffectiveSetting>, String> {
+    resolve_effective(&state.get(), overrides.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh path under the system temp dir, unique to this process and test
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("skanyxx-prefs-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(PREFERENCES_FILE)
+    }
+
+    #[test]
+    fn preferences_round_trip_through_the_file() {
+        let path = temp_path("round-trip");
+        let mut prefs = Preferences::default();
+        prefs.default_subscription = Some("sub".to_string());
+        prefs.tool_paths.insert("ruchy".to_string(), "/opt/ruchy".to_string());
+        prefs.http_timeout_secs = 5;
+
+        save_preferences(&path, &prefs).unwrap();
+        assert_eq!(load_preferences(&path).unwrap(), prefs);
+
+        let state = PreferencesState::load(path.clone());
+        assert_eq!(state.get(), prefs);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn missing_file_and_fields_fall_back_to_defaults() {
+        let path = temp_path("defaults");
+        assert_eq!(load_preferences(&path).unwrap(), Preferences::default());
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, r#"{ "http_timeout_secs": 10 }"#).unwrap();
+        let loaded = load_preferences(&path).unwrap();
+        assert_eq!(loaded.http_timeout_secs, 10);
+        assert_eq!(loaded.max_concurrency, Preferences::default().max_concurrency);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn unreadable_file_falls_back_to_defaults_and_keeps_the_error() {
+        let path = temp_path("unreadable");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{ not json").unwrap();
+
+        let state = PreferencesState::load(path.clone());
+        assert_eq!(state.get(), Preferences::default());
+        assert!(state.load_error().unwrap().starts_with("Failed to parse"));
+
+        state.set(Preferences::default()).unwrap();
+        assert_eq!(state.load_error(), None);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn reset_restores_defaults_and_backs_up_the_previous_file() {
+        let path = temp_path("reset");
+        let prefs = Preferences {
+            http_timeout_secs: 5,
+            ..Default::default()
+        };
+        let state = PreferencesState::load(path.clone());
+        state.set(prefs.clone()).unwrap();
+
+        assert_eq!(state.reset().unwrap(), Preferences::default());
+        assert_eq!(state.get(), Preferences::default());
+        assert_eq!(load_preferences(&path).unwrap(), Preferences::default());
+        let backup = path.with_extension(PREFERENCES_BACKUP_EXTENSION);
+        assert_eq!(load_preferences(&backup).unwrap(), prefs);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn default_args_are_prepended_unless_skipped() {
+        let mut prefs = Preferences::default();
+        prefs.default_args.insert(
+            "azure-resource-finder".to_string(),
+            vec!["--subscription".to_string(), "s".to_string()],
+        );
+        let args = vec!["--type".to_string(), "vm".to_string()];
+
+        assert_eq!(
+            prefs.args_for("azure-resource-finder", args.clone(), false),
+            ["--subscription", "s", "--type", "vm"]
+        );
+        assert_eq!(prefs.args_for("azure-resource-finder", args.clone(), true), args);
+        assert_eq!(prefs.args_for("az", args.clone(), false), args);
+    }
+
+    #[test]
+    fn relative_output_paths_resolve_under_the_workspace() {
+        let workspace = std::env::temp_dir().join("skanyxx-workspace");
+        let prefs = Preferences {
+            workspace_dir: Some(workspace.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        assert!(prefs.validate().is_ok());
+        assert_eq!(
+            prefs.resolve_output_path("exports/vms.json").unwrap(),
+            workspace.join("exports").join("vms.json")
+        );
+
+        let absolute = std::env::temp_dir().join("elsewhere.json");
+        assert_eq!(prefs.resolve_output_path(&absolute.to_string_lossy()).unwrap(), absolute);
+
+        let relative_workspace = Preferences {
+            workspace_dir: Some("exports".to_string()),
+            ..Default::default()
+        };
+        assert!(relative_workspace.validate().is_err());
+        let resolved = Preferences::default().resolve_output_path("vms.json").unwrap();
+        assert!(resolved.is_absolute());
+    }
+
+    #[test]
+    fn validate_rejects_values_that_would_break_commands() {
+        assert!(Preferences::default().validate().is_ok());
+
+        let zero_timeout = Preferences {
+            http_timeout_secs: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            zero_timeout.validate().unwrap_err(),
+            "http_timeout_secs must be at least 1"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_tool_path_is_stored_canonicalized() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_path("tool-path").parent().unwrap().to_path_buf();
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        let ruchy = dir.join("bin").join("ruchy");
+        fs::write(&ruchy, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&ruchy, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let indirect = dir.join("bin").join("..").join("bin").join("ruchy");
+        let canonical = canonical_tool_path(&indirect.to_string_lossy()).unwrap();
+        assert_eq!(Path::new(&canonical), fs::canonicalize(&ruchy).unwrap());
+
+        let mut prefs = Preferences::default();
+        prefs.tool_paths.insert("ruchy".to_string(), indirect.to_string_lossy().into_owned());
+        prefs.canonicalize_tool_paths(&Preferences::default()).unwrap();
+        assert_eq!(prefs.tool_paths["ruchy"], canonical);
+
+        fs::set_permissions(&ruchy, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(canonical_tool_path(&canonical).unwrap_err().ends_with("is not executable"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn directory_tool_path_is_rejected() {
+        let dir = temp_path("tool-dir").parent().unwrap().to_path_buf();
+        fs::create_dir_all(&dir).unwrap();
+        let error = canonical_tool_path(&dir.to_string_lossy()).unwrap_err();
+        assert!(error.ends_with("is a directory, not an executable"), "{}", error);
+
+        let mut prefs = Preferences::default();
+        prefs.tool_paths.insert("ruchy".to_string(), dir.to_string_lossy().into_owned());
+        assert!(prefs
+            .canonicalize_tool_paths(&Preferences::default())
+            .unwrap_err()
+            .starts_with("Invalid path for ruchy"));
+
+        // A path already stored is left alone, even though it no longer points at a tool
+        let previous = prefs.clone();
+        prefs.http_timeout_secs += 1;
+        prefs.canonicalize_tool_paths(&previous).unwrap();
+        assert_eq!(prefs.tool_paths["ruchy"], dir.to_string_lossy());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_tool_path_is_rejected() {
+        let missing = temp_path("tool-missing").parent().unwrap().join("ruchy");
+        let error = canonical_tool_path(&missing.to_string_lossy()).unwrap_err();
+        assert!(error.contains("does not exist"), "{}", error);
+    }
+
+    #[test]
+    fn effective_config_reflects_override_precedence() {
+        let mut prefs = Preferences {
+            http_timeout_secs: 5,
+            default_subscription: Some("sub-from-prefs".to_string()),
+            ..Default::default()
+        };
+        prefs.default_args.insert(
+            "az".to_string(),
+            vec!["--client-secret".to_string(), "s3cret".to_string()],
+        );
+        let overrides = serde_json::json!({ "default_subscription": "sub-from-call" });
+        let overrides = overrides.as_object().unwrap().clone();
+
+        let effective = resolve_effective(&prefs, overrides).unwrap();
+        assert_eq!(effective["default_subscription"].value, "sub-from-call");
+        assert_eq!(effective["default_subscription"].source, ConfigSource::Override);
+        assert_eq!(effective["http_timeout_secs"].value, 5);
+        assert_eq!(effective["http_timeout_secs"].source, ConfigSource::Preferences);
+        assert_eq!(effective["max_concurrency"].source, ConfigSource::Default);
+        assert_eq!(
+            effective["default_args"].value,
+            serde_json::json!({ "az": ["--client-secret", "[REDACTED]"] })
+        );
+
+        let unknown = serde_json::json!({ "no_such_setting": 1 });
+        assert!(resolve_effective(&prefs, unknown.as_object().unwrap().clone()).is_err());
+        let invalid = serde_json::json!({ "http_timeout_secs": 0 });
+        assert!(resolve_effective(&prefs, invalid.as_object().unwrap().clone()).is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::az::{self, AzAccount};
+use crate::azure_cloud::AzureCloudInfo;
+use crate::preferences::PreferencesState;
+
+// Any HTTP answer from ARM, even a 401 for the missing token, proves it's reachable
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of one `preflight_azure` check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    // Not run because a check it depends on failed
+    pub skipped: bool,
+    pub detail: String,
+}
+
+impl PreflightCheck {
+    fn from_result(name: &'static str, result: Result<String, String>) -> Self {
+        let passed = result.is_ok();
+        PreflightCheck {
+            name,
+            passed,
+            skipped: false,
+            detail: result.unwrap_or_else(|e| e),
+        }
+    }
+
+    fn skipped(name: &'static str, needs: &str) -> Self {
+        PreflightCheck {
+            name,
+            passed: false,
+            skipped: true,
+            detail: format!("Skipped because {} failed", needs),
+        }
+    }
+}
+
+/// Go/no-go for Azure work: `ready` when every check passed
+#[derive(Debug, Serialize)]
+pub struct Preflight {
+    pub ready: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+async fn az_json(az_path: &str, env: &HashMap<String, String>, args: &[&str]) -> Result<std::process::Output, String> {
+    tokio::process::Command::new(az_path)
+        .args(args)
+        .args(["--output", "json"])
+        .envs(env)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute az: {}", e))
+}
+
+async fn check_login(az_path: &str, env: &HashMap<String, String>) -> Result<(AzAccount, AzureCloudInfo), String> {
+    let output = az_json(az_path, env, &["account", "show"]).await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(if az::is_auth_error(&stderr) {
+            "Not logged in; run 'az login'".to_string()
+        } else {
+            format!("az account show failed: {}", stderr.trim())
+        });
+    }
+    let payload: serde_json::Value =
+        serde_json::from_slice(az::split_az_json(&output.stdout).0).map_err(|e| format!("Failed to parse az account show: {}", e))?;
+    let cloud = AzureCloudInfo::from_account(&payload);
+    let account = serde_json::from_value(payload).map_err(|e| format!("Failed to parse az account show: {}", e))?;
+    Ok((account, cloud))
+}
+
+async fn check_connectivity(arm_base_url: &str) -> Result<String, String> {
+    let client = crate::http::build_client(CONNECTIVITY_TIMEOUT, false)?;
+    let response = client
+        .get(arm_base_url)
+        .send()
+        .await
+        .map_err(|e| format!("{} is unreachable: {}", arm_base_url, e))?;
+    Ok(format!("{} answered with {}", arm_base_url, response.status()))
+}
+
+async fn check_token(az_path: &str, env: &HashMap<String, String>) -> Result<String, String> {
+    let query = "{expires_on: expires_on, tenant: tenant, subscription: subscription}";
+    let output = az_json(az_path, env, &["account", "get-access-token", "--query", query]).await?;
+    if !output.status.success() {
+        return Err(format!("Failed to get an access token: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    match az::parse_token_expiry(&output.stdout)?.status(az::unix_now()) {
+        Some(status) if status.expires_in_secs > 0 => {
+            Ok(format!("Access token valid for {} more minutes", status.expires_in_secs / 60))
+        }
+        Some(_) => Err("The access token has expired; run 'az login' again".to_string()),
+        None => Ok("Access token issued; this Azure CLI doesn't report its expiry".to_string()),
+    }
+}
+
+fn check_subscription(account: &AzAccount) -> Result<String, String> {
+    match account.state.as_deref() {
+        Some("Enabled") | None => Ok(format!("{} ({})", account.name, account.id)),
+        Some(state) => Err(format!("Subscription {} ({}) is {}", account.name, account.id, state)),
+    }
+}
+
+// Runs the checks with `az` (or why it's missing). Login and ARM connectivity are checked side
+// by side; checks that need az, a login or a reachable ARM are skipped without them.
+// `arm_base_url` replaces the endpoint of the login's cloud.
+async fn run_preflight(
+    az: Result<String, String>,
+    env: &HashMap<String, String>,
+    arm_base_url: Option<&str>,
+) -> Preflight {
+    let public_arm = AzureCloudInfo::from_account(&serde_json::Value::Null).arm_base_url;
+    let probe_url = arm_base_url.unwrap_or(&public_arm);
+    let mut checks = vec![PreflightCheck::from_result("az_available", az.clone())];
+
+    let Ok(az_path) = az else {
+        let connectivity = check_connectivity(probe_url).await;
+        checks.push(PreflightCheck::skipped("logged_in", "az_available"));
+        checks.push(PreflightCheck::from_result("arm_connectivity", connectivity));
+        checks.push(PreflightCheck::skipped("token_expiry", "az_available"));
+        checks.push(PreflightCheck::skipped("active_subscription", "az_available"));
+        return Preflight { ready: false, checks };
+    };
+
+    let (login, connectivity) = tokio::join!(check_login(&az_path, env), check_connectivity(probe_url));
+    match login {
+        Ok((account, cloud)) => {
+            // Sovereign clouds have their own endpoint, known only once the login is
+            let connectivity = match arm_base_url {
+                None if cloud.arm_base_url != public_arm => check_connectivity(&cloud.arm_base_url).await,
+                _ => connectivity,
+            };
+            let online = connectivity.is_ok();
+            checks.push(PreflightCheck::from_result("logged_in", Ok(format!("Logged in to the {} cloud", cloud.cloud))));
+            checks.push(PreflightCheck::from_result("arm_connectivity", connectivity));
+            checks.push(if online {
+                PreflightCheck::from_result("token_expiry", check_token(&az_path, env).await)
+            } else {
+                PreflightCheck::skipped("token_expiry", "arm_connectivity")
+            });
+            checks.push(PreflightCheck::from_result("active_subscription", check_subscription(&account)));
+        }
+        Err(e) => {
+            checks.push(PreflightCheck::from_result("logged_in", Err(e)));
+            checks.push(PreflightCheck::from_result("arm_connectivity", connectivity));
+            checks.push(PreflightCheck::skipped("token_expiry", "logged_in"));
+            checks.push(PreflightCheck::skipped("active_subscription", "logged_in"));
+        }
+    }
+
+    Preflight {
+        ready: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+// One call confirming Azure work can start: az is installed, logged in, ARM is reachable, the
+// access token is valid and the active subscription is enabled. Each check reports pass/fail
+// with a detail, and `ready` is the overall go/no-go.
+#[tauri::command]
+pub async fn preflight_azure(prefs: tauri::State<'_, PreferencesState>) -> Result<Preflight, String> {
+    let tool_info = crate::locate_tool("az".to_string(), &prefs.get()).await?;
+    let az = match tool_info.path {
+        Some(path) if tool_info.available => Ok(path),
+        _ => Err(tool_info.error.unwrap_or_else(|| "Azure CLI not found".to_string())),
+    };
+    Ok(run_preflight(az, &crate::azure_tool_env(), None).await)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // An az stand-in answering `account show` and `account get-access-token` with `script`
+    fn fake_az(name: &str, script: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("skanyxx-preflight-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("az");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    // A local stand-in for ARM answering every request with 401, as ARM does without a token
+    async fn fake_arm() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let _ = socket
+                    .write_all(b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+        url
+    }
+
+    fn outcome(preflight: &Preflight) -> Vec<(&'static str, bool, bool)> {
+        preflight.checks.iter().map(|check| (check.name, check.passed, check.skipped)).collect()
+    }
+
+    #[tokio::test]
+    async fn everything_ready_passes_every_check() {
+        let az = fake_az(
+            "ready",
+            r#"case "$2" in
+show) echo '{"environmentName": "AzureCloud", "id": "0b1f6471-1bf0-4dda-aec3-cb9272f09590", "name": "Production", "state": "Enabled", "tenantId": "72f988bf-86f1-41af-91ab-2d7cd011db47"}' ;;
+get-access-token) echo "{\"expires_on\": $(( $(date +%s) + 3600 )), \"tenant\": \"t\", \"subscription\": \"s\"}" ;;
+esac"#,
+        );
+        let arm = fake_arm().await;
+        let preflight = run_preflight(Ok(az.clone()), &HashMap::new(), Some(&arm)).await;
+        let _ = std::fs::remove_dir_all(std::path::Path::new(&az).parent().unwrap());
+
+        assert!(preflight.ready, "{:?}", preflight.checks);
+        assert_eq!(
+            outcome(&preflight),
+            [
+                ("az_available", true, false),
+                ("logged_in", true, false),
+                ("arm_connectivity", true, false),
+                ("token_expiry", true, false),
+                ("active_subscription", true, false),
+            ]
+        );
+        assert!(preflight.checks[3].detail.ends_with("more minutes"), "{}", preflight.checks[3].detail);
+        assert_eq!(
+            preflight.checks[4].detail,
+            "Production (0b1f6471-1bf0-4dda-aec3-cb9272f09590)"
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_login_skips_the_checks_that_need_it() {
+        let az = fake_az(
+            "logged-out",
+            r#"echo "ERROR: Please run 'az login' to setup account." >&2; exit 1"#,
+        );
+        let arm = fake_arm().await;
+        let preflight = run_preflight(Ok(az.clone()), &HashMap::new(), Some(&arm)).await;
+        let _ = std::fs::remove_dir_all(std::path::Path::new(&az).parent().unwrap());
+
+        assert!(!preflight.ready);
+        assert_eq!(
+            outcome(&preflight),
+            [
+                ("az_available", true, false),
+                ("logged_in", false, false),
+                ("arm_connectivity", true, false),
+                ("token_expiry", false, true),
+                ("active_subscription", false, true),
+            ]
+        );
+        assert_eq!(preflight.checks[1].detail, "Not logged in; run 'az login'");
+    }
+}
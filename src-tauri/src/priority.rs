@@ -0,0 +1,75 @@
+use std::process::Command;
+
+// Process priority classes for CreateProcess
+#[cfg(windows)]
+const IDLE_PRIORITY_CLASS: u32 = 0x0000_0040;
+#[cfg(windows)]
+const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+#[cfg(windows)]
+const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+#[cfg(windows)]
+const HIGH_PRIORITY_CLASS: u32 = 0x0000_0080;
+
+// Windows has no niceness, so map the Unix range (-20..=19) onto its priority classes
+#[cfg(windows)]
+fn priority_class(priority: i32) -> Option<u32> {
+    match priority {
+        15.. => Some(IDLE_PRIORITY_CLASS),
+        1..=14 => Some(BELOW_NORMAL_PRIORITY_CLASS),
+        0 => None,
+        -14..=-1 => Some(ABOVE_NORMAL_PRIORITY_CLASS),
+        _ => Some(HIGH_PRIORITY_CLASS),
+    }
+}
+
+/// Runs the child at `priority`, a Unix niceness (higher is lower priority). Raising priority
+/// above normal usually needs elevated rights, and spawning fails without them.
+#[cfg(unix)]
+pub fn apply_priority(command: &mut Command, priority: i32) {
+    use std::os::unix::process::CommandExt;
+
+    // Safety: runs in the forked child before exec and only calls setpriority, which is
+    // async-signal-safe
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setpriority(libc::PRIO_PROCESS, 0, priority) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// `program` and `args` rewritten to start under `nice` at `priority`, for spawners without a
+/// pre-exec hook such as portable-pty. Where `apply_priority` fails the spawn, `nice` only warns
+/// about a priority it isn't allowed to set and runs the tool at normal priority.
+#[cfg(unix)]
+pub fn under_nice(program: &str, args: &[String], priority: i32) -> (String, Vec<String>) {
+    let mut nice_args = vec!["-n".to_string(), priority.to_string(), "--".to_string(), program.to_string()];
+    nice_args.extend_from_slice(args);
+    ("nice".to_string(), nice_args)
+}
+
+#[cfg(windows)]
+pub fn apply_priority(command: &mut Command, priority: i32) {
+    use std::os::windows::process::CommandExt;
+
+    if let Some(class) = priority_class(priority) {
+        command.creation_flags(class);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_runs_with_the_requested_niceness() {
+        // `nice` with no arguments prints its own niceness; 19 is the lowest priority, which
+        // any user may move to
+        let mut command = Command::new("nice");
+        apply_priority(&mut command, 19);
+        let output = command.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "19");
+    }
+}
@@ -0,0 +1,223 @@
+use std::process::{Output, Stdio};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::error::CommandError;
+use crate::spawn_retry::with_spawn_retry;
+
+/// Limits for running untrusted input through a tool. Each is off when `None`; the memory and
+/// CPU limits are applied with setrlimit and only take effect on Unix.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessLimits {
+    // Wall-clock time before the child is killed
+    pub timeout: Option<Duration>,
+    // Address-space cap (RLIMIT_AS)
+    pub max_memory_mb: Option<u64>,
+    // CPU time cap (RLIMIT_CPU); the kernel stops the child with SIGXCPU
+    pub max_cpu_secs: Option<u64>,
+}
+
+/// How the input written to a child's stdin fared, for diagnosing delivery problems such as a
+/// REPL that exits before reading everything it was sent
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StdinDelivery {
+    pub bytes_total: usize,
+    pub bytes_written: usize,
+    // Every byte was written and stdin closed before the child exited
+    pub completed: bool,
+    // The child closed its stdin (usually by exiting) before all the input was written
+    pub broken_pipe: bool,
+    // Any other write failure
+    pub error: Option<String>,
+}
+
+async fn write_stdin(mut stdin: tokio::process::ChildStdin, input: Vec<u8>) -> StdinDelivery {
+    let mut delivery = StdinDelivery {
+        bytes_total: input.len(),
+        ..Default::default()
+    };
+    while delivery.bytes_written < input.len() {
+        match stdin.write(&input[delivery.bytes_written..]).await {
+            Ok(0) => break,
+            Ok(written) => delivery.bytes_written += written,
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                delivery.broken_pipe = true;
+                return delivery;
+            }
+            Err(e) => {
+                delivery.error = Some(e.to_string());
+                return delivery;
+            }
+        }
+    }
+    // Closing stdin is what tells the child the input is over
+    match stdin.shutdown().await {
+        Ok(()) => delivery.completed = delivery.bytes_written == input.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => delivery.broken_pipe = true,
+        Err(e) => delivery.error = Some(e.to_string()),
+    }
+    delivery
+}
+
+#[cfg(unix)]
+fn apply_rlimits(command: &mut tokio::process::Command, limits: &ProcessLimits) {
+    let memory = limits.max_memory_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let cpu = limits.max_cpu_secs;
+    if memory.is_none() && cpu.is_none() {
+        return;
+    }
+
+    fn set(resource: libc::c_int, value: u64) -> std::io::Result<()> {
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+        // Safety: setrlimit only reads the struct passed to it
+        if unsafe { libc::setrlimit(resource as _, &limit) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Safety: runs in the forked child before exec and only calls setrlimit, which is
+    // async-signal-safe
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = memory {
+                set(libc::RLIMIT_AS as libc::c_int, bytes)?;
+            }
+            if let Some(secs) = cpu {
+                set(libc::RLIMIT_CPU as libc::c_int, secs)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_rlimits(_command: &mut tokio::process::Command, _limits: &ProcessLimits) {}
+
+// Whether a failed run hit one of the rlimits rather than failing on its own
+fn hit_rlimit(output: &Output, limits: &ProcessLimits) -> Option<String> {
+    #[cfg(unix)]
+    if let Some(secs) = limits.max_cpu_secs {
+        use std::os::unix::process::ExitStatusExt;
+        if output.status.signal() == Some(libc::SIGXCPU) {
+            return Some(format!("Exceeded the CPU limit of {}s", secs));
+        }
+    }
+    // Rust programs abort with this message when an allocation fails
+    let mb = limits.max_memory_mb?;
+    String::from_utf8_lossy(&output.stderr)
+        .contains("memory allocation of")
+        .then(|| format!("Exceeded the memory limit of {} MB", mb))
+}
+
+/// Spawns `command` with piped stdio, writes `input` to its stdin and collects its output,
+/// killing it with a `Timeout` error if it outlives `limits.timeout`. Runs ended by an rlimit
+/// fail with `ResourceLimit`. Also reports how much of `input` reached the child.
+pub async fn output_with_limits(
+    mut command: tokio::process::Command,
+    input: Vec<u8>,
+    limits: &ProcessLimits,
+) -> Result<(Output, StdinDelivery), CommandError> {
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    apply_rlimits(&mut command, limits);
+    let mut child = with_spawn_retry(|| command.spawn()).map_err(|e| format!("Failed to spawn: {}", e))?;
+
+    // Written alongside the wait so a child that never reads can't block us past the timeout
+    let stdin = child.stdin.take().map(|stdin| tokio::spawn(write_stdin(stdin, input)));
+
+    // Dropping the wait future on timeout drops the child, which kills it
+    let output = match limits.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| CommandError::Timeout(format!("Timed out after {}s", timeout.as_secs_f64())))?,
+        None => child.wait_with_output().await,
+    }
+    .map_err(|e| format!("Failed to read output: {}", e))?;
+
+    if !output.status.success() {
+        if let Some(message) = hit_rlimit(&output, limits) {
+            return Err(CommandError::ResourceLimit(message));
+        }
+    }
+    // The child has exited, so a write still pending fails with a broken pipe straight away
+    let delivery = match stdin {
+        Some(writer) => writer.await.unwrap_or_else(|e| StdinDelivery {
+            error: Some(e.to_string()),
+            ..Default::default()
+        }),
+        None => StdinDelivery::default(),
+    };
+    Ok((output, delivery))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn busy_loop() -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", "while :; do :; done"]);
+        command
+    }
+
+    #[tokio::test]
+    async fn infinite_loop_is_killed_by_the_timeout() {
+        let limits = ProcessLimits {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let result = output_with_limits(busy_loop(), Vec::new(), &limits).await;
+        assert!(matches!(result, Err(CommandError::Timeout(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn stdin_delivery_reports_a_child_that_stops_reading() {
+        let mut reads_four = tokio::process::Command::new("sh");
+        reads_four.args(["-c", "head -c 4 >/dev/null; echo done"]);
+        // Far more than a pipe buffer, so the write can't finish before the child exits
+        let input = vec![b'x'; 4 * 1024 * 1024];
+        let (output, delivery) = output_with_limits(reads_four, input, &ProcessLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "done");
+        assert!(delivery.broken_pipe);
+        assert!(!delivery.completed);
+        assert!(delivery.bytes_written < delivery.bytes_total);
+
+        let cat = tokio::process::Command::new("cat");
+        let (_, delivery) = output_with_limits(cat, b"hello\n".to_vec(), &ProcessLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            delivery,
+            StdinDelivery {
+                bytes_total: 6,
+                bytes_written: 6,
+                completed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn cpu_limit_is_reported_as_a_resource_limit() {
+        let limits = ProcessLimits {
+            timeout: Some(Duration::from_secs(20)),
+            max_cpu_secs: Some(1),
+            ..Default::default()
+        };
+        let result = output_with_limits(busy_loop(), Vec::new(), &limits).await;
+        assert!(matches!(result, Err(CommandError::ResourceLimit(_))));
+    }
+}
@@ -0,0 +1,281 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+// Flags whose following argument is a credential
+const SECRET_FLAGS: [&str; 8] = [
+    "--password",
+    "-p",
+    "--client-secret",
+    "--secret",
+    "--token",
+    "--account-key",
+    "--sas-token",
+    "--connection-string",
+];
+
+// JSON keys holding credentials, compared lowercased with `_` and `-` dropped
+const SECRET_KEY_PARTS: [&str; 5] = ["password", "secret", "token", "accountkey", "connectionstring"];
+
+// Replay keeps the recorded pacing, but doesn't sit through long pauses
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(2);
+
+/// One command and what it returned, as stored in a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    // Wall-clock time, milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    // Milliseconds since the recording started
+    pub elapsed_ms: u64,
+    pub command: String,
+    pub args: Vec<String>,
+    pub output: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingFile {
+    pub started_ms: u64,
+    pub entries: Vec<RecordedEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordingSummary {
+    pub path: String,
+    pub entries: usize,
+}
+
+struct ActiveRecording {
+    path: PathBuf,
+    started: SystemTime,
+    entries: Vec<RecordedEntry>,
+}
+
+/// The recording in progress, if any. Kept in managed state; commands call `record` after
+/// they finish, which does nothing while no recording is running.
+#[derive(Default)]
+pub struct RecordingState {
+    active: Mutex<Option<ActiveRecording>>,
+}
+
+fn unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_lowercase();
+    SECRET_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+pub(crate) fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut hide_next = false;
+    for arg in args {
+        if std::mem::take(&mut hide_next) {
+            redacted.push(REDACTED.to_string());
+        } else if let Some((flag, _)) = arg.split_once('=').filter(|(flag, _)| SECRET_FLAGS.contains(flag)) {
+            redacted.push(format!("{}={}", flag, REDACTED));
+        } else {
+            hide_next = SECRET_FLAGS.contains(&arg.as_str());
+            redacted.push(arg.clone());
+        }
+    }
+    redacted
+}
+
+// Drops the token after every "Bearer " in free text
+fn redact_bearer(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find("Bearer ") {
+        let token_start = at + "Bearer ".len();
+        redacted.push_str(&rest[..token_start]);
+        redacted.push_str(REDACTED);
+        let token_len = rest[token_start..]
+            .find(|c: char| c.is_whitespace() || c == '"')
+            .unwrap_or(rest.len() - token_start);
+        rest = &rest[token_start + token_len..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+// Tool stdout is often JSON inside a string, so strings that parse as JSON are redacted as
+// JSON too. They're only re-serialized when something was actually hidden.
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::String(text) => {
+            if let Ok(mut parsed) = serde_json::from_str::<Value>(text) {
+                if parsed.is_object() || parsed.is_array() {
+                    let original = parsed.clone();
+                    redact_value(&mut parsed);
+                    if parsed != original {
+                        *text = serde_json::to_string_pretty(&parsed).unwrap_or_default();
+                    }
+                    return;
+                }
+            }
+            *text = redact_bearer(text);
+        }
+        _ => {}
+    }
+}
+
+impl RecordingState {
+    pub fn start(&self, path: PathBuf) -> Result<(), String> {
+        let mut active = self.active.lock().unwrap();
+        if let Some(recording) = active.as_ref() {
+            return Err(format!("Already recording to {}", recording.path.display()));
+        }
+        *active = Some(ActiveRecording {
+            path,
+            started: SystemTime::now(),
+            entries: Vec::new(),
+        });
+        Ok(())
+    }
+
+    pub fn record<T: Serialize>(&self, command: &str, args: &[String], output: &T) {
+        let mut active = self.active.lock().unwrap();
+        let Some(recording) = active.as_mut() else {
+            return;
+        };
+        let mut output = serde_json::to_value(output).unwrap_or(Value::Null);
+        redact_value(&mut output);
+        let now = SystemTime::now();
+        recording.entries.push(RecordedEntry {
+            timestamp_ms: unix_ms(now),
+            elapsed_ms: now.duration_since(recording.started).unwrap_or_default().as_millis() as u64,
+            command: command.to_string(),
+            args: redact_args(args),
+            output,
+        });
+    }
+
+    // Ends the recording and writes it out; the recording is dropped even if the write fails
+    pub fn stop(&self) -> Result<RecordingSummary, String> {
+        let recording = self.active.lock().unwrap().take().ok_or("Not recording")?;
+        let file = RecordingFile {
+            started_ms: unix_ms(recording.started),
+            entries: recording.entries,
+        };
+        let json = serde_json::to_vec_pretty(&file).map_err(|e| format!("Failed to serialize recording: {}", e))?;
+        if let Some(parent) = recording.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&recording.path, json)
+            .map_err(|e| format!("Failed to write {}: {}", recording.path.display(), e))?;
+        Ok(RecordingSummary {
+            path: recording.path.display().to_string(),
+            entries: file.entries.len(),
+        })
+    }
+}
+
+fn load_recording(path: &Path) -> Result<RecordingFile, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Invalid recording {}: {}", path.display(), e))
+}
+
+// Records every finder, az and REPL run, with their outputs, until `stop_recording`.
+// Credentials in arguments and outputs are redacted before they're kept.
+#[tauri::command]
+pub fn start_recording(recording: tauri::State<'_, RecordingState>, path: String) -> Result<(), String> {
+    recording.start(PathBuf::from(path))
+}
+
+#[tauri::command]
+pub fn stop_recording(recording: tauri::State<'_, RecordingState>) -> Result<RecordingSummary, String> {
+    recording.stop()
+}
+
+// Re-emits each recorded entry as a `recording-replay` event, paced like the original session
+// (pauses capped at two seconds), then `recording-replay-done`. Nothing is run. Returns the
+// number of entries that will be replayed.
+#[tauri::command]
+pub fn replay_recording(app: AppHandle, path: String) -> Result<usize, String> {
+    let file = load_recording(Path::new(&path))?;
+    let count = file.entries.len();
+    tauri::async_runtime::spawn(async move {
+        let mut previous_ms = 0;
+        for entry in file.entries {
+            let gap = Duration::from_millis(entry.elapsed_ms.saturating_sub(previous_ms));
+            tokio::time::sleep(gap.min(MAX_REPLAY_GAP)).await;
+            previous_ms = entry.elapsed_ms;
+            let _ = app.emit("recording-replay", &entry);
+        }
+        let _ = app.emit("recording-replay-done", count);
+    });
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips_with_secrets_redacted() {
+        let path = std::env::temp_dir().join(format!("skanyxx-recording-{}.json", std::process::id()));
+        let state = RecordingState::default();
+        state.record("run_az", &["ignored".to_string()], &"not recording yet");
+
+        state.start(path.clone()).unwrap();
+        assert!(state.start(path.clone()).is_err());
+        let login: Vec<String> = ["login", "--service-principal", "-u", "app", "-p", "hunter2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        state.record("run_az", &login, &serde_json::json!([{ "name": "sub", "accessToken": "abc" }]));
+        state.record(
+            "run_azure_resource_finder",
+            &["--client-secret=s3cret".to_string()],
+            &serde_json::json!({ "stdout": "{\"password\": \"pw\", \"name\": \"vm1\"}", "success": true }),
+        );
+        let summary = state.stop().unwrap();
+        assert_eq!(summary.entries, 2);
+        assert!(state.active.lock().unwrap().is_none());
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        for secret in ["hunter2", "abc", "s3cret", "\\\"pw\\\""] {
+            assert!(!raw.contains(secret), "{} leaked into the recording", secret);
+        }
+
+        let file = load_recording(&path).unwrap();
+        assert_eq!(file.entries[0].command, "run_az");
+        assert_eq!(file.entries[0].args[5], REDACTED);
+        assert_eq!(file.entries[0].output[0]["name"], "sub");
+        assert_eq!(file.entries[1].args, ["--client-secret=[REDACTED]"]);
+        assert!(file.entries[1].output["stdout"].as_str().unwrap().contains("vm1"));
+        assert!(file.entries[0].elapsed_ms <= file.entries[1].elapsed_ms);
+        assert!(file.started_ms <= file.entries[0].timestamp_ms);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bearer_tokens_are_redacted_in_text() {
+        assert_eq!(
+            redact_bearer("Authorization: Bearer eyJ0.abc rest"),
+            "Authorization: Bearer [REDACTED] rest"
+        );
+    }
+}
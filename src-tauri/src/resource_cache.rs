@@ -0,0 +1,479 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::preferences::{Preferences, PreferencesState};
+use crate::resource_views::id_segment;
+
+pub const RESOURCE_CACHE_FILE: &str = "resource_cache.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_ms INTEGER NOT NULL,
+    subscription TEXT
+);
+CREATE TABLE IF NOT EXISTS resources (
+    run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    resource_id TEXT NOT NULL,
+    resource_group TEXT,
+    type TEXT,
+    name TEXT,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS resources_by_run ON resources(run_id);
+";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// How much history the cache keeps; 0 turns a bound off
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    pub max_runs: usize,
+    pub max_age_days: u64,
+}
+
+impl From<&Preferences> for Retention {
+    fn from(prefs: &Preferences) -> Self {
+        Retention {
+            max_runs: prefs.resource_cache_max_runs,
+            max_age_days: prefs.resource_cache_max_age_days,
+        }
+    }
+}
+
+/// A stored finder run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CachedRun {
+    pub run_id: i64,
+    pub created_ms: i64,
+    pub subscription: Option<String>,
+    pub resource_count: u64,
+}
+
+/// Which cached resources to read back. Without `run_id`, the latest run (for `subscription`,
+/// when given) is used.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ResourceFilter {
+    pub run_id: Option<i64>,
+    pub subscription: Option<String>,
+    #[serde(rename = "type")]
+    pub resource_type: Option<String>,
+    pub resource_group: Option<String>,
+    // Case-insensitive substring of the resource name
+    pub name_contains: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CachedResources {
+    pub run: CachedRun,
+    pub resources: Vec<Value>,
+}
+
+/// SQLite store of parsed finder results in the config dir, opened (and its schema created) on
+/// first use. Kept in managed state.
+pub struct ResourceCache {
+    path: PathBuf,
+    conn: Mutex<Option<Connection>>,
+}
+
+impl ResourceCache {
+    pub fn new(path: PathBuf) -> Self {
+        ResourceCache {
+            path,
+            conn: Mutex::new(None),
+        }
+    }
+
+    fn with_conn<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        let mut conn = self.conn.lock().unwrap();
+        if conn.is_none() {
+            if let Some(parent) = self.path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            let opened = Connection::open(&self.path)
+                .and_then(|c| c.execute_batch("PRAGMA foreign_keys = ON;").map(|_| c))
+                .and_then(|c| c.execute_batch(SCHEMA).map(|_| c))
+                .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+            *conn = Some(opened);
+        }
+        f(conn.as_mut().unwrap()).map_err(|e| format!("Resource cache error: {}", e))
+    }
+
+    // Stores one run's resources and then trims history to `retention`, returning the run id
+    pub fn store_run(
+        &self,
+        subscription: Option<&str>,
+        resources: &[Value],
+        retention: Retention,
+    ) -> Result<i64, String> {
+        let created_ms = now_ms();
+        self.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO runs (created_ms, subscription) VALUES (?1, ?2)",
+                params![created_ms, subscription],
+            )?;
+            let run_id = tx.last_insert_rowid();
+            {
+                let mut insert = tx.prepare(
+                    "INSERT INTO resources (run_id, resource_id, resource_group, type, name, data)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                for resource in resources {
+                    let field = |key: &str| resource.get(key).and_then(Value::as_str);
+                    let id = field("id").unwrap_or_default();
+                    let segments: Vec<&str> = id.split('/').filter(|s| !s.is_empty()).collect();
+                    let group = field("resourceGroup")
+                        .or_else(|| id_segment(&segments, "resourceGroups"))
+                        .map(str::to_ascii_lowercase);
+                    insert.execute(params![
+                        run_id,
+                        id,
+                        group,
+                        field("type"),
+                        field("name"),
+                        resource.to_string()
+                    ])?;
+                }
+            }
+
+            if retention.max_age_days > 0 {
+                let cutoff = created_ms - retention.max_age_days as i64 * DAY_MS;
+                tx.execute("DELETE FROM runs WHERE created_ms < ?1", params![cutoff])?;
+            }
+            if retention.max_runs > 0 {
+                tx.execute(
+                    "DELETE FROM runs WHERE id NOT IN (SELECT id FROM runs ORDER BY id DESC LIMIT ?1)",
+                    params![retention.max_runs as i64],
+                )?;
+            }
+            tx.commit()?;
+            Ok(run_id)
+        })
+    }
+
+    pub fn runs(&self) -> Result<Vec<CachedRun>, String> {
+        self.with_conn(|conn| {
+            let mut select = conn.prepare(
+                "SELECT runs.id, runs.created_ms, runs.subscription, COUNT(resources.run_id)
+                 FROM runs LEFT JOIN resources ON resources.run_id = runs.id
+                 GROUP BY runs.id ORDER BY runs.id DESC",
+            )?;
+            let runs = select
+                .query_map([], |row| {
+                    Ok(CachedRun {
+                        run_id: row.get(0)?,
+                        created_ms: row.get(1)?,
+                        subscription: row.get(2)?,
+                        resource_count: row.get::<_, i64>(3)? as u64,
+                    })
+                })?
+                .collect();
+            runs
+        })
+    }
+
+    // The run `filter` picks, or None when the cache has no such run
+    fn find_run(&self, filter: &ResourceFilter) -> Result<Option<CachedRun>, String> {
+        let runs = self.runs()?;
+        Ok(runs.into_iter().find(|run| match filter.run_id {
+            Some(id) => run.run_id == id,
+            None => filter.subscription.is_none() || run.subscription == filter.subscription,
+        }))
+    }
+
+    pub fn query(&self, filter: &ResourceFilter) -> Result<Option<CachedResources>, String> {
+        let Some(run) = self.find_run(filter)? else {
+            return Ok(None);
+        };
+        let mut sql = "SELECT data FROM resources WHERE run_id = ?".to_string();
+        let mut values = vec![SqlValue::Integer(run.run_id)];
+        if let Some(resource_type) = &filter.resource_type {
+            sql.push_str(" AND type = ? COLLATE NOCASE");
+            values.push(SqlValue::Text(resource_type.clone()));
+        }
+        if let Some(group) = &filter.resource_group {
+            sql.push_str(" AND resource_group = ?");
+            values.push(SqlValue::Text(group.to_ascii_lowercase()));
+        }
+        if let Some(name) = &filter.name_contains {
+            sql.push_str(" AND instr(lower(name), ?) > 0");
+            values.push(SqlValue::Text(name.to_lowercase()));
+        }
+        sql.push_str(" ORDER BY rowid");
+
+        let data: Vec<String> = self.with_conn(|conn| {
+            let mut select = conn.prepare(&sql)?;
+            let rows = select.query_map(params_from_iter(values.iter()), |row| row.get(0))?.collect();
+            rows
+        })?;
+        let resources = data
+            .iter()
+            .filter_map(|json| serde_json::from_str(json).ok())
+            .collect();
+        Ok(Some(CachedResources { run, resources }))
+    }
+
+    // Every resource stored for one run, by resource id
+    fn run_resources(&self, run_id: i64) -> Result<Vec<(String, Value)>, String> {
+        let rows: Option<Vec<(String, String)>> = self.with_conn(|conn| {
+            let exists = conn
+                .query_row("SELECT id FROM runs WHERE id = ?1", params![run_id], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .optional()?;
+            if exists.is_none() {
+                return Ok(None);
+            }
+            let mut select =
+                conn.prepare("SELECT resource_id, data FROM resources WHERE run_id = ?1 ORDER BY rowid")?;
+            let rows = select
+                .query_map(params![run_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(Some(rows))
+        })?;
+        let rows = rows.ok_or_else(|| format!("No cached run with id {}", run_id))?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, json)| Some((id, serde_json::from_str(&json).ok()?)))
+            .collect())
+    }
+}
+
+// Fields that change on every read without the resource itself changing
+const VOLATILE_FIELDS: [&str; 1] = ["etag"];
+
+// Hash of a resource's properties with object keys sorted and volatile fields dropped, so key
+// order and etag churn don't count as changes
+fn property_hash(resource: &Value) -> u64 {
+    fn normalize(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| !VOLATILE_FIELDS.contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), normalize(value)))
+                    .collect::<BTreeMap<_, _>>()
+                    .into_iter()
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+            other => other.clone(),
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    normalize(resource).to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A resource present in both runs with different properties
+#[derive(Debug, Serialize)]
+pub struct ResourceChange {
+    pub id: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// What changed from one cached run to another
+#[derive(Debug, Serialize)]
+pub struct RunDiff {
+    pub added: Vec<Value>,
+    pub removed: Vec<Value>,
+    pub changed: Vec<ResourceChange>,
+    pub unchanged: usize,
+}
+
+// Pairs resources by id (case-insensitively, as ARM ids are) and compares their property hashes
+fn diff_resources(before: Vec<(String, Value)>, after: Vec<(String, Value)>) -> RunDiff {
+    let mut before: BTreeMap<String, Value> =
+        before.into_iter().map(|(id, resource)| (id.to_ascii_lowercase(), resource)).collect();
+    let mut diff = RunDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        unchanged: 0,
+    };
+    for (id, resource) in after {
+        match before.remove(&id.to_ascii_lowercase()) {
+            None => diff.added.push(resource),
+            Some(old) if property_hash(&old) == property_hash(&resource) => diff.unchanged += 1,
+            Some(old) => diff.changed.push(ResourceChange {
+                id,
+                before: old,
+                after: resource,
+            }),
+        }
+    }
+    diff.removed = before.into_values().collect();
+    diff
+}
+
+// The resource list in a finder's JSON output: a bare array, or one under `value`/`resources`
+pub(crate) fn parse_finder_resources(stdout: &str) -> Option<Vec<Value>> {
+    match serde_json::from_str(stdout).ok()? {
+        Value::Array(resources) => Some(resources),
+        Value::Object(mut object) => {
+            match object.remove("value").or_else(|| object.remove("resources"))? {
+                Value::Array(resources) => Some(resources),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Stored finder runs, newest first
+#[tauri::command]
+pub fn list_cached_runs(cache: tauri::State<'_, ResourceCache>) -> Result<Vec<CachedRun>, String> {
+    cache.runs()
+}
+
+// Reads resources from a cached finder run without re-running it, e.g. while offline. `null`
+// when no run matches.
+#[tauri::command]
+pub fn query_cached_resources(
+    cache: tauri::State<'_, ResourceCache>,
+    filter: Option<ResourceFilter>,
+) -> Result<Option<CachedResources>, String> {
+    cache.query(&filter.unwrap_or_default())
+}
+
+// Resources added, removed and changed from cached run `run_id_a` to `run_id_b`, for drift
+// detection across any two stored runs
+#[tauri::command]
+pub fn diff_cached_runs(
+    cache: tauri::State<'_, ResourceCache>,
+    run_id_a: i64,
+    run_id_b: i64,
+) -> Result<RunDiff, String> {
+    Ok(diff_resources(cache.run_resources(run_id_a)?, cache.run_resources(run_id_b)?))
+}
+
+// Stores resources parsed elsewhere (e.g. a streamed finder run) as a cached run
+#[tauri::command]
+pub fn cache_resources(
+    cache: tauri::State<'_, ResourceCache>,
+    prefs: tauri::State<'_, PreferencesState>,
+    subscription: Option<String>,
+    resources: Vec<Value>,
+) -> Result<i64, String> {
+    cache.store_run(subscription.as_deref(), &resources, Retention::from(&prefs.get()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_cache(name: &str) -> (ResourceCache, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("skanyxx-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        (ResourceCache::new(dir.join(RESOURCE_CACHE_FILE)), dir)
+    }
+
+    #[test]
+    fn stored_run_can_be_queried_back() {
+        let (cache, dir) = temp_cache("query");
+        let resources = vec![
+            json!({ "id": "/subscriptions/s1/resourceGroups/RG-Web/providers/Microsoft.Web/sites/app1", "name": "app1", "type": "Microsoft.Web/sites" }),
+            json!({ "id": "/subscriptions/s1/resourceGroups/rg-data/providers/Microsoft.Sql/servers/db1", "name": "db1", "type": "Microsoft.Sql/servers" }),
+            json!({ "id": "/subscriptions/s1/resourceGroups/rg-web/providers/Microsoft.Web/sites/api", "name": "api", "type": "Microsoft.Web/sites" }),
+        ];
+        let retention = Retention {
+            max_runs: 2,
+            max_age_days: 0,
+        };
+        let first = cache.store_run(Some("s1"), &resources[..1], retention).unwrap();
+        let latest = cache.store_run(Some("s1"), &resources, retention).unwrap();
+
+        let all = cache.query(&ResourceFilter::default()).unwrap().unwrap();
+        assert_eq!(all.run.run_id, latest);
+        assert_eq!(all.run.resource_count, 3);
+        assert_eq!(all.resources, resources);
+
+        let filter = ResourceFilter {
+            resource_type: Some("microsoft.web/sites".to_string()),
+            resource_group: Some("RG-WEB".to_string()),
+            name_contains: Some("AP".to_string()),
+            ..Default::default()
+        };
+        let web = cache.query(&filter).unwrap().unwrap();
+        assert_eq!(web.resources, [resources[0].clone(), resources[2].clone()]);
+
+        let older = ResourceFilter {
+            run_id: Some(first),
+            ..Default::default()
+        };
+        assert_eq!(cache.query(&older).unwrap().unwrap().resources.len(), 1);
+        let other_subscription = ResourceFilter {
+            subscription: Some("s2".to_string()),
+            ..Default::default()
+        };
+        assert!(cache.query(&other_subscription).unwrap().is_none());
+
+        // A third run pushes the first out of the two-run history
+        cache.store_run(Some("s1"), &resources, retention).unwrap();
+        let runs: Vec<i64> = cache.runs().unwrap().iter().map(|run| run.run_id).collect();
+        assert!(!runs.contains(&first));
+        assert_eq!(runs.len(), 2);
+        drop(cache);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn runs_are_diffed_by_id_and_properties() {
+        let (cache, dir) = temp_cache("diff");
+        let retention = Retention {
+            max_runs: 0,
+            max_age_days: 0,
+        };
+        let vm = json!({ "id": "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Compute/virtualMachines/vm1", "tags": { "a": "1", "b": "2" }, "etag": "1" });
+        let old_disk = json!({ "id": "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Compute/disks/d1" });
+        let new_disk = json!({ "id": "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Compute/disks/d2" });
+        let first = cache.store_run(Some("s1"), &[vm.clone(), old_disk.clone()], retention).unwrap();
+        // Same VM with a new etag and the id in different case: not a change
+        let vm_again = json!({ "id": "/SUBSCRIPTIONS/s1/resourceGroups/rg/providers/Microsoft.Compute/virtualMachines/vm1", "etag": "2", "tags": { "b": "2", "a": "1" } });
+        let second = cache.store_run(Some("s1"), &[new_disk.clone(), vm_again], retention).unwrap();
+
+        let diff = diff_resources(cache.run_resources(first).unwrap(), cache.run_resources(second).unwrap());
+        assert_eq!(diff.added, [new_disk]);
+        assert_eq!(diff.removed, [old_disk]);
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.unchanged, 1);
+
+        let changed = vec![("vm".to_string(), json!({ "tags": { "a": "3" } }))];
+        let diff = diff_resources(vec![("VM".to_string(), json!({ "tags": { "a": "1" } }))], changed);
+        assert_eq!(diff.changed.len(), 1);
+        assert!(cache.run_resources(9999).is_err());
+        drop(cache);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn finder_output_shapes_are_recognised() {
+        assert_eq!(parse_finder_resources(r#"[{"id": "a"}]"#).unwrap().len(), 1);
+        assert_eq!(parse_finder_resources(r#"{"value": [{"id": "a"}, {"id": "b"}]}"#).unwrap().len(), 2);
+        assert!(parse_finder_resources("Found 3 resources").is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::Path;
+use std::process::Stdio;
+
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::jobs::{self, JobOutput, JobRegistry};
+use crate::json_stream::JsonArrayStream;
+use crate::preferences::PreferencesState;
+
+// Runs azure-resource-finder and emits each element of its JSON array output as a `resource`
+// event while the tool is still writing, followed by `resources-done` with the total count.
+// Failures (spawn errors, malformed JSON, non-zero exit, cancellation) emit `resources-error`.
+// `output_file` also saves the raw output as it arrives; if the stream is cancelled the file is
+// deleted, or kept as `<output_file>.partial` with `keep_partial`, so a truncated export can't
+// pass for a complete one. A relative `output_file` lands under `workspace_dir`, and
+// `resources-done` carries the resolved path. A `command-exit` event reports the finder's exit
+// as soon as it happens.
+#[tauri::command]
+pub async fn stream_azure_resources(
+    app: AppHandle,
+    prefs: tauri::State<'_, PreferencesState>,
+    jobs: tauri::State<'_, JobRegistry>,
+    args: Vec<String>,
+    output_file: Option<String>,
+    keep_partial: Option<bool>,
+) -> Result<usize, String> {
+    let output_path = output_file.map(|path| prefs.get().resolve_output_path(&path)).transpose()?;
+    let output = output_path.clone().map(|path| JobOutput {
+        path,
+        keep_partial: keep_partial.unwrap_or(false),
+    });
+    let result = jobs
+        .run_with_output(
+            "stream_azure_resources",
+            output,
+            stream_resources(&app, &prefs, &jobs, args, output_path.as_deref()),
+        )
+        .await;
+    match &result {
+        Ok(total) => {
+            let output_file = output_path.as_ref().map(|path| path.display().to_string());
+            app.emit("resources-done", serde_json::json!({ "total": total, "output_file": output_file }))
+                .map_err(|e| format!("Failed to emit resources-done: {}", e))?;
+        }
+        Err(message) => {
+            let _ = app.emit("resources-error", serde_json::json!({ "message": message }));
+        }
+    }
+    result
+}
+
+async fn stream_resources(
+    app: &AppHandle,
+    prefs: &PreferencesState,
+    jobs: &JobRegistry,
+    args: Vec<String>,
+    output_file: Option<&Path>,
+) -> Result<usize, String> {
+    let tool_info = crate::locate_tool("azure-resource-finder".to_string(), &prefs.get()).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Azure Resource Finder not available".to_string()));
+    }
+    let azure_finder_path = tool_info.path.unwrap();
+
+    let started = std::time::Instant::now();
+    let mut child = tokio::process::Command::new(&azure_finder_path)
+        .args(&args)
+        .envs(crate::azure_tool_env())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute azure-resource-finder: {}", e))?;
+    let _tracked = jobs.track_child(child.id(), "azure-resource-finder", &args);
+
+    // Drain stderr alongside stdout so a chatty tool can't block on a full pipe
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr_task = tokio::spawn(async move {
+        let mut buffer = String::new();
+        let _ = stderr.read_to_string(&mut buffer).await;
+        buffer
+    });
+
+    let mut file = match output_file {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            let file = tokio::fs::File::create(path)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            Some(file)
+        }
+        None => None,
+    };
+
+    let mut stdout = child.stdout.take().unwrap();
+    let mut parser = JsonArrayStream::new();
+    let read_all = async {
+        let mut chunk = [0u8; 8192];
+        let mut total = 0;
+        loop {
+            let read = stdout
+                .read(&mut chunk)
+                .await
+                .map_err(|e| format!("Failed to read azure-resource-finder output: {}", e))?;
+            if read == 0 {
+                return Ok::<usize, String>(total);
+            }
+            if let Some(file) = file.as_mut() {
+                file.write_all(&chunk[..read])
+                    .await
+                    .map_err(|e| format!("Failed to write output file: {}", e))?;
+            }
+
+            // Returning early drops the child, which kills it
+            for resource in parser.feed(&chunk[..read])? {
+                app.emit("resource", resource)
+                    .map_err(|e| format!("Failed to emit resource: {}", e))?;
+                total += 1;
+            }
+        }
+    };
+    // Waited for alongside the reads so `command-exit` goes out when the finder exits, not when
+    // its stdout closes
+    let wait = async {
+        jobs::wait_reporting_exit(&mut child, started, |exit| {
+            let _ = app.emit("command-exit", exit);
+        })
+        .await
+        .map_err(|e| format!("Failed to wait for azure-resource-finder: {}", e))
+    };
+    let (total, status) = tokio::try_join!(read_all, wait)?;
+    let stderr = stderr_task.await.unwrap_or_default();
+    if !status.success() {
+        return Err(format!("azure-resource-finder exited with {}: {}", status, stderr.trim()));
+    }
+
+    parser.finish()?;
+    if let Some(file) = file.as_mut() {
+        file.flush().await.map_err(|e| format!("Failed to write output file: {}", e))?;
+    }
+    Ok(total)
+}
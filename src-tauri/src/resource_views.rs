@@ -0,0 +1,496 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Buckets for resources whose id doesn't name a subscription or resource group
+const NO_SUBSCRIPTION: &str = "(no subscription)";
+const NO_RESOURCE_GROUP: &str = "(no resource group)";
+const UNKNOWN_TYPE: &str = "unknown";
+
+#[derive(Debug, Serialize)]
+pub struct ResourceTypeNode {
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub resources: Vec<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceGroupNode {
+    pub name: String,
+    pub types: Vec<ResourceTypeNode>,
+}
+
+/// One subscription's resources, nested by resource group and then type
+#[derive(Debug, Serialize)]
+pub struct SubscriptionNode {
+    pub subscription_id: String,
+    pub resource_groups: Vec<ResourceGroupNode>,
+}
+
+// The value after `key` in an ARM id path; ARM compares the keys case-insensitively
+pub(crate) fn id_segment<'a>(segments: &[&'a str], key: &str) -> Option<&'a str> {
+    segments
+        .windows(2)
+        .find(|pair| pair[0].eq_ignore_ascii_case(key))
+        .map(|pair| pair[1])
+}
+
+// The `type` field, or `Namespace/type` from the first `providers` segment of the id
+fn resource_type(resource: &Value, segments: &[&str]) -> String {
+    if let Some(resource_type) = resource.get("type").and_then(Value::as_str) {
+        return resource_type.to_string();
+    }
+    segments
+        .iter()
+        .position(|segment| segment.eq_ignore_ascii_case("providers"))
+        .and_then(|at| Some(format!("{}/{}", segments.get(at + 1)?, segments.get(at + 2)?)))
+        .unwrap_or_else(|| UNKNOWN_TYPE.to_string())
+}
+
+type Tree = BTreeMap<String, BTreeMap<String, BTreeMap<String, Vec<Value>>>>;
+
+fn build_tree(resources: Vec<Value>) -> Vec<SubscriptionNode> {
+    let mut tree = Tree::new();
+    for resource in resources {
+        let id = resource.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
+        let segments: Vec<&str> = id.split('/').filter(|segment| !segment.is_empty()).collect();
+        let subscription = id_segment(&segments, "subscriptions").unwrap_or(NO_SUBSCRIPTION);
+        // Group names are case-insensitive in ARM, and ids don't always agree on the casing
+        let group = id_segment(&segments, "resourceGroups")
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_else(|| NO_RESOURCE_GROUP.to_string());
+        let resource_type = resource_type(&resource, &segments);
+
+        tree.entry(subscription.to_string())
+            .or_default()
+            .entry(group)
+            .or_default()
+            .entry(resource_type)
+            .or_default()
+            .push(resource);
+    }
+
+    tree.into_iter()
+        .map(|(subscription_id, groups)| SubscriptionNode {
+            subscription_id,
+            resource_groups: groups
+                .into_iter()
+                .map(|(name, types)| ResourceGroupNode {
+                    name,
+                    types: types
+                        .into_iter()
+                        .map(|(resource_type, resources)| ResourceTypeNode { resource_type, resources })
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// Nests a flat resource listing as subscription -> resource group -> type, from each resource's
+// ARM id, for the tree view. Resources outside any group (e.g. subscription-level ones) go in a
+// `(no resource group)` bucket. Levels are sorted by name.
+#[tauri::command]
+pub fn build_resource_tree(resources: Vec<Value>) -> Vec<SubscriptionNode> {
+    build_tree(resources)
+}
+
+/// How many resources of one type a listing has
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TypeCount {
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub count: usize,
+}
+
+fn count_by_type(resources: &[Value]) -> Vec<TypeCount> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for resource in resources {
+        let resource_type = resource.get("type").and_then(Value::as_str).unwrap_or(UNKNOWN_TYPE);
+        *counts.entry(resource_type).or_default() += 1;
+    }
+    let mut counts: Vec<TypeCount> = counts
+        .into_iter()
+        .map(|(resource_type, count)| TypeCount {
+            resource_type: resource_type.to_string(),
+            count,
+        })
+        .collect();
+    // Stable sort keeps equal counts in type-name order
+    counts.sort_by(|a, b| b.count.cmp(&a.count));
+    counts
+}
+
+// Resource count per `type`, most common first, for the dashboard breakdown tile. Entries
+// without a type are counted as `unknown`.
+#[tauri::command]
+pub fn summarize_resources_by_type(resources: Vec<Value>) -> Vec<TypeCount> {
+    count_by_type(&resources)
+}
+
+/// An ARM resource id split into its parts. Nested resources have one name per type level,
+/// e.g. type `Microsoft.Sql/servers/databases` with names `["sql1", "db1"]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceIdParts {
+    pub subscription_id: String,
+    #[serde(default)]
+    pub resource_group: Option<String>,
+    // Provider namespace, e.g. `Microsoft.Sql`; derived from `resource_type` when building
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub resource_type: Option<String>,
+    // The innermost name; derived from `names` when building
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+fn parse_id(id: &str) -> Result<ResourceIdParts, String> {
+    let invalid = |reason: &str| format!("Invalid resource id '{}': {}", id, reason);
+    let segments: Vec<&str> = id.trim_end_matches('/').split('/').collect();
+    let rooted = segments.first() == Some(&"");
+    if !rooted || !segments.get(1).is_some_and(|s| s.eq_ignore_ascii_case("subscriptions")) {
+        return Err(invalid("it must start with /subscriptions/"));
+    }
+    let segments = &segments[1..];
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(invalid("it has an empty segment"));
+    }
+    let subscription_id = segments.get(1).ok_or_else(|| invalid("the subscription id is missing"))?;
+    let mut rest = &segments[2..];
+
+    let mut resource_group = None;
+    if rest.first().is_some_and(|s| s.eq_ignore_ascii_case("resourceGroups")) {
+        let group = rest.get(1).ok_or_else(|| invalid("the resource group name is missing"))?;
+        resource_group = Some(group.to_string());
+        rest = &rest[2..];
+    }
+
+    let mut parts = ResourceIdParts {
+        subscription_id: subscription_id.to_string(),
+        resource_group,
+        provider: None,
+        resource_type: None,
+        name: None,
+        names: Vec::new(),
+    };
+    if rest.is_empty() {
+        return Ok(parts);
+    }
+    if !rest[0].eq_ignore_ascii_case("providers") || rest.len() < 4 {
+        return Err(invalid("expected providers/{namespace}/{type}/{name}"));
+    }
+    let typed = &rest[2..];
+    if typed.len() % 2 != 0 {
+        return Err(invalid("every resource type needs a name"));
+    }
+    let types: Vec<&str> = typed.iter().step_by(2).copied().collect();
+    parts.names = typed.iter().skip(1).step_by(2).map(|name| name.to_string()).collect();
+    parts.name = parts.names.last().cloned();
+    parts.resource_type = Some(format!("{}/{}", rest[1], types.join("/")));
+    parts.provider = Some(rest[1].to_string());
+    Ok(parts)
+}
+
+fn build_id(parts: &ResourceIdParts) -> Result<String, String> {
+    let check = |value: &str, what: &str| {
+        if value.is_empty() || value.contains('/') {
+            return Err(format!("{} must be non-empty and contain no '/'", what));
+        }
+        Ok(())
+    };
+    check(&parts.subscription_id, "subscription_id")?;
+    let mut id = format!("/subscriptions/{}", parts.subscription_id);
+    if let Some(group) = &parts.resource_group {
+        check(group, "resource_group")?;
+        id.push_str("/resourceGroups/");
+        id.push_str(group);
+    }
+    let Some(resource_type) = &parts.resource_type else {
+        if !parts.names.is_empty() {
+            return Err("names were given without a resource_type".to_string());
+        }
+        return Ok(id);
+    };
+    let mut type_segments = resource_type.split('/');
+    let namespace = type_segments.next().unwrap_or_default();
+    let types: Vec<&str> = type_segments.collect();
+    if namespace.is_empty() || types.is_empty() || types.iter().any(|t| t.is_empty()) {
+        return Err(format!("resource_type '{}' must look like Namespace/type[/childType]", resource_type));
+    }
+    if types.len() != parts.names.len() {
+        return Err(format!(
+            "resource_type '{}' has {} type levels but {} names were given",
+            resource_type,
+            types.len(),
+            parts.names.len()
+        ));
+    }
+    id.push_str("/providers/");
+    id.push_str(namespace);
+    for (level, name) in types.iter().zip(&parts.names) {
+        check(name, "each name")?;
+        id.push_str(&format!("/{}/{}", level, name));
+    }
+    Ok(id)
+}
+
+// Splits a resource id into subscription, resource group, provider, type and names so the UI
+// doesn't have to string-split ids itself
+#[tauri::command]
+pub fn parse_resource_id(id: String) -> Result<ResourceIdParts, String> {
+    parse_id(id.trim())
+}
+
+// The reverse of parse_resource_id, from `subscription_id`, `resource_group`, `resource_type`
+// and `names`; `provider` and `name` are ignored since they follow from the others
+#[tauri::command]
+pub fn build_resource_id(components: ResourceIdParts) -> Result<String, String> {
+    build_id(&components)
+}
+
+/// Several listings combined into one, each resource once
+#[derive(Debug, Serialize)]
+pub struct MergedResources {
+    pub resources: Vec<Value>,
+    pub duplicates_removed: usize,
+}
+
+// Adds the listing index to `sourceListings`, and `subscriptionId` from the id when the
+// resource doesn't carry one
+fn add_provenance(resource: &mut Value, listing: usize) {
+    let subscription = resource
+        .get("id")
+        .and_then(Value::as_str)
+        .and_then(|id| {
+            let segments: Vec<&str> = id.split('/').filter(|segment| !segment.is_empty()).collect();
+            id_segment(&segments, "subscriptions").map(str::to_string)
+        });
+    let Some(fields) = resource.as_object_mut() else {
+        return;
+    };
+    if let Some(subscription) = subscription {
+        fields.entry("subscriptionId").or_insert(Value::String(subscription));
+    }
+    match fields.get_mut("sourceListings").and_then(Value::as_array_mut) {
+        Some(sources) => {
+            if !sources.contains(&Value::from(listing)) {
+                sources.push(Value::from(listing));
+            }
+        }
+        None => {
+            fields.insert("sourceListings".to_string(), Value::from(vec![listing]));
+        }
+    }
+}
+
+fn merge_listings(listings: Vec<Vec<Value>>) -> MergedResources {
+    let mut resources: Vec<Value> = Vec::new();
+    // Lowercased id -> position in `resources`; ARM ids are case-insensitive
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut duplicates_removed = 0;
+    for (listing, listed) in listings.into_iter().enumerate() {
+        for mut resource in listed {
+            let id = resource.get("id").and_then(Value::as_str).map(str::to_lowercase);
+            if let Some(&at) = id.as_ref().and_then(|id| seen.get(id)) {
+                add_provenance(&mut resources[at], listing);
+                duplicates_removed += 1;
+                continue;
+            }
+            add_provenance(&mut resource, listing);
+            if let Some(id) = id {
+                seen.insert(id, resources.len());
+            }
+            resources.push(resource);
+        }
+    }
+    MergedResources {
+        resources,
+        duplicates_removed,
+    }
+}
+
+// Concatenates listings from several subscription scans, keeping the first copy of each
+// resource id. Each resource records which listings (by index) it came from in
+// `sourceListings`, and its subscription in `subscriptionId`. Resources without an id are
+// never treated as duplicates.
+#[tauri::command]
+pub fn merge_resource_listings(listings: Vec<Vec<Value>>) -> MergedResources {
+    merge_listings(listings)
+}
+
+// A field for a table cell; dots reach into nested objects, e.g. `properties.provisioningState`
+fn column_value<'a>(resource: &'a Value, column: &str) -> Option<&'a Value> {
+    column.split('.').try_fold(resource, |value, key| value.get(key))
+}
+
+// A pipe would end the cell and a newline the row
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+fn markdown_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => escape_cell(text),
+        Some(other) => escape_cell(&other.to_string()),
+    }
+}
+
+// The resources as a GitHub-flavored Markdown table of `columns`, for pasting into issues and
+// docs. Missing fields are left as empty cells; objects and arrays are written as JSON.
+#[tauri::command]
+pub fn export_resources_markdown(resources: Vec<Value>, columns: Vec<String>) -> Result<String, String> {
+    if columns.is_empty() {
+        return Err("At least one column is needed for a Markdown table".to_string());
+    }
+    let row = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+    let mut table = row(columns.iter().map(|column| escape_cell(column)).collect());
+    table.push_str(&row(vec!["---".to_string(); columns.len()]));
+    for resource in &resources {
+        table.push_str(&row(columns.iter().map(|column| markdown_cell(column_value(resource, column))).collect()));
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resources_are_nested_by_subscription_group_and_type() {
+        let resources = vec![
+            json!({ "id": "/subscriptions/s1/resourceGroups/rg-web/providers/Microsoft.Web/sites/app1", "type": "Microsoft.Web/sites" }),
+            json!({ "id": "/subscriptions/s1/resourceGroups/rg-data/providers/Microsoft.Sql/servers/db1" }),
+            json!({ "id": "/subscriptions/s1/resourcegroups/RG-WEB/providers/Microsoft.Web/sites/app2", "type": "Microsoft.Web/sites" }),
+            json!({ "id": "/subscriptions/s1/providers/Microsoft.Security/pricings/default" }),
+        ];
+        let tree = build_tree(resources);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].subscription_id, "s1");
+        let groups: Vec<&str> = tree[0].resource_groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(groups, ["(no resource group)", "rg-data", "rg-web"]);
+
+        let data = &tree[0].resource_groups[1];
+        assert_eq!(data.types[0].resource_type, "Microsoft.Sql/servers");
+        let web = &tree[0].resource_groups[2];
+        assert_eq!(web.types.len(), 1);
+        assert_eq!(web.types[0].resources.len(), 2);
+        assert_eq!(tree[0].resource_groups[0].types[0].resource_type, "Microsoft.Security/pricings");
+    }
+
+    #[test]
+    fn type_counts_are_sorted_most_common_first() {
+        let resources = vec![
+            json!({ "type": "Microsoft.Storage/storageAccounts" }),
+            json!({ "type": "Microsoft.Compute/virtualMachines" }),
+            json!({ "name": "no-type" }),
+            json!({ "type": "Microsoft.Compute/virtualMachines" }),
+            json!({ "type": "Microsoft.Compute/disks" }),
+            json!({ "type": "Microsoft.Compute/virtualMachines" }),
+            json!({ "type": "Microsoft.Compute/disks" }),
+        ];
+        let summary = count_by_type(&resources);
+        let counts: Vec<(&str, usize)> = summary.iter().map(|c| (c.resource_type.as_str(), c.count)).collect();
+        assert_eq!(
+            counts,
+            [
+                ("Microsoft.Compute/virtualMachines", 3),
+                ("Microsoft.Compute/disks", 2),
+                ("Microsoft.Storage/storageAccounts", 1),
+                ("unknown", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_table_escapes_pipes_and_leaves_missing_fields_empty() {
+        let resources = vec![
+            json!({ "name": "web|api", "location": "westeurope", "properties": { "sku": "P1v3" } }),
+            json!({ "name": "db1", "properties": {} }),
+        ];
+        let columns = ["name", "location", "properties.sku"].map(String::from).to_vec();
+        let table = export_resources_markdown(resources, columns).unwrap();
+        assert_eq!(
+            table,
+            "| name | location | properties.sku |\n\
+             | --- | --- | --- |\n\
+             | web\\|api | westeurope | P1v3 |\n\
+             | db1 |  |  |\n"
+        );
+        assert!(export_resources_markdown(Vec::new(), Vec::new()).is_err());
+    }
+
+    #[test]
+    fn overlapping_listings_merge_with_provenance() {
+        let shared = "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Network/dnsZones/contoso.com";
+        let first = vec![
+            json!({ "id": shared, "name": "contoso.com" }),
+            json!({ "id": "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Web/sites/app1" }),
+        ];
+        let second = vec![
+            json!({ "id": shared.to_uppercase(), "name": "contoso.com" }),
+            json!({ "id": "/subscriptions/s2/resourceGroups/rg/providers/Microsoft.Web/sites/app2" }),
+            json!({ "name": "no-id" }),
+        ];
+        let merged = merge_listings(vec![first, second]);
+
+        assert_eq!(merged.duplicates_removed, 1);
+        assert_eq!(merged.resources.len(), 4);
+        assert_eq!(merged.resources[0]["id"], shared);
+        assert_eq!(merged.resources[0]["sourceListings"], json!([0, 1]));
+        assert_eq!(merged.resources[0]["subscriptionId"], "s1");
+        assert_eq!(merged.resources[2]["subscriptionId"], "s2");
+        assert_eq!(merged.resources[2]["sourceListings"], json!([1]));
+        assert_eq!(merged.resources[3]["name"], "no-id");
+    }
+
+    #[test]
+    fn standard_resource_id_round_trips() {
+        let id = "/subscriptions/s1/resourceGroups/rg-web/providers/Microsoft.Web/sites/app1";
+        let parts = parse_id(id).unwrap();
+        assert_eq!(parts.subscription_id, "s1");
+        assert_eq!(parts.resource_group.as_deref(), Some("rg-web"));
+        assert_eq!(parts.provider.as_deref(), Some("Microsoft.Web"));
+        assert_eq!(parts.resource_type.as_deref(), Some("Microsoft.Web/sites"));
+        assert_eq!(parts.name.as_deref(), Some("app1"));
+        assert_eq!(build_id(&parts).unwrap(), id);
+    }
+
+    #[test]
+    fn nested_child_resource_id_keeps_every_level() {
+        let id = "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Sql/servers/sql1/databases/db1";
+        let parts = parse_id(id).unwrap();
+        assert_eq!(parts.resource_type.as_deref(), Some("Microsoft.Sql/servers/databases"));
+        assert_eq!(parts.names, ["sql1", "db1"]);
+        assert_eq!(parts.name.as_deref(), Some("db1"));
+        assert_eq!(build_id(&parts).unwrap(), id);
+
+        let subscription_level = parse_id("/subscriptions/s1/providers/Microsoft.Security/pricings/default").unwrap();
+        assert_eq!(subscription_level.resource_group, None);
+        assert_eq!(subscription_level.name.as_deref(), Some("default"));
+    }
+
+    #[test]
+    fn malformed_resource_ids_are_rejected() {
+        for id in [
+            "subscriptions/s1/resourceGroups/rg",
+            "/tenants/t1/providers/Microsoft.Web/sites/app1",
+            "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Sql/servers/sql1/databases",
+            "/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Web",
+            "/subscriptions//resourceGroups/rg",
+            "/subscriptions/s1/resourceGroups/rg/sites/app1",
+        ] {
+            assert!(parse_id(id).is_err(), "{} accepted", id);
+        }
+
+        let mut parts = parse_id("/subscriptions/s1/resourceGroups/rg/providers/Microsoft.Sql/servers/sql1").unwrap();
+        parts.names.push("db1".to_string());
+        assert!(build_id(&parts).is_err());
+    }
+}
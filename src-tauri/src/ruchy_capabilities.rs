@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::help_text::{help_output, parse_help, HelpSubcommand};
+use crate::preferences::PreferencesState;
+
+/// What the installed ruchy binary says it supports, so the UI can hide features it lacks
+#[derive(Debug, Clone, Serialize)]
+pub struct RuchyCapabilities {
+    pub path: String,
+    pub subcommands: Vec<HelpSubcommand>,
+    // Long and short flags of `ruchy` itself, e.g. `--eval`
+    pub flags: Vec<String>,
+    // Flags of `ruchy repl`
+    pub repl_flags: Vec<String>,
+    // False when the help output didn't look like anything we could read; the lists are then
+    // empty and callers should assume only `ruchy repl`
+    pub parsed: bool,
+}
+
+/// Capabilities per resolved binary path, so switching ruchy installs re-probes. Kept in
+/// managed state.
+#[derive(Default)]
+pub struct RuchyCapabilitiesCache {
+    by_path: Mutex<HashMap<String, RuchyCapabilities>>,
+}
+
+async fn probe(path: &str) -> RuchyCapabilities {
+    let top = parse_help(&help_output(path, &["--help"]).await);
+    let repl = parse_help(&help_output(path, &["repl", "--help"]).await);
+    RuchyCapabilities {
+        path: path.to_string(),
+        parsed: !top.subcommands.is_empty() || !top.flags.is_empty(),
+        subcommands: top.subcommands,
+        flags: top.flags,
+        repl_flags: repl.flags,
+    }
+}
+
+// Subcommands and flags the resolved ruchy supports, from `ruchy --help` and `ruchy repl --help`
+#[tauri::command]
+pub async fn ruchy_capabilities(
+    prefs: tauri::State<'_, PreferencesState>,
+    cache: tauri::State<'_, RuchyCapabilitiesCache>,
+) -> Result<RuchyCapabilities, String> {
+    let tool_info = crate::locate_tool("ruchy".to_string(), &prefs.get()).await?;
+    let path = tool_info
+        .path
+        .ok_or_else(|| tool_info.error.unwrap_or_else(|| "Ruchy not available".to_string()))?;
+
+    if let Some(capabilities) = cache.by_path.lock().unwrap().get(&path) {
+        return Ok(capabilities.clone());
+    }
+    let capabilities = probe(&path).await;
+    cache.by_path.lock().unwrap().insert(path, capabilities.clone());
+    Ok(capabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::help_text::HelpText;
+
+    #[test]
+    fn clap_help_output_is_parsed() {
+        let help = "\
+Ruchy programming language
+
+Usage: ruchy [OPTIONS] [COMMAND]
+
+Commands:
+  repl    Start the interactive REPL
+  run     Compile and run a file
+  check   Type check a file
+  help    Print this message or the help of the given subcommand(s)
+
+Options:
+  -e, --eval <EXPR>      Evaluate an expression
+      --format=<FORMAT>  Output format [default: text]
+  -h, --help             Print help
+  -V, --version          Print version
+";
+        let parsed = parse_help(help);
+        let names: Vec<&str> = parsed.subcommands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["repl", "run", "check"]);
+        assert_eq!(parsed.subcommands[1].description, "Compile and run a file");
+        assert_eq!(parsed.flags, ["-e", "--eval", "--format", "-h", "--help", "-V", "--version"]);
+    }
+
+    #[test]
+    fn unparseable_help_yields_nothing() {
+        assert_eq!(parse_help("ruchy 0.1.0\nsomething went wrong"), HelpText::default());
+        assert_eq!(parse_help(""), HelpText::default());
+    }
+}
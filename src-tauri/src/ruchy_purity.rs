@@ -0,0 +1,88 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+// Functions and methods known not to touch anything outside the snippet. Anything else counts
+// as a possible side effect.
+const PURE_CALLS: [&str; 40] = [
+    "abs", "all", "any", "ceil", "chars", "concat", "contains", "count", "ends_with", "enumerate", "Err", "filter",
+    "first", "floor", "fold", "is_empty", "join", "keys", "last", "len", "map", "max", "min", "None", "Ok", "pow",
+    "range", "reduce", "replace", "reverse", "round", "Some", "split", "sqrt", "starts_with", "sum", "to_lower",
+    "to_string", "to_upper", "trim",
+];
+
+// Words followed by `(` that aren't calls
+const KEYWORDS: [&str; 10] = ["if", "else", "while", "for", "in", "match", "return", "let", "fun", "fn"];
+
+/// Result of `ruchy_is_pure`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PurityCheck {
+    pub pure: bool,
+    // Calls that may have side effects, in order of first appearance
+    pub impure_calls: Vec<String>,
+}
+
+fn call_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // `name(`, `path::name(`, `.method(` and `macro!(`
+    PATTERN.get_or_init(|| Regex::new(r"([A-Za-z_][A-Za-z0-9_]*(?:::[A-Za-z_][A-Za-z0-9_]*)*!?)\s*\(").unwrap())
+}
+
+fn definition_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(?:fun|fn)\s+([A-Za-z_][A-Za-z0-9_]*)").unwrap())
+}
+
+// Conservative scan: every call has to be a known pure builtin or a function the snippet defines
+// itself (whose body is scanned too). REPL commands like `:load` are never pure.
+fn check_purity(snippet: &str) -> PurityCheck {
+    let defined: Vec<&str> = definition_pattern()
+        .captures_iter(snippet)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect();
+    let mut impure_calls: Vec<String> = Vec::new();
+    if let Some(command) = snippet.lines().map(str::trim).find(|line| line.starts_with(':')) {
+        impure_calls.push(command.split_whitespace().next().unwrap_or(command).to_string());
+    }
+    for captures in call_pattern().captures_iter(snippet) {
+        let name = captures.get(1).unwrap().as_str();
+        let known = KEYWORDS.contains(&name) || PURE_CALLS.contains(&name) || defined.contains(&name);
+        if !known && !impure_calls.iter().any(|call| call == name) {
+            impure_calls.push(name.to_string());
+        }
+    }
+    PurityCheck {
+        pure: impure_calls.is_empty(),
+        impure_calls,
+    }
+}
+
+// Whether a snippet looks free of side effects (I/O, network, printing, process or file access),
+// so a safe preview can run it without asking. This is a static scan, not an analysis by
+// ruchy: any call it doesn't know is reported in `impure_calls` and makes the snippet impure.
+#[tauri::command]
+pub fn ruchy_is_pure(snippet: String) -> PurityCheck {
+    check_purity(&snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_and_own_functions_are_pure() {
+        let check = check_purity("let x = 2 + 3 * 4\nfun square(n) { n * n }\nsquare(x) + max(x, 10).pow(2)");
+        assert_eq!(check, PurityCheck { pure: true, impure_calls: Vec::new() });
+    }
+
+    #[test]
+    fn io_calls_are_reported() {
+        let snippet = "let data = fs::read_file(\"/etc/passwd\")\nprintln(data)\nhttp_get(url).len()\nprintln(1)";
+        let check = check_purity(snippet);
+        assert!(!check.pure);
+        assert_eq!(check.impure_calls, ["fs::read_file", "println", "http_get"]);
+
+        assert_eq!(check_purity(":load script.ruchy").impure_calls, [":load"]);
+    }
+}
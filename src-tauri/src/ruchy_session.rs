@@ -0,0 +1,665 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::Mutex;
+
+use crate::preferences::PreferencesState;
+use crate::RuchyResult;
+
+const PROMPT: &str = "ruchy>";
+// Shown instead of PROMPT while the REPL waits for the rest of an unfinished expression
+const CONTINUATION_PROMPT: &str = "...";
+// How long output has to stall at a continuation prompt before the REPL counts as waiting
+const CONTINUATION_SETTLE: Duration = Duration::from_millis(200);
+const INCOMPLETE_INPUT_ERROR: &str =
+    "Incomplete input: the expression isn't finished (check for an unclosed bracket, brace or string)";
+// Printed after every eval so the reader knows where that eval's output ends
+const EVAL_SENTINEL: &str = "__skanyxx_eval_done__";
+// Oldest transcript entries are dropped past this many
+const MAX_TRANSCRIPT_ENTRIES: usize = 1000;
+
+/// A long-lived `ruchy repl` child, so definitions persist between evals
+pub struct RuchySession {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    // Filled by a background task so a chatty stderr can't block the REPL
+    stderr: Arc<std::sync::Mutex<String>>,
+    started: Instant,
+}
+
+impl RuchySession {
+    pub fn spawn(program: &str, args: &[&str]) -> Result<Self, String> {
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to spawn ruchy: {}", e))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let stderr = Arc::new(std::sync::Mutex::new(String::new()));
+        let mut stderr_pipe = child.stderr.take().unwrap();
+        let stderr_buffer = stderr.clone();
+        tokio::spawn(async move {
+            let mut chunk = [0u8; 4096];
+            while let Ok(read) = stderr_pipe.read(&mut chunk).await {
+                if read == 0 {
+                    break;
+                }
+                stderr_buffer
+                    .lock()
+                    .unwrap()
+                    .push_str(&String::from_utf8_lossy(&chunk[..read]));
+            }
+        });
+
+        Ok(RuchySession {
+            child,
+            stdin,
+            stdout,
+            stderr,
+            started: Instant::now(),
+        })
+    }
+
+    async fn send(&mut self, input: &str) -> Result<(), String> {
+        self.stdin
+            .write_all(input.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to ruchy stdin: {}", e))?;
+        self.stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write to ruchy stdin: {}", e))
+    }
+
+    // Reads one line into `line`. Since prompts aren't newline-terminated, a REPL left waiting at
+    // its continuation prompt would block a plain read_line forever; that's reported instead.
+    async fn next_line(&mut self, line: &mut Vec<u8>) -> Result<ReplLine, String> {
+        loop {
+            let available = match tokio::time::timeout(CONTINUATION_SETTLE, self.stdout.fill_buf()).await {
+                Ok(read) => read.map_err(|e| format!("Failed to read ruchy output: {}", e))?,
+                Err(_) if is_continuation_prompt(&String::from_utf8_lossy(line)) => return Ok(ReplLine::Continuation),
+                Err(_) => continue,
+            };
+            if available.is_empty() {
+                return Ok(ReplLine::Eof);
+            }
+            let (taken, complete) = match available.iter().position(|&b| b == b'\n') {
+                Some(end) => (end + 1, true),
+                None => (available.len(), false),
+            };
+            line.extend_from_slice(&available[..taken]);
+            self.stdout.consume(taken);
+            if complete {
+                return Ok(ReplLine::Line);
+            }
+        }
+    }
+
+    // Collects output lines into `stdout` until the sentinel. Returns false if the REPL stopped
+    // at its continuation prompt instead, having taken the sentinel as more of the input.
+    async fn read_until_sentinel(&mut self, stdout: &mut String) -> Result<bool, String> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match self.next_line(&mut line).await? {
+                ReplLine::Line => {}
+                ReplLine::Continuation => return Ok(false),
+                ReplLine::Eof => return Err("Ruchy session ended unexpectedly".to_string()),
+            }
+            let line = String::from_utf8_lossy(&line);
+            // The REPL doesn't end its prompt with a newline, so output lands after it
+            let content = strip_prompts(line.trim_end());
+            if content == EVAL_SENTINEL {
+                return Ok(true);
+            }
+            stdout.push_str(content);
+            stdout.push('\n');
+        }
+    }
+
+    async fn eval(&mut self, code: &str) -> Result<RuchyResult, String> {
+        let sentinel = format!("println(\"{}\")\n", EVAL_SENTINEL);
+        self.send(&format!("{}\n{}", code, sentinel)).await?;
+
+        let mut stdout = String::new();
+        let complete = self.read_until_sentinel(&mut stdout).await?;
+        if !complete {
+            // A blank line ends the unfinished expression so the REPL reports it and goes back
+            // to its main prompt; a fresh sentinel then shows the session is in sync again
+            self.send(&format!("\n{}", sentinel)).await?;
+            if !self.read_until_sentinel(&mut stdout).await? {
+                return Err(format!("{}; the Ruchy session couldn't recover", INCOMPLETE_INPUT_ERROR));
+            }
+        }
+
+        let stderr = std::mem::take(&mut *self.stderr.lock().unwrap());
+        let mut result = crate::ruchy_command_output(&stdout, &stderr);
+        if !complete {
+            result.output.success = false;
+            result.value = None;
+            result.error = Some(INCOMPLETE_INPUT_ERROR.to_string());
+        }
+        Ok(result)
+    }
+
+    async fn stop(mut self) {
+        let _ = self.stdin.write_all(b":quit\n").await;
+        let _ = self.child.kill().await;
+    }
+}
+
+enum ReplLine {
+    Line,
+    Continuation,
+    Eof,
+}
+
+fn strip_prompts(mut content: &str) -> &str {
+    loop {
+        let rest = content.strip_prefix(PROMPT).or_else(|| {
+            content
+                .strip_prefix(CONTINUATION_PROMPT)
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+        });
+        match rest {
+            Some(rest) => content = rest.trim_start(),
+            None => return content,
+        }
+    }
+}
+
+// Whether an unterminated line is nothing but prompts, ending in a continuation prompt
+fn is_continuation_prompt(partial: &str) -> bool {
+    let trimmed = partial.trim_end();
+    trimmed.ends_with(CONTINUATION_PROMPT) && strip_prompts(trimmed).is_empty()
+}
+
+/// One eval in the session transcript: what was sent and what came back
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptEntry {
+    pub input: String,
+    pub output: String,
+    pub value: Option<String>,
+    // The REPL's error, or why the eval couldn't run at all
+    pub error: Option<String>,
+    pub success: bool,
+}
+
+impl TranscriptEntry {
+    fn new(input: &str, result: &Result<RuchyResult, String>) -> Self {
+        match result {
+            Ok(result) => TranscriptEntry {
+                input: input.to_string(),
+                output: result.output.stdout.clone(),
+                value: result.value.clone(),
+                error: result.error.clone(),
+                success: result.output.success,
+            },
+            Err(error) => TranscriptEntry {
+                input: input.to_string(),
+                output: String::new(),
+                value: None,
+                error: Some(error.clone()),
+                success: false,
+            },
+        }
+    }
+}
+
+/// Health of the persistent session, from `ruchy_session_status`
+#[derive(Debug, Default, Serialize)]
+pub struct SessionStatus {
+    // Whether a session has been started and not stopped
+    pub exists: bool,
+    // False when the session's REPL process has exited on its own, e.g. after a crash
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub uptime_ms: u64,
+    // How the process ended, when it has
+    pub exit_status: Option<String>,
+}
+
+/// Managed state holding the persistent session, if one is running
+#[derive(Default)]
+pub struct RuchySessionState {
+    session: Arc<Mutex<Option<RuchySession>>>,
+    // Bumped on every eval; an idle timer only fires if nothing happened since it was armed
+    activity: Arc<AtomicU64>,
+    // Inputs and results of recorded evals, oldest first. Kept across session restarts until
+    // cleared.
+    transcript: std::sync::Mutex<VecDeque<TranscriptEntry>>,
+}
+
+impl RuchySessionState {
+    pub async fn start_with(&self, spawn: impl FnOnce() -> Result<RuchySession, String>) -> Result<(), String> {
+        let mut session = self.session.lock().await;
+        if session.is_none() {
+            *session = Some(spawn()?);
+        }
+        Ok(())
+    }
+
+    pub async fn stop(&self) -> bool {
+        self.activity.fetch_add(1, Ordering::SeqCst);
+        let session = self.session.lock().await.take();
+        match session {
+            Some(session) => {
+                session.stop().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn is_running(&self) -> bool {
+        self.session.lock().await.is_some()
+    }
+
+    pub async fn status(&self) -> SessionStatus {
+        let mut session = self.session.lock().await;
+        let Some(session) = session.as_mut() else {
+            return SessionStatus::default();
+        };
+        let exited = session.child.try_wait().ok().flatten();
+        SessionStatus {
+            exists: true,
+            running: exited.is_none(),
+            pid: session.child.id(),
+            uptime_ms: session.started.elapsed().as_millis() as u64,
+            exit_status: exited.map(|status| status.to_string()),
+        }
+    }
+
+    pub fn transcript(&self) -> Vec<TranscriptEntry> {
+        self.transcript.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear_transcript(&self) {
+        self.transcript.lock().unwrap().clear();
+    }
+
+    fn record(&self, entry: TranscriptEntry) {
+        let mut transcript = self.transcript.lock().unwrap();
+        if transcript.len() == MAX_TRANSCRIPT_ENTRIES {
+            transcript.pop_front();
+        }
+        transcript.push_back(entry);
+    }
+
+    /// Evaluates `code`, then arms an idle timer: if no other eval happens within
+    /// `idle_timeout` the session is stopped and `on_idle` runs. A zero timeout disables it.
+    /// With `record` set the eval is added to the transcript.
+    pub async fn eval<F>(
+        &self,
+        code: &str,
+        idle_timeout: Duration,
+        record: bool,
+        on_idle: F,
+    ) -> Result<RuchyResult, String>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut guard = self.session.lock().await;
+        let session = guard
+            .as_mut()
+            .ok_or_else(|| "No Ruchy session is running".to_string())?;
+        let result = session.eval(code).await;
+        if record {
+            self.record(TranscriptEntry::new(code, &result));
+        }
+        if result.is_err() {
+            // A session that failed mid-eval can't be trusted to be in sync any more
+            if let Some(session) = guard.take() {
+                session.stop().await;
+            }
+        }
+
+        // Armed while still holding the lock so a pending timer can't slip in between
+        self.arm_idle_timer(idle_timeout, on_idle);
+        result
+    }
+
+    fn arm_idle_timer<F>(&self, idle_timeout: Duration, on_idle: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let generation = self.activity.fetch_add(1, Ordering::SeqCst) + 1;
+        if idle_timeout.is_zero() {
+            return;
+        }
+        let session = self.session.clone();
+        let activity = self.activity.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(idle_timeout).await;
+            let mut guard = session.lock().await;
+            if activity.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Some(session) = guard.take() {
+                drop(guard);
+                session.stop().await;
+                on_idle();
+            }
+        });
+    }
+}
+
+async fn start_session(prefs: &PreferencesState, state: &RuchySessionState) -> Result<(), String> {
+    let tool_info = crate::locate_tool("ruchy".to_string(), &prefs.get()).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| "Ruchy not available".to_string()));
+    }
+    let ruchy_path = tool_info.path.unwrap();
+    state.start_with(|| RuchySession::spawn(&ruchy_path, &["repl"])).await
+}
+
+#[tauri::command]
+pub async fn ruchy_session_start(
+    prefs: tauri::State<'_, PreferencesState>,
+    state: tauri::State<'_, RuchySessionState>,
+) -> Result<(), String> {
+    start_session(&prefs, &state).await
+}
+
+// Evaluates in the running session, starting one first if needed. `record` adds the eval to
+// the transcript; queries the UI makes on its own behalf leave it out.
+async fn eval_in_session(
+    app: AppHandle,
+    prefs: &PreferencesState,
+    state: &RuchySessionState,
+    code: &str,
+    record: bool,
+) -> Result<RuchyResult, String> {
+    if !state.is_running().await {
+        start_session(prefs, state).await?;
+    }
+    let prefs = prefs.get();
+    let idle_timeout = Duration::from_secs(prefs.ruchy_idle_timeout_secs);
+    let mut result = state
+        .eval(code, idle_timeout, record, move || {
+            let _ = app.emit("ruchy-session-closed", serde_json::json!({ "reason": "idle" }));
+        })
+        .await?;
+    result.classify_error(&prefs);
+    Ok(result)
+}
+
+// After `ruchy_idle_timeout_secs` without an eval the session is stopped and
+// `ruchy-session-closed` is emitted so the UI can offer to reopen it.
+#[tauri::command]
+pub async fn ruchy_session_eval(
+    app: AppHandle,
+    prefs: tauri::State<'_, PreferencesState>,
+    state: tauri::State<'_, RuchySessionState>,
+    code: String,
+) -> Result<RuchyResult, String> {
+    eval_in_session(app, &prefs, &state, &code, true).await
+}
+
+// Evaluates `cells` in order through `eval`. A failing cell is kept in the results with its
+// error, and with `stop_on_error` the cells after it aren't run.
+async fn run_cells<F, Fut>(cells: Vec<String>, stop_on_error: bool, mut eval: F) -> Result<Vec<RuchyResult>, String>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<RuchyResult, String>>,
+{
+    let mut results = Vec::with_capacity(cells.len());
+    for cell in cells {
+        let result = eval(cell).await?;
+        let failed = !result.output.success;
+        results.push(result);
+        if failed && stop_on_error {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+// Runs a notebook's cells in order in the persistent session, one result per cell run, each
+// recorded in the transcript. A cell that kills the session fails the whole call.
+#[tauri::command]
+pub async fn run_ruchy_cells(
+    app: AppHandle,
+    prefs: tauri::State<'_, PreferencesState>,
+    state: tauri::State<'_, RuchySessionState>,
+    cells: Vec<String>,
+    stop_on_error: Option<bool>,
+) -> Result<Vec<RuchyResult>, String> {
+    let (prefs, state) = (&*prefs, &*state);
+    run_cells(cells, stop_on_error.unwrap_or(false), move |cell| {
+        let app = app.clone();
+        async move { eval_in_session(app, prefs, state, &cell, true).await }
+    })
+    .await
+}
+
+// Pull the type out of a `:type` reply, e.g. `Type: Integer`. Versions without the command
+// answer with an error or an unknown-command message instead.
+fn parse_type_reply(result: &RuchyResult) -> Result<String, String> {
+    let reply = result.output.stdout.trim();
+    if !result.output.success || reply.is_empty() || reply.to_lowercase().contains("unknown command") {
+        return Err("The installed ruchy doesn't support :type queries".to_string());
+    }
+    let last_line = reply.lines().last().unwrap_or_default().trim();
+    let type_name = last_line.strip_prefix("Type:").unwrap_or(last_line).trim();
+    Ok(type_name.to_string())
+}
+
+// Type of `expr` as the REPL reports it, evaluated in the persistent session so earlier
+// definitions are in scope
+#[tauri::command]
+pub async fn ruchy_type_of(
+    app: AppHandle,
+    prefs: tauri::State<'_, PreferencesState>,
+    state: tauri::State<'_, RuchySessionState>,
+    expr: String,
+) -> Result<String, String> {
+    if expr.trim().is_empty() || expr.contains('\n') {
+        return Err("Expression must be a single non-empty line".to_string());
+    }
+    let output = eval_in_session(app, &prefs, &state, &format!(":type {}", expr.trim()), false).await?;
+    parse_type_reply(&output)
+}
+
+// Whether the persistent session is there and its REPL still alive, so the UI can offer a
+// restart when it died instead of failing the next eval
+#[tauri::command]
+pub async fn ruchy_session_status(state: tauri::State<'_, RuchySessionState>) -> Result<SessionStatus, String> {
+    Ok(state.status().await)
+}
+
+#[tauri::command]
+pub async fn ruchy_session_stop(state: tauri::State<'_, RuchySessionState>) -> Result<bool, String> {
+    Ok(state.stop().await)
+}
+
+// Every `ruchy_session_eval` input with its result, oldest first, for the notebook history.
+// Holds the last 1000 evals.
+#[tauri::command]
+pub fn ruchy_transcript(state: tauri::State<'_, RuchySessionState>) -> Vec<TranscriptEntry> {
+    state.transcript()
+}
+
+#[tauri::command]
+pub fn clear_ruchy_transcript(state: tauri::State<'_, RuchySessionState>) {
+    state.clear_transcript();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn type_is_parsed_from_a_canned_reply() {
+        let output = crate::ruchy_command_output("Welcome to Ruchy REPL v1.0\nType: Integer\n", "");
+        assert_eq!(parse_type_reply(&output).unwrap(), "Integer");
+
+        let unsupported = crate::ruchy_command_output("Unknown command: :type\n", "");
+        assert!(parse_type_reply(&unsupported).is_err());
+    }
+
+    // `cat` stands in for ruchy: it stays alive until stdin closes, which is all the timer needs
+    #[cfg(unix)]
+    async fn started_state() -> RuchySessionState {
+        let state = RuchySessionState::default();
+        state.start_with(|| RuchySession::spawn("cat", &[])).await.unwrap();
+        state
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn session_closes_after_inactivity() {
+        let state = started_state().await;
+        let closed = Arc::new(AtomicBool::new(false));
+        let flag = closed.clone();
+        state.arm_idle_timer(Duration::from_millis(50), move || flag.store(true, Ordering::SeqCst));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(closed.load(Ordering::SeqCst));
+        assert!(!state.is_running().await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn status_follows_the_session_process() {
+        let state = started_state().await;
+        let status = state.status().await;
+        assert!(status.exists && status.running);
+        assert!(status.pid.is_some());
+        assert_eq!(status.exit_status, None);
+
+        assert!(state.stop().await);
+        let status = state.status().await;
+        assert!(!status.exists && !status.running);
+        assert_eq!(status.pid, None);
+
+        // A REPL that dies on its own is still listed, but not running
+        state.start_with(|| RuchySession::spawn("true", &[])).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let status = state.status().await;
+        assert!(status.exists);
+        assert!(!status.running);
+        assert!(status.exit_status.is_some());
+        assert!(state.stop().await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn activity_resets_the_idle_timer() {
+        let state = started_state().await;
+        let closed = Arc::new(AtomicBool::new(false));
+        let flag = closed.clone();
+        state.arm_idle_timer(Duration::from_millis(50), move || flag.store(true, Ordering::SeqCst));
+        // A later eval re-arms the timer, so the first one must not fire
+        state.arm_idle_timer(Duration::ZERO, || {});
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!closed.load(Ordering::SeqCst));
+        assert!(state.is_running().await);
+        assert!(state.stop().await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn recorded_evals_are_kept_in_the_transcript() {
+        // Echoes each input back as its value, and the sentinel for its println
+        let script = "while read -r line; do case \"$line\" in println*) echo __skanyxx_eval_done__;; \
+                      *) echo \"= $line\";; esac; done";
+        let state = RuchySessionState::default();
+        state.start_with(|| RuchySession::spawn("sh", &["-c", script])).await.unwrap();
+
+        state.eval("let x = 1", Duration::ZERO, true, || {}).await.unwrap();
+        state.eval(":type x", Duration::ZERO, false, || {}).await.unwrap();
+        state.eval("x + 1", Duration::ZERO, true, || {}).await.unwrap();
+
+        let transcript = state.transcript();
+        assert_eq!(transcript.len(), 2);
+        assert_eq!(transcript[0].input, "let x = 1");
+        assert_eq!(transcript[0].value.as_deref(), Some("= let x = 1"));
+        assert_eq!(transcript[1].input, "x + 1");
+        assert_eq!(transcript[1].output, "= x + 1");
+        assert!(transcript.iter().all(|entry| entry.success));
+
+        state.clear_transcript();
+        assert!(state.transcript().is_empty());
+        assert!(state.stop().await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_cell_stops_the_run_only_when_asked() {
+        // Fails cells mentioning `fail`, echoes the rest back as their value
+        let script = "while read -r line; do case \"$line\" in println*) echo __skanyxx_eval_done__;; \
+                      *fail*) echo \"Error: Runtime error: $line\";; *) echo \"= $line\";; esac; done";
+        let state = RuchySessionState::default();
+        state.start_with(|| RuchySession::spawn("sh", &["-c", script])).await.unwrap();
+        let cells = || vec!["let a = 1".to_string(), "fail()".to_string(), "a + 1".to_string()];
+        let eval = |cell: String| {
+            let state = &state;
+            async move { state.eval(&cell, Duration::ZERO, true, || {}).await }
+        };
+
+        let results = run_cells(cells(), true, eval).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].output.success);
+        assert!(!results[1].output.success);
+        assert_eq!(results[1].error.as_deref(), Some("Runtime error: fail()"));
+
+        let results = run_cells(cells(), false, eval).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(!results[1].output.success);
+        assert_eq!(results[2].value.as_deref(), Some("= a + 1"));
+        assert_eq!(state.transcript().len(), 5);
+        assert!(state.stop().await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn incomplete_input_is_reported_and_the_session_recovers() {
+        // A line ending in `{` leaves it at a `... ` prompt until a blank line, like the REPL
+        let script = "pending=; while IFS= read -r line; do \
+                      if [ -n \"$pending\" ]; then \
+                      if [ -z \"$line\" ]; then pending=; echo 'Error: Unexpected end of input'; \
+                      else printf '... '; fi; continue; fi; \
+                      case \"$line\" in *'{') pending=1; printf '... ';; \
+                      println*) echo __skanyxx_eval_done__;; *) echo \"= $line\";; esac; done";
+        let state = RuchySessionState::default();
+        state.start_with(|| RuchySession::spawn("sh", &["-c", script])).await.unwrap();
+
+        let result = state.eval("fun f() {", Duration::ZERO, false, || {}).await.unwrap();
+        assert!(!result.output.success);
+        assert_eq!(result.error.as_deref(), Some(INCOMPLETE_INPUT_ERROR));
+        assert_eq!(result.value, None);
+        assert!(result.output.stdout.contains("Unexpected end of input"));
+
+        let result = state.eval("1 + 1", Duration::ZERO, false, || {}).await.unwrap();
+        assert!(result.output.success);
+        assert_eq!(result.value.as_deref(), Some("= 1 + 1"));
+        assert!(state.stop().await);
+    }
+
+    #[test]
+    fn continuation_prompt_is_recognised() {
+        assert!(is_continuation_prompt("... ... "));
+        assert!(is_continuation_prompt("ruchy> ..."));
+        assert!(!is_continuation_prompt("ruchy> "));
+        assert!(!is_continuation_prompt("loading..."));
+        assert_eq!(strip_prompts("... ... Error: x"), "Error: x");
+    }
+}
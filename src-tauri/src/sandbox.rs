@@ -0,0 +1,81 @@
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+/// Whether the app may start other programs, checked once up front so a sandbox shows up as
+/// one clear message instead of every tool command failing on its own
+#[derive(Debug, Serialize)]
+pub struct SandboxDiagnostics {
+    pub execution_allowed: bool,
+    // Why the probe spawn failed
+    pub error: Option<String>,
+    // macOS sets APP_SANDBOX_CONTAINER_ID inside the App Sandbox; None elsewhere
+    pub sandboxed: Option<bool>,
+    // The app's signed entitlements plist, when codesign can read it (macOS only)
+    pub entitlements: Option<String>,
+}
+
+#[cfg(windows)]
+const PROBE: (&str, &[&str]) = ("cmd", &["/C", "echo", "ok"]);
+#[cfg(not(windows))]
+const PROBE: (&str, &[&str]) = ("/bin/echo", &["ok"]);
+
+// Runs a trivial program and checks it printed what it was asked to
+fn probe_spawn(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    if !output.status.success() || String::from_utf8_lossy(&output.stdout).trim().is_empty() {
+        return Err(format!("{} ran but exited with {} and no output", program, output.status));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn entitlements() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    let output = Command::new("codesign")
+        .args(["-d", "--entitlements", ":-"])
+        .arg(exe)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let plist = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !plist.is_empty()).then_some(plist)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn entitlements() -> Option<String> {
+    None
+}
+
+#[tauri::command]
+pub fn sandbox_diagnostics() -> SandboxDiagnostics {
+    let (program, args) = PROBE;
+    let probe = probe_spawn(program, args);
+    let sandboxed = cfg!(target_os = "macos").then(|| std::env::var_os("APP_SANDBOX_CONTAINER_ID").is_some());
+    SandboxDiagnostics {
+        execution_allowed: probe.is_ok(),
+        error: probe.err(),
+        sandboxed,
+        entitlements: entitlements(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn echo_spawn_reports_execution_allowed() {
+        let diagnostics = sandbox_diagnostics();
+        assert!(diagnostics.execution_allowed, "{:?}", diagnostics.error);
+        assert_eq!(diagnostics.error, None);
+
+        assert!(probe_spawn("/nonexistent/skanyxx-probe", &[]).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use jsonschema::JSONSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+/// One way the instance breaks the schema
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SchemaError {
+    // JSON Pointer to the offending value; empty for the document root
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchemaValidation {
+    pub valid: bool,
+    pub errors: Vec<SchemaError>,
+}
+
+fn validate(instance: &Value, schema: &Value) -> Result<SchemaValidation, String> {
+    let compiled = JSONSchema::compile(schema).map_err(|e| format!("Invalid schema: {}", e))?;
+    let errors: Vec<SchemaError> = match compiled.validate(instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|error| SchemaError {
+                path: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect(),
+    };
+    Ok(SchemaValidation {
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+// Checks finder output (or any JSON) against a JSON Schema before it goes to downstream
+// tooling, listing every violation rather than stopping at the first
+#[tauri::command]
+pub fn validate_json_schema(instance: Value, schema: Value) -> Result<SchemaValidation, String> {
+    validate(&instance, &schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn resource_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "location"],
+            "properties": {
+                "id": { "type": "string" },
+                "location": { "type": "string" },
+                "tags": { "type": "object", "additionalProperties": { "type": "string" } }
+            }
+        })
+    }
+
+    #[test]
+    fn passing_and_failing_instances() {
+        let valid = json!({ "id": "/subscriptions/s/resourceGroups/rg", "location": "westeurope" });
+        let result = validate(&valid, &resource_schema()).unwrap();
+        assert!(result.valid);
+        assert!(result.errors.is_empty());
+
+        let invalid = json!({ "id": 42, "tags": { "env": 1 } });
+        let result = validate(&invalid, &resource_schema()).unwrap();
+        assert!(!result.valid);
+        let paths: Vec<&str> = result.errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&""), "missing `location` is reported at the root");
+        assert!(paths.contains(&"/id"));
+        assert!(paths.contains(&"/tags/env"));
+
+        assert!(validate(&valid, &json!({ "type": "no-such-type" })).is_err());
+    }
+}
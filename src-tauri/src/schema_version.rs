@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Bumped whenever any entry in TYPE_VERSIONS is
+pub const SCHEMA_VERSION: u32 = 5;
+
+// Serialized types the frontend depends on and the version of each one's shape. Bump a type's
+// version and SCHEMA_VERSION whenever fields are added, removed or renamed.
+const TYPE_VERSIONS: [(&str, u32); 4] = [
+    ("AzOutput", 3),
+    ("CommandOutput", 4),
+    ("RuchyResult", 4),
+    ("ToolInfo", 1),
+];
+
+#[derive(Debug, Serialize)]
+pub struct SchemaVersions {
+    pub schema_version: u32,
+    pub types: BTreeMap<String, u32>,
+}
+
+// The backend's payload schema versions, so the frontend can detect an older or newer backend
+// and degrade gracefully. `CommandOutput` and `ToolInfo` (and so `RuchyResult`) also carry
+// `schema_version` themselves.
+#[tauri::command]
+pub fn schema_version() -> SchemaVersions {
+    SchemaVersions {
+        schema_version: SCHEMA_VERSION,
+        types: TYPE_VERSIONS.iter().map(|(name, version)| (name.to_string(), *version)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn field_names(value: &impl Serialize) -> Vec<String> {
+        let Value::Object(fields) = serde_json::to_value(value).unwrap() else {
+            panic!("not serialized as an object");
+        };
+        let mut names: Vec<String> = fields.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    // Each type's fields as of its reported version. When this fails, the struct changed shape:
+    // bump its entry in TYPE_VERSIONS and SCHEMA_VERSION, then update the expectation here.
+    #[test]
+    fn reported_versions_match_the_current_structs() {
+        let expected: [(&str, u32, &[&str]); 4] = [
+            ("AzOutput", 3, &["bytes_written", "output_path", "result", "stdout_warning", "warning"]),
+            (
+                "CommandOutput",
+                4,
+                &[
+                    "compressed",
+                    "crash",
+                    "errors",
+                    "no_results",
+                    "output_files",
+                    "schema_version",
+                    "stderr",
+                    "stdout",
+                    "success",
+                    "warnings",
+                ],
+            ),
+            (
+                "RuchyResult",
+                4,
+                &[
+                    "compressed",
+                    "crash",
+                    "error",
+                    "error_kind",
+                    "errors",
+                    "no_results",
+                    "output_files",
+                    "printed_output",
+                    "schema_version",
+                    "stderr",
+                    "stdin_delivery",
+                    "stdout",
+                    "success",
+                    "value",
+                    "warnings",
+                ],
+            ),
+            ("ToolInfo", 1, &["available", "error", "name", "path", "schema_version"]),
+        ];
+
+        let ruchy = crate::ruchy_command_output("42\n", "");
+        let tool = crate::ToolInfo {
+            name: "az".to_string(),
+            available: false,
+            path: None,
+            error: None,
+            schema_version: SCHEMA_VERSION,
+        };
+        let az = crate::az::AzOutput {
+            result: Value::Null,
+            bytes_written: None,
+            output_path: None,
+            warning: None,
+            stdout_warning: None,
+        };
+        let actual = [
+            ("AzOutput", field_names(&az)),
+            ("CommandOutput", field_names(&ruchy.output)),
+            ("RuchyResult", field_names(&ruchy)),
+            ("ToolInfo", field_names(&tool)),
+        ];
+
+        let versions = schema_version();
+        assert_eq!(versions.schema_version, SCHEMA_VERSION);
+        assert_eq!(versions.types.len(), expected.len());
+        for ((name, version, fields), (actual_name, actual_fields)) in expected.iter().zip(&actual) {
+            assert_eq!(name, actual_name);
+            assert_eq!(versions.types[*name], *version, "{} version", name);
+            assert_eq!(actual_fields, fields, "{} fields changed; bump its schema version", name);
+        }
+        assert_eq!(ruchy.output.schema_version, SCHEMA_VERSION);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::preferences::PreferencesState;
+
+// Characters that never need quoting in either shell
+fn is_plain(arg: &str) -> bool {
+    !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:,@+%".contains(c))
+}
+
+// POSIX sh: single quotes keep everything literal; an embedded ' closes the quote, adds an
+// escaped ' and reopens it
+fn posix_quote(arg: &str) -> String {
+    if is_plain(arg) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+// PowerShell: single-quoted strings are literal and a quote is escaped by doubling it.
+// PowerShell also treats the typographic single quotes as quote characters.
+fn powershell_quote(arg: &str) -> String {
+    if is_plain(arg) {
+        return arg.to_string();
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('\'');
+    for c in arg.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+fn posix_command(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(posix_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn powershell_command(program: &str, args: &[String]) -> String {
+    let program = powershell_quote(program);
+    // A quoted program is just a string to PowerShell; the call operator runs it
+    let mut parts = vec![if program.starts_with('\'') {
+        format!("& {}", program)
+    } else {
+        program
+    }];
+    parts.extend(args.iter().map(String::as_str).map(powershell_quote));
+    parts.join(" ")
+}
+
+// A copy-pasteable command line for the current platform: POSIX sh on Unix, PowerShell on
+// Windows. The tool is resolved the same way the run commands resolve it, falling back to
+// its bare name when it isn't found.
+#[tauri::command]
+pub async fn shell_quote_command(
+    prefs: tauri::State<'_, PreferencesState>,
+    tool: String,
+    args: Vec<String>,
+) -> Result<String, String> {
+    let tool_info = crate::locate_tool(tool.clone(), &prefs.get()).await?;
+    let program = tool_info.path.unwrap_or(tool);
+    Ok(if cfg!(target_os = "windows") {
+        powershell_command(&program, &args)
+    } else {
+        posix_command(&program, &args)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tricky_args() -> Vec<String> {
+        vec![
+            "--query".to_string(),
+            "[?name=='my rg'].id".to_string(),
+            "it's $HOME".to_string(),
+            String::new(),
+        ]
+    }
+
+    #[test]
+    fn posix_quoting_of_tricky_arguments() {
+        assert_eq!(
+            posix_command("/usr/local/bin/az", &tricky_args()),
+            r#"/usr/local/bin/az --query '[?name=='\''my rg'\''].id' 'it'\''s $HOME' ''"#
+        );
+    }
+
+    #[test]
+    fn powershell_quoting_of_tricky_arguments() {
+        assert_eq!(
+            powershell_command(r"C:\Program Files\az.cmd", &tricky_args()),
+            r#"& 'C:\Program Files\az.cmd' --query '[?name==''my rg''].id' 'it''s $HOME' ''"#
+        );
+        assert_eq!(powershell_command("az", &["group".to_string()]), "az group");
+    }
+}
@@ -0,0 +1,79 @@
+use std::io;
+use std::time::Duration;
+
+// Attempts in total, including the first
+const SPAWN_ATTEMPTS: u32 = 3;
+// Linear backoff step between attempts
+const SPAWN_BACKOFF: Duration = Duration::from_millis(50);
+
+// "Text file busy": the executable was just written and is still open for writing
+#[cfg(unix)]
+const ETXTBSY: i32 = 26;
+
+// Errors worth another try: the OS was briefly out of process slots (EAGAIN surfaces as
+// WouldBlock) or the binary is still being written. NotFound, permissions etc. won't change.
+fn is_transient(error: &io::Error) -> bool {
+    #[cfg(unix)]
+    if error.raw_os_error() == Some(ETXTBSY) {
+        return true;
+    }
+    error.kind() == io::ErrorKind::WouldBlock
+}
+
+/// Runs `spawn` (a `Command::spawn`/`output` call), retrying transient OS failures a couple
+/// of times. Once attempts run out the last error is returned unchanged.
+pub fn with_spawn_retry<T>(mut spawn: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut attempt = 1;
+    loop {
+        match spawn() {
+            Err(e) if is_transient(&e) && attempt < SPAWN_ATTEMPTS => {
+                std::thread::sleep(SPAWN_BACKOFF * attempt);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_failure_succeeds_on_retry() {
+        let mut calls = 0;
+        let result = with_spawn_retry(|| {
+            calls += 1;
+            if calls == 1 {
+                Err(io::Error::from(io::ErrorKind::WouldBlock))
+            } else {
+                Ok("spawned")
+            }
+        });
+        assert_eq!(result.unwrap(), "spawned");
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn not_found_is_not_retried() {
+        let mut calls = 0;
+        let result: io::Result<()> = with_spawn_retry(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        let mut calls = 0;
+        let result: io::Result<()> = with_spawn_retry(|| {
+            calls += 1;
+            Err(io::Error::from_raw_os_error(ETXTBSY))
+        });
+        assert_eq!(result.unwrap_err().raw_os_error(), Some(ETXTBSY));
+        assert_eq!(calls, SPAWN_ATTEMPTS);
+    }
+}
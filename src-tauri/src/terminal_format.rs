@@ -0,0 +1,130 @@
+use serde_json::Value;
+
+const RESET: &str = "\x1b[0m";
+const KEY: &str = "\x1b[36m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[33m";
+const LITERAL: &str = "\x1b[35m";
+
+// Greedy word wrap; a word longer than the whole width is split across lines
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if width == 0 || line.chars().count() <= width {
+        return vec![line.to_string()];
+    }
+
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let mut word: Vec<char> = word.chars().collect();
+        let current_len = current.chars().count();
+        if current_len > 0 && current_len + 1 + word.len() <= width {
+            current.push(' ');
+            current.extend(word);
+            continue;
+        }
+        if current_len > 0 {
+            lines.push(std::mem::take(&mut current));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        current.extend(word);
+    }
+    lines.push(current);
+    lines
+}
+
+fn wrap(output: &str, width: usize) -> String {
+    output
+        .lines()
+        .flat_map(|line| wrap_line(line, width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn paint(color: &str, text: &str) -> String {
+    format!("{}{}{}", color, text, RESET)
+}
+
+// Two-space indented JSON with ANSI colours for keys and each kind of scalar
+fn colorize(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    let close_pad = "  ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push_str("{\n");
+            for (i, (key, value)) in map.iter().enumerate() {
+                out.push_str(&pad);
+                out.push_str(&paint(KEY, &Value::String(key.clone()).to_string()));
+                out.push_str(": ");
+                colorize(value, indent + 1, out);
+                out.push_str(if i + 1 < map.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&close_pad);
+            out.push('}');
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&pad);
+                colorize(item, indent + 1, out);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&close_pad);
+            out.push(']');
+        }
+        Value::String(_) => out.push_str(&paint(STRING, &value.to_string())),
+        Value::Number(_) => out.push_str(&paint(NUMBER, &value.to_string())),
+        Value::Bool(_) | Value::Null => out.push_str(&paint(LITERAL, &value.to_string())),
+        // Empty object or array
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+fn format_output(output: &str, wrap_width: usize, colorize_json: bool) -> String {
+    if colorize_json {
+        if let Ok(value) = serde_json::from_str::<Value>(output) {
+            // Not wrapped: breaking a line could split an escape sequence
+            let mut colored = String::new();
+            colorize(&value, 0, &mut colored);
+            return colored;
+        }
+    }
+    wrap(output, wrap_width)
+}
+
+// Prepares tool output for the built-in terminal view: lines longer than `wrap_width` are
+// wrapped (0 disables), and with `colorize` output that parses as JSON is rendered as indented,
+// ANSI-coloured JSON instead
+#[tauri::command]
+pub fn format_for_terminal(output: String, wrap_width: usize, colorize: Option<bool>) -> String {
+    format_output(&output, wrap_width, colorize.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_wrap_at_word_boundaries() {
+        let wrapped = wrap("the quick brown fox jumps\nshort", 10);
+        assert_eq!(wrapped, "the quick\nbrown fox\njumps\nshort");
+    }
+
+    #[test]
+    fn words_longer_than_the_width_are_split() {
+        assert_eq!(wrap("abcdefghij xy", 4), "abcd\nefgh\nij\nxy");
+        assert_eq!(wrap("unchanged", 0), "unchanged");
+    }
+
+    #[test]
+    fn json_is_colorized_only_when_asked() {
+        let json = r#"{"name":"rg","count":2}"#;
+        assert_eq!(format_output(json, 80, false), json);
+
+        let colored = format_output(json, 80, true);
+        assert!(colored.contains(&format!("{}\"name\"{}", KEY, RESET)));
+        assert!(colored.contains(&format!("{}2{}", NUMBER, RESET)));
+        assert_eq!(format_output("not json", 80, true), "not json");
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+// Upper bound of each bucket but the last, which takes every slower run
+const BUCKET_BOUNDS: [Duration; 4] = [
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+];
+const BUCKET_LABELS: [&str; 5] = ["<100ms", "<1s", "<5s", "<30s", ">30s"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingBucket {
+    pub label: &'static str,
+    pub count: u64,
+}
+
+/// How long one tool's runs have taken this session
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolTiming {
+    pub tool: String,
+    pub runs: u64,
+    pub buckets: Vec<TimingBucket>,
+}
+
+/// Per-tool latency histograms since startup or the last reset, kept in managed state
+#[derive(Default)]
+pub struct TimingHistograms {
+    tools: Mutex<BTreeMap<String, [u64; 5]>>,
+}
+
+impl TimingHistograms {
+    pub fn record(&self, tool: &str, elapsed: Duration) {
+        let bucket = BUCKET_BOUNDS
+            .iter()
+            .position(|bound| elapsed < *bound)
+            .unwrap_or(BUCKET_BOUNDS.len());
+        let mut tools = self.tools.lock().unwrap();
+        tools.entry(tool.to_string()).or_default()[bucket] += 1;
+    }
+
+    /// Tools in name order
+    pub fn histograms(&self) -> Vec<ToolTiming> {
+        self.tools
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(tool, counts)| ToolTiming {
+                tool: tool.clone(),
+                runs: counts.iter().sum(),
+                buckets: BUCKET_LABELS
+                    .iter()
+                    .zip(counts)
+                    .map(|(label, count)| TimingBucket { label: *label, count: *count })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    pub fn reset(&self) {
+        self.tools.lock().unwrap().clear();
+    }
+}
+
+// How long each tool's runs (az, azure-resource-finder, ruchy and run_tool runs) have taken
+// this session, bucketed, so a consistently slow tool stands out
+#[tauri::command]
+pub fn tool_timing_histograms(timing: tauri::State<'_, TimingHistograms>) -> Vec<ToolTiming> {
+    timing.histograms()
+}
+
+#[tauri::command]
+pub fn reset_timing_histograms(timing: tauri::State<'_, TimingHistograms>) {
+    timing.reset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_land_in_the_bucket_for_their_duration() {
+        let timing = TimingHistograms::default();
+        for millis in [5, 99, 100, 999, 4_000, 29_999, 30_000, 120_000] {
+            timing.record("az", Duration::from_millis(millis));
+        }
+        timing.record("ruchy", Duration::from_millis(250));
+
+        let histograms = timing.histograms();
+        assert_eq!(histograms.len(), 2);
+        assert_eq!(histograms[0].tool, "az");
+        assert_eq!(histograms[0].runs, 8);
+        let counts: Vec<(&str, u64)> = histograms[0].buckets.iter().map(|bucket| (bucket.label, bucket.count)).collect();
+        assert_eq!(counts, [("<100ms", 2), ("<1s", 2), ("<5s", 1), ("<30s", 1), (">30s", 2)]);
+        assert_eq!(histograms[1].tool, "ruchy");
+        assert_eq!(histograms[1].buckets[1].count, 1);
+
+        timing.reset();
+        assert!(timing.histograms().is_empty());
+    }
+}
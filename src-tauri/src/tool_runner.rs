@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::Stdio;
+use std::sync::Mutex;
+
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use zeroize::Zeroizing;
+
+use crate::ansi_html::{AnsiStripper, AnsiToHtml};
+use crate::jobs::{wait_reporting_exit, CommandExit};
+use crate::output_limit::collect_output;
+use crate::preferences::PreferencesState;
+use crate::recording::{RecordingState, REDACTED};
+use crate::timing::TimingHistograms;
+
+// Terminal size reported to tools run under a PTY
+const PTY_SIZE: PtySize = PtySize {
+    rows: 40,
+    cols: 120,
+    pixel_width: 0,
+    pixel_height: 0,
+};
+
+/// A piece of output from a running tool, emitted as a `tool-output` event
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputChunk {
+    // "stdout", "stderr", or "pty" when both arrive interleaved through the terminal
+    pub stream: &'static str,
+    pub data: String,
+}
+
+/// How colour codes in streamed output are delivered
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    // As the tool wrote it, as `tool-output` events
+    #[default]
+    Raw,
+    // Escape sequences removed, as `tool-output` events
+    Stripped,
+    // Colours converted to styled spans, as `output-html` events
+    Html,
+}
+
+/// Output converted to HTML, emitted as an `output-html` event
+#[derive(Debug, Clone, Serialize)]
+pub struct HtmlChunk {
+    pub stream: &'static str,
+    pub html: String,
+}
+
+// Emits chunks in the requested mode, keeping per-stream converter state so escape sequences
+// split across reads still convert
+struct OutputSink {
+    app: AppHandle,
+    mode: OutputMode,
+    strippers: Mutex<HashMap<&'static str, AnsiStripper>>,
+    converters: Mutex<HashMap<&'static str, AnsiToHtml>>,
+}
+
+impl OutputSink {
+    fn new(app: AppHandle, mode: OutputMode) -> Self {
+        OutputSink {
+            app,
+            mode,
+            strippers: Mutex::default(),
+            converters: Mutex::default(),
+        }
+    }
+
+    fn emit(&self, chunk: OutputChunk) {
+        let _ = match self.mode {
+            OutputMode::Raw => self.app.emit("tool-output", chunk),
+            OutputMode::Stripped => {
+                let mut strippers = self.strippers.lock().unwrap();
+                let data = strippers.entry(chunk.stream).or_default().push(&chunk.data);
+                self.app.emit("tool-output", OutputChunk { data, ..chunk })
+            }
+            OutputMode::Html => {
+                let mut converters = self.converters.lock().unwrap();
+                let html = converters.entry(chunk.stream).or_default().push(&chunk.data);
+                self.app.emit("output-html", HtmlChunk { stream: chunk.stream, html })
+            }
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolRunResult {
+    // None when the tool was killed by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+}
+
+// Decodes output read in chunks. A multibyte character cut off at the end of one read is held
+// back until the rest of it arrives, instead of turning into U+FFFD on both sides.
+#[derive(Debug, Default)]
+struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let complete = self.pending.len() - incomplete_tail(&self.pending);
+        let tail = self.pending.split_off(complete);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = tail;
+        text
+    }
+
+    // Whatever is still held back once the stream has ended
+    fn finish(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned()
+    }
+}
+
+// Length of a character at the end of `bytes` that's missing its last bytes
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        // Skip continuation bytes to the character's first byte
+        if byte & 0xC0 != 0x80 {
+            let needed = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if needed > back { back } else { 0 };
+        }
+    }
+    0
+}
+
+async fn pump(
+    mut pipe: impl AsyncRead + Unpin,
+    stream: &'static str,
+    emit: &(dyn Fn(OutputChunk) + Sync),
+) {
+    let mut decoder = Utf8Decoder::default();
+    let mut chunk = [0u8; 8192];
+    while let Ok(read) = pipe.read(&mut chunk).await {
+        if read == 0 {
+            break;
+        }
+        let data = decoder.push(&chunk[..read]);
+        if !data.is_empty() {
+            emit(OutputChunk { stream, data });
+        }
+    }
+    let data = decoder.finish();
+    if !data.is_empty() {
+        emit(OutputChunk { stream, data });
+    }
+}
+
+async fn run_piped(
+    program: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    priority: Option<i32>,
+    emit: &(dyn Fn(OutputChunk) + Sync),
+    notify_exit: impl FnOnce(CommandExit),
+) -> Result<ToolRunResult, String> {
+    let started = std::time::Instant::now();
+    let mut command = tokio::process::Command::new(program);
+    command
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(priority) = priority {
+        crate::priority::apply_priority(command.as_std_mut(), priority);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    // Waited for alongside the pumps so the exit is reported even while something the tool
+    // started in the background keeps its pipes open
+    let (_, _, status) = tokio::join!(
+        pump(stdout, "stdout", emit),
+        pump(stderr, "stderr", emit),
+        wait_reporting_exit(&mut child, started, notify_exit)
+    );
+    let status = status.map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+    Ok(ToolRunResult {
+        exit_code: status.code(),
+        success: status.success(),
+    })
+}
+
+// portable-pty is blocking, so callers run this on a blocking thread. It spawns the child itself,
+// so on Unix a `priority` goes through `nice`; Windows PTY runs keep normal priority.
+fn run_in_pty(
+    program: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    priority: Option<i32>,
+    emit: &(dyn Fn(OutputChunk) + Sync),
+) -> Result<ToolRunResult, String> {
+    let pair = native_pty_system()
+        .openpty(PTY_SIZE)
+        .map_err(|e| format!("Failed to open a pseudo-terminal: {}", e))?;
+    let (launcher, launcher_args) = match priority {
+        #[cfg(unix)]
+        Some(priority) => crate::priority::under_nice(program, args, priority),
+        _ => (program.to_string(), args.to_vec()),
+    };
+    let mut command = CommandBuilder::new(launcher);
+    command.args(launcher_args);
+    for (key, value) in env {
+        command.env(key, value);
+    }
+    let mut child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    // Only the child should hold the terminal's slave side, or reads never see the end
+    drop(pair.slave);
+
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read from the pseudo-terminal: {}", e))?;
+    let mut decoder = Utf8Decoder::default();
+    let mut chunk = [0u8; 8192];
+    // Linux reports EIO instead of EOF once the child side has closed
+    while let Ok(read) = reader.read(&mut chunk) {
+        if read == 0 {
+            break;
+        }
+        let data = decoder.push(&chunk[..read]);
+        if !data.is_empty() {
+            emit(OutputChunk { stream: "pty", data });
+        }
+    }
+    let data = decoder.finish();
+    if !data.is_empty() {
+        emit(OutputChunk { stream: "pty", data });
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+    Ok(ToolRunResult {
+        exit_code: i32::try_from(status.exit_code()).ok(),
+        success: status.success(),
+    })
+}
+
+// Runs a known tool with `args`, streaming its output as `tool-output` events while it runs.
+// With `use_pty` the tool gets a pseudo-terminal instead of pipes, for tools that only show
+// prompts, colours or progress bars on a TTY; stdout and stderr then arrive together.
+// `output_mode` picks raw, ANSI-stripped or HTML (`output-html` events) output. Without a PTY
+// the tool's exit is also sent as a `command-exit` event the moment it happens. `priority` is a
+// Unix-style niceness, e.g. 10 to keep a long run from making the UI sluggish.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn run_tool(
+    app: AppHandle,
+    prefs: tauri::State<'_, PreferencesState>,
+    timing: tauri::State<'_, TimingHistograms>,
+    tool: String,
+    args: Vec<String>,
+    use_pty: Option<bool>,
+    output_mode: Option<OutputMode>,
+    priority: Option<i32>,
+) -> Result<ToolRunResult, String> {
+    let prefs = prefs.get();
+    let tool_info = crate::locate_tool(tool.clone(), &prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| format!("{} not available", tool)));
+    }
+    let program = tool_info.path.unwrap();
+    let args = prefs.args_for(&tool, args, false);
+    let env = crate::azure_tool_env();
+    let sink = OutputSink::new(app, output_mode.unwrap_or_default());
+
+    let started = std::time::Instant::now();
+    let result = if use_pty.unwrap_or(false) {
+        tokio::task::spawn_blocking(move || run_in_pty(&program, &args, &env, priority, &|chunk| sink.emit(chunk)))
+            .await
+            .map_err(|e| format!("Tool run failed: {}", e))?
+    } else {
+        let notify_exit = |exit: CommandExit| {
+            let _ = sink.app.emit("command-exit", exit);
+        };
+        run_piped(&program, &args, &env, priority, &|chunk| sink.emit(chunk), notify_exit).await
+    };
+    timing.record(&tool, started.elapsed());
+    result
+}
+
+/// Raw output of `run_tool_bytes`, base64-encoded so binary output survives IPC
+#[derive(Debug, Serialize)]
+pub struct ToolBytesResult {
+    pub stdout_base64: String,
+    pub stderr_base64: String,
+    // Decoded sizes in bytes
+    pub stdout_len: u64,
+    pub stderr_len: u64,
+    // None when the tool was killed by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+}
+
+fn run_for_bytes(
+    program: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    max_buffered_bytes: u64,
+) -> Result<ToolBytesResult, String> {
+    use base64::Engine;
+
+    let child = std::process::Command::new(program)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    let output = collect_output(child, max_buffered_bytes).map_err(|e| e.to_string())?;
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(ToolBytesResult {
+        stdout_base64: engine.encode(&output.stdout),
+        stderr_base64: engine.encode(&output.stderr),
+        stdout_len: output.stdout.len() as u64,
+        stderr_len: output.stderr.len() as u64,
+        exit_code: output.status.code(),
+        success: output.status.success(),
+    })
+}
+
+// Like `run_tool`, for tools whose output isn't text (e.g. exporting a zip): the complete
+// stdout and stderr come back base64-encoded instead of streamed as strings, so no bytes are
+// lost to UTF-8 conversion. Output counts against `max_buffered_output_bytes`.
+#[tauri::command]
+pub async fn run_tool_bytes(
+    prefs: tauri::State<'_, PreferencesState>,
+    tool: String,
+    args: Vec<String>,
+) -> Result<ToolBytesResult, String> {
+    let prefs = prefs.get();
+    let tool_info = crate::locate_tool(tool.clone(), &prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| format!("{} not available", tool)));
+    }
+    let program = tool_info.path.unwrap();
+    let args = prefs.args_for(&tool, args, false);
+    let env = crate::azure_tool_env();
+    let max_buffered_bytes = prefs.max_buffered_output_bytes;
+    tokio::task::spawn_blocking(move || run_for_bytes(&program, &args, &env, max_buffered_bytes))
+        .await
+        .map_err(|e| format!("Tool run failed: {}", e))?
+}
+
+/// Collected text output of a tool run
+#[derive(Debug, Serialize)]
+pub struct ToolOutput {
+    pub stdout: String,
+    pub stderr: String,
+    // None when the tool was killed by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+}
+
+// Writes `secret` and a newline to the tool's stdin and closes it. The copy made for writing is
+// zeroed once sent, and any echo of the secret in the output is replaced with REDACTED.
+fn run_with_secret(
+    program: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    secret: &str,
+    max_buffered_bytes: u64,
+) -> Result<ToolOutput, String> {
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+
+    let mut input = Zeroizing::new(Vec::with_capacity(secret.len() + 1));
+    input.extend_from_slice(secret.as_bytes());
+    input.push(b'\n');
+    let mut stdin = child.stdin.take().unwrap();
+    let written = stdin.write_all(&input);
+    drop(input);
+    drop(stdin);
+    if let Err(e) = written {
+        // A tool that exits without reading stdin closes the pipe early; its output says why
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Failed to pass the secret to {}: {}", program, e));
+        }
+    }
+
+    let output = collect_output(child, max_buffered_bytes).map_err(|e| e.to_string())?;
+    let scrub = |bytes: &[u8]| {
+        let text = String::from_utf8_lossy(bytes);
+        if secret.is_empty() {
+            text.into_owned()
+        } else {
+            text.replace(secret, REDACTED)
+        }
+    };
+    Ok(ToolOutput {
+        stdout: scrub(&output.stdout),
+        stderr: scrub(&output.stderr),
+        exit_code: output.status.code(),
+        success: output.status.success(),
+    })
+}
+
+// Runs a tool that reads a password or other secret from stdin, so it never shows up in argv
+// (visible to other processes) or in the recorded command history. Only `args` are recorded,
+// and the secret is kept out of errors and the returned output.
+#[tauri::command]
+pub async fn run_tool_with_secret(
+    prefs: tauri::State<'_, PreferencesState>,
+    recording: tauri::State<'_, RecordingState>,
+    tool: String,
+    args: Vec<String>,
+    secret: String,
+) -> Result<ToolOutput, String> {
+    let secret = Zeroizing::new(secret);
+    let prefs = prefs.get();
+    let tool_info = crate::locate_tool(tool.clone(), &prefs).await?;
+    if !tool_info.available {
+        return Err(tool_info.error.unwrap_or_else(|| format!("{} not available", tool)));
+    }
+    let program = tool_info.path.unwrap();
+    let args = prefs.args_for(&tool, args, false);
+    let env = crate::azure_tool_env();
+    let max_buffered_bytes = prefs.max_buffered_output_bytes;
+    let run_args = args.clone();
+    // The secret moves to the blocking thread and is wiped there once the tool has it
+    let result = tokio::task::spawn_blocking(move || {
+        run_with_secret(&program, &run_args, &env, &secret, max_buffered_bytes)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Tool run failed: {}", e)));
+    recording.record("run_tool_with_secret", &args, &result);
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const TTY_CHECK: &str = "if [ -t 1 ]; then echo attached-to-tty; else echo no-tty; fi";
+
+    fn sh_args() -> Vec<String> {
+        vec!["-c".to_string(), TTY_CHECK.to_string()]
+    }
+
+    #[test]
+    fn pty_run_is_attached_to_a_terminal() {
+        let output = Mutex::new(String::new());
+        let result = run_in_pty("sh", &sh_args(), &HashMap::new(), None, &|chunk| {
+            output.lock().unwrap().push_str(&chunk.data);
+        })
+        .unwrap();
+        assert!(result.success);
+        assert!(output.lock().unwrap().contains("attached-to-tty"));
+    }
+
+    #[tokio::test]
+    async fn piped_run_is_not_a_terminal() {
+        let output = Mutex::new(String::new());
+        let mut exit = None;
+        let result = run_piped(
+            "sh",
+            &sh_args(),
+            &HashMap::new(),
+            None,
+            &|chunk| {
+                assert_eq!(chunk.stream, "stdout");
+                output.lock().unwrap().push_str(&chunk.data);
+            },
+            |reported| exit = Some(reported),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(exit.unwrap().exit_code, Some(0));
+        assert_eq!(output.lock().unwrap().trim(), "no-tty");
+    }
+
+    #[tokio::test]
+    async fn tool_runs_at_the_requested_niceness() {
+        // `nice` with no arguments prints its own niceness; 19 is the lowest priority, which
+        // any user may move to
+        let output = Mutex::new(String::new());
+        let collect = |chunk: OutputChunk| output.lock().unwrap().push_str(&chunk.data);
+        let result = run_piped("nice", &[], &HashMap::new(), Some(19), &collect, |_| {}).await.unwrap();
+        assert!(result.success);
+        assert_eq!(output.lock().unwrap().trim(), "19");
+
+        output.lock().unwrap().clear();
+        let result = run_in_pty("nice", &[], &HashMap::new(), Some(19), &collect).unwrap();
+        assert!(result.success);
+        assert_eq!(output.lock().unwrap().trim(), "19");
+    }
+
+    #[test]
+    fn characters_split_across_reads_are_decoded_whole() {
+        let text = "rg-café-🚀";
+        let bytes = text.as_bytes();
+        let e_acute = text.find('é').unwrap();
+        let rocket = text.find('🚀').unwrap();
+
+        let mut decoder = Utf8Decoder::default();
+        let mut decoded = decoder.push(&bytes[..e_acute + 1]);
+        assert_eq!(decoded, "rg-caf");
+        decoded += &decoder.push(&bytes[e_acute + 1..rocket + 2]);
+        decoded += &decoder.push(&bytes[rocket + 2..rocket + 3]);
+        decoded += &decoder.push(&bytes[rocket + 3..]);
+        decoded += &decoder.finish();
+        assert_eq!(decoded, text);
+
+        // Bytes that can never complete a character still come out, as U+FFFD
+        let mut decoder = Utf8Decoder::default();
+        assert_eq!(decoder.push(b"ok\xff"), "ok\u{fffd}");
+        assert_eq!(decoder.push(b"\xe2\x82"), "");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+
+    #[tokio::test]
+    async fn exit_is_reported_while_a_background_process_holds_stdout() {
+        let args = vec!["-c".to_string(), "sleep 3 & echo started; exit 3".to_string()];
+        let (exit_tx, exit_rx) = tokio::sync::oneshot::channel();
+        let run = run_piped("sh", &args, &HashMap::new(), None, &|_| {}, move |exit| {
+            let _ = exit_tx.send(exit);
+        });
+        tokio::select! {
+            _ = run => panic!("the run can't finish while the background sleep holds stdout"),
+            exit = tokio::time::timeout(std::time::Duration::from_secs(2), exit_rx) => {
+                let exit = exit.expect("exit should be reported before stdout closes").unwrap();
+                assert_eq!(exit.exit_code, Some(3));
+            }
+        }
+    }
+
+    #[test]
+    fn binary_output_round_trips_through_base64() {
+        use base64::Engine;
+
+        // Bytes that aren't valid UTF-8, including a NUL
+        let script = r"printf '\377\376\000PK\003\004'; printf 'warn' >&2";
+        let args = vec!["-c".to_string(), script.to_string()];
+        let result = run_for_bytes("sh", &args, &HashMap::new(), 1 << 20).unwrap();
+        assert!(result.success);
+        assert_eq!(result.stdout_len, 7);
+        let engine = base64::engine::general_purpose::STANDARD;
+        assert_eq!(engine.decode(&result.stdout_base64).unwrap(), [0xff, 0xfe, 0x00, b'P', b'K', 0x03, 0x04]);
+        assert_eq!(engine.decode(&result.stderr_base64).unwrap(), b"warn");
+        assert_eq!(result.stderr_len, 4);
+    }
+
+    #[test]
+    fn secret_reaches_stdin_but_not_the_recording() {
+        let script = r#"read -r secret; [ "$secret" = "hunter2" ] && echo "accepted $secret""#;
+        let args = vec!["-c".to_string(), script.to_string()];
+        let path = std::env::temp_dir().join(format!("skanyxx-secret-recording-{}.json", std::process::id()));
+        let recording = RecordingState::default();
+        recording.start(path.clone()).unwrap();
+
+        let result = run_with_secret("sh", &args, &HashMap::new(), "hunter2", 1 << 20);
+        recording.record("run_tool_with_secret", &args, &result);
+        recording.stop().unwrap();
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let result = result.unwrap();
+        assert!(result.success);
+        assert_eq!(result.stdout.trim(), format!("accepted {}", REDACTED));
+        assert!(recorded.contains("run_tool_with_secret"));
+        assert!(recorded.contains("read -r secret"));
+        assert!(!recorded.contains("hunter2"));
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::preferences::PreferencesState;
+
+// The release feed's latest entry. `download_url` is accepted for `url`, and versions may be
+// written with a leading `v`.
+#[derive(Debug, Deserialize)]
+struct ReleaseFeed {
+    version: String,
+    #[serde(default, alias = "download_url")]
+    url: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+/// Whether the release feed has a newer build of the app than the one running
+#[derive(Debug, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    pub download_url: Option<String>,
+    pub notes: Option<String>,
+}
+
+fn parse_version(version: &str) -> Result<Version, String> {
+    let trimmed = version.trim();
+    Version::parse(trimmed.strip_prefix('v').unwrap_or(trimmed))
+        .map_err(|e| format!("Invalid version {:?}: {}", version, e))
+}
+
+fn update_info(current: &str, feed: ReleaseFeed) -> Result<UpdateInfo, String> {
+    let update_available = parse_version(&feed.version)? > parse_version(current)?;
+    Ok(UpdateInfo {
+        current_version: current.to_string(),
+        latest_version: feed.version,
+        update_available,
+        download_url: feed.url,
+        notes: feed.notes,
+    })
+}
+
+// Checks a JSON release feed for a newer version of the app itself (tool versions are reported
+// by warm_up_tools). Uses the shared HTTP client, so the proxy environment and the
+// `http_timeout_secs` preference apply.
+#[tauri::command]
+pub async fn check_for_update(
+    prefs: tauri::State<'_, PreferencesState>,
+    feed_url: String,
+) -> Result<UpdateInfo, String> {
+    let client = crate::http::build_client(Duration::from_secs(prefs.get().http_timeout_secs), false)?;
+    let response = client
+        .get(&feed_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch release feed: {}", e))?;
+    let feed: ReleaseFeed = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid release feed: {}", e))?;
+    update_info(env!("CARGO_PKG_VERSION"), feed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(json: &str) -> ReleaseFeed {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn newer_feed_version_is_an_update() {
+        let info = update_info(
+            "0.1.0",
+            feed(r#"{ "version": "v0.2.0", "download_url": "https://example.com/app.dmg", "notes": "Fixes" }"#),
+        )
+        .unwrap();
+        assert!(info.update_available);
+        assert_eq!(info.download_url.as_deref(), Some("https://example.com/app.dmg"));
+        assert_eq!(info.notes.as_deref(), Some("Fixes"));
+    }
+
+    #[test]
+    fn older_or_equal_feed_version_is_not_an_update() {
+        assert!(!update_info("0.2.0", feed(r#"{ "version": "0.1.9" }"#)).unwrap().update_available);
+        assert!(!update_info("0.2.0", feed(r#"{ "version": "0.2.0" }"#)).unwrap().update_available);
+        assert!(update_info("0.2.0", feed(r#"{ "version": "latest" }"#)).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::preferences::PreferencesState;
+
+/// First line of each tool's `--version` output, filled in by `warm_up_tools`. Kept in managed state.
+#[derive(Default)]
+pub struct ToolVersions {
+    versions: Mutex<HashMap<String, String>>,
+    warming: AtomicBool,
+}
+
+impl ToolVersions {
+    pub fn get(&self, tool: &str) -> Option<String> {
+        self.versions.lock().unwrap().get(tool).cloned()
+    }
+
+    pub fn all(&self) -> HashMap<String, String> {
+        self.versions.lock().unwrap().clone()
+    }
+}
+
+// Some tools print their version to stderr, so fall back to it when stdout is empty
+pub(crate) async fn version_of(path: &str) -> Option<String> {
+    let output = tokio::process::Command::new(path)
+        .arg("--version")
+        .envs(crate::azure_tool_env())
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let text = if stdout.trim().is_empty() { stderr } else { stdout };
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+// Runs `--version` for each (name, path) pair, caching what they report
+async fn warm_up(versions: &ToolVersions, tools: &[(String, String)]) {
+    for (name, path) in tools {
+        if let Some(version) = version_of(path).await {
+            versions.versions.lock().unwrap().insert(name.clone(), version);
+        }
+    }
+}
+
+// Pays each tool's cold-start cost in the background so the first real action is quicker,
+// then emits `warmup-done` with the cached versions. Returns false without starting another
+// pass if one is already running.
+#[tauri::command]
+pub fn warm_up_tools(app: AppHandle, versions: tauri::State<'_, ToolVersions>) -> bool {
+    if versions
+        .warming
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return false;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let prefs = app.state::<PreferencesState>().get();
+        let mut tools = Vec::new();
+        for tool in crate::KNOWN_TOOLS {
+            let located = crate::locate_tool(tool.to_string(), &prefs).await;
+            if let Ok(crate::ToolInfo { path: Some(path), .. }) = located {
+                tools.push((tool.to_string(), path));
+            }
+        }
+
+        let versions = app.state::<ToolVersions>();
+        warm_up(&versions, &tools).await;
+        // A cheap authenticated call primes az's token and config caches
+        if let Some((_, az_path)) = tools.iter().find(|(name, _)| name == "az") {
+            let _ = tokio::process::Command::new(az_path)
+                .args(["account", "show"])
+                .envs(crate::azure_tool_env())
+                .output()
+                .await;
+        }
+
+        versions.warming.store(false, Ordering::SeqCst);
+        let _ = app.emit("warmup-done", versions.all());
+    });
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn warm_up_completes_and_caches_versions() {
+        let versions = ToolVersions::default();
+        // `echo` stands in for a tool: it exits straight away and prints its argument
+        warm_up(&versions, &[("echo".to_string(), "echo".to_string())]).await;
+        assert!(versions.get("echo").is_some());
+        assert_eq!(versions.get("missing"), None);
+    }
+}
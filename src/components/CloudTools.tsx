@@ -6,6 +6,7 @@ interface CommandOutput {
   stdout: string
   stderr: string
   success: boolean
+  no_results?: boolean
 }
 
 interface ToolInfo {
@@ -157,7 +158,10 @@ export function CloudTools({ onDebugInfo }: CloudToolsProps) {
       const args = searchQuery.trim().split(/\s+/).filter(arg => arg)
       const result = await invoke<CommandOutput>('run_azure_resource_finder', { args })
       
-      if (result.success && result.stdout) {
+      if (result.no_results) {
+        setSearchResults('No resources found')
+        onDebugInfo?.('Azure search completed with no results')
+      } else if (result.success && result.stdout) {
         setSearchResults(result.stdout)
         onDebugInfo?.('Azure search completed successfully')
       } else if (result.stderr) {
@@ -239,9 +243,11 @@ export function CloudTools({ onDebugInfo }: CloudToolsProps) {
         setRuchyOutput(prev => prev + '\nError: ' + result.stderr)
         onDebugInfo?.(`Ruchy error: ${result.stderr}`)
       }
-    } catch (error) {
-      setRuchyOutput(prev => prev + `\nError: ${error}`)
-      onDebugInfo?.(`Ruchy execution failed: ${error}`)
+    } catch (error: any) {
+      // Timeouts and resource limits arrive as { kind, message }
+      const message = error?.message ?? error
+      setRuchyOutput(prev => prev + `\nError: ${message}`)
+      onDebugInfo?.(`Ruchy execution failed: ${message}`)
     } finally {
       setIsLoading(false)
     }
@@ -440,8 +440,11 @@ export class KagentAPI {
         const data = await response.json()
         return data
       }
-    } catch (error) {
-      throw new Error(`Network error: ${error instanceof Error ? error.message : String(error)}`)
+    } catch (error: any) {
+      // Tauri commands reject with { kind, message }
+      const message = error instanceof Error ? error.message : error?.message ?? String(error)
+      const kind = error instanceof Error || !error?.kind ? '' : ` (${error.kind})`
+      throw new Error(`Network error${kind}: ${message}`)
     }
   }
 